@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE `journal`(
+	`id` INTEGER NOT NULL PRIMARY KEY,
+	`vcmp_kind` TEXT NOT NULL,
+	`vcmp_id` INTEGER NOT NULL,
+	`before` TEXT,
+	`after` TEXT,
+	`undone` BOOL NOT NULL DEFAULT false,
+	`created_at` TEXT NOT NULL
+);
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX uid_todos;
+CREATE UNIQUE INDEX uid_todos ON vtodos(uid);
+DROP INDEX uid_event;
+CREATE UNIQUE INDEX uid_event ON vevents(uid);
+ALTER TABLE `vevents` DROP COLUMN `deleted_at`;
+ALTER TABLE `vtodos` DROP COLUMN `deleted_at`;
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+ALTER TABLE `vevents` ADD COLUMN `deleted_at` TEXT;
+ALTER TABLE `vtodos` ADD COLUMN `deleted_at` TEXT;
+-- A component moved to another calendar keeps its uid while the delete of the old
+-- resource waits for the next sync
+DROP INDEX uid_event;
+CREATE UNIQUE INDEX uid_event ON vevents(uid) WHERE deleted_at IS NULL;
+DROP INDEX uid_todos;
+CREATE UNIQUE INDEX uid_todos ON vtodos(uid) WHERE deleted_at IS NULL;
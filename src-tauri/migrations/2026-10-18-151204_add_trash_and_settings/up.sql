@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TABLE `trash`(
+	`id` INTEGER NOT NULL PRIMARY KEY,
+	`vcmp_kind` TEXT NOT NULL,
+	`vcmp_id` INTEGER NOT NULL,
+	`calendar_id` INTEGER NOT NULL,
+	`summary` TEXT NOT NULL,
+	`snapshot` TEXT NOT NULL,
+	`ical_data` TEXT NOT NULL,
+	`deleted_at` TEXT NOT NULL
+);
+
+CREATE TABLE `settings`(
+	`key` TEXT NOT NULL PRIMARY KEY,
+	`value` TEXT NOT NULL
+);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX `vtodos_parent_uid`;
+ALTER TABLE `vtodos` DROP COLUMN `parent_uid`;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE `vtodos` ADD COLUMN `parent_uid` TEXT;
+CREATE INDEX `vtodos_parent_uid` ON `vtodos`(`parent_uid`);
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE `vtodos` ADD COLUMN `created_at` TEXT;
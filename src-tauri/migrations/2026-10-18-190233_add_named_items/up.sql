@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE `named_items`(
+	`kind` TEXT NOT NULL,
+	`name` TEXT NOT NULL,
+	`value` TEXT NOT NULL,
+	PRIMARY KEY(`kind`, `name`)
+);
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE `todo_occurrences`(
+	`vcmp_uid` TEXT NOT NULL,
+	`occurrence_start` TEXT NOT NULL,
+	`status` TEXT NOT NULL,
+	`completed` TEXT,
+	PRIMARY KEY(`vcmp_uid`, `occurrence_start`)
+);
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE `journal` ADD COLUMN `occurrence_start` TEXT;
@@ -1,7 +1,10 @@
 use std::fmt::Display;
 
 use crate::{
-    caldav::get_sync_report::{GetSyncReport, GetSyncReportResponse},
+    caldav::{
+        get_sync_report::{GetSyncReport, GetSyncReportResponse},
+        update_resource::{UpdateResource, UpdateResourceResponse},
+    },
     models::{NewCalendar, server::Server},
     util::{Etag, Href, SyncToken},
 };
@@ -25,6 +28,7 @@ use libdav::{
 use tower_http::auth::AddAuthorization;
 
 pub mod get_sync_report;
+pub mod update_resource;
 pub mod util;
 
 pub type HyperAuthClient =
@@ -183,18 +187,19 @@ impl Caldav {
         Ok((href_str.into(), etag.map(Etag)))
     }
 
+    /// Replaces the resource if `etag` is still the latest one, see
+    /// [`util::is_precondition_failed`] for the error when it changed on the server
     pub async fn update_component(
         &self,
         cmp_href: &Href,
         etag: &Etag,
         calendar: &icalendar::Calendar,
     ) -> anyhow::Result<Option<Etag>> {
-        let body = calendar.to_string();
-        let href = cmp_href.to_string();
-        let create_req = PutResource::new(&href).update(body, mime_types::CALENDAR, etag);
-        let PutResourceResponse { etag } = self.caldav_client.request(create_req).await?;
-
-        Ok(etag.map(Etag))
+        let UpdateResourceResponse { etag } = self
+            .caldav_client
+            .request(UpdateResource::new(cmp_href, etag, calendar))
+            .await?;
+        Ok(etag)
     }
 
     pub async fn fetch_resource(
@@ -0,0 +1,66 @@
+use http::{Method, header};
+use libdav::requests::{DavRequest, PreparedRequest};
+
+use crate::{
+    caldav::util::check_status,
+    util::{Etag, Href},
+};
+
+/// Replaces a resource only if it still has the etag it was last fetched with, otherwise
+/// the server answers `412 Precondition Failed` and nothing is written
+pub struct UpdateResource<'a> {
+    href: &'a Href,
+    etag: &'a Etag,
+    body: String,
+}
+
+/// Response from an `UpdateResource` request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateResourceResponse {
+    /// Etag of the new content, if returned by the server.
+    pub etag: Option<Etag>,
+}
+
+impl<'a> UpdateResource<'a> {
+    #[must_use]
+    pub fn new(href: &'a Href, etag: &'a Etag, calendar: &icalendar::Calendar) -> Self {
+        Self {
+            href,
+            etag,
+            body: calendar.to_string(),
+        }
+    }
+}
+
+impl DavRequest for UpdateResource<'_> {
+    type Response = UpdateResourceResponse;
+    type ParseError = anyhow::Error;
+    type Error<E> = anyhow::Error;
+
+    fn prepare_request(&self) -> Result<PreparedRequest, http::Error> {
+        Ok(PreparedRequest {
+            method: Method::PUT,
+            path: self.href.to_string(),
+            body: self.body.clone(),
+            headers: vec![
+                ("Content-Type".into(), "text/calendar; charset=utf-8".into()),
+                ("If-Match".into(), self.etag.to_string()),
+            ],
+        })
+    }
+
+    fn parse_response(
+        &self,
+        parts: &http::response::Parts,
+        _body: &[u8],
+    ) -> Result<Self::Response, anyhow::Error> {
+        check_status(parts.status)?;
+
+        let etag = parts
+            .headers
+            .get(header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(|etag| Etag(etag.to_string()));
+        Ok(UpdateResourceResponse { etag })
+    }
+}
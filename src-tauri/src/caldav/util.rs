@@ -15,6 +15,14 @@ pub fn get_node_by_name<'a, 'b>(
 ) -> Option<roxmltree::Node<'a, 'b>> {
     node.descendants().find(|node| node.tag_name() == prop)
 }
+/// Whether the request was rejected because the etag it was sent with isn't the latest one
+pub fn is_precondition_failed(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<CaldavError>(),
+        Some(CaldavError::ErrorResponse(StatusCode::PRECONDITION_FAILED))
+    )
+}
+
 /// Checks if the status code is success. If it is not, return it as an error.
 #[inline]
 pub fn check_status(status: StatusCode) -> Result<(), CaldavError> {
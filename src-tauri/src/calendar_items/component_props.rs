@@ -4,7 +4,11 @@ use std::str::FromStr;
 use chrono::{DateTime, Utc};
 use icalendar::{Component, TodoStatus};
 
-use super::{event_status::EventStatus, event_type::EventType};
+use super::{
+    event_status::EventStatus,
+    event_type::EventType,
+    priority::{NO_PRIORITY, from_priority, to_priority},
+};
 
 #[derive(Debug, PartialEq, strum_macros::AsRefStr)]
 pub enum ComponentProps {
@@ -34,8 +38,18 @@ pub enum ComponentProps {
     Exdate,
     #[strum(serialize = "DURATION")]
     Duration,
+    #[strum(serialize = "RELATED-TO")]
+    RelatedTo,
+    #[strum(serialize = "PRIORITY")]
+    Priority,
+    #[strum(serialize = "TRANSP")]
+    Transp,
 }
 
+/// Parameter of `RELATED-TO` with the kind of relationship
+const RELTYPE_PARAM: &str = "RELTYPE";
+const RELTYPE_PARENT: &str = "PARENT";
+
 impl From<ComponentProps> for String {
     fn from(value: ComponentProps) -> Self {
         value.as_ref().to_string()
@@ -72,6 +86,129 @@ pub fn get_string_property<Cmp: icalendar::Component>(
         .map(|e| e.to_string())
 }
 
+/// Gets every value for a property that can be repeated, like `CATEGORIES`
+pub fn get_all_string_properties<Cmp: icalendar::Component>(
+    event: &Cmp,
+    property: ComponentProps,
+) -> Vec<String> {
+    let key = property.as_ref();
+    let single = event.property_value(key).map(|v| v.to_string());
+    let multi = event
+        .multi_properties()
+        .get(key)
+        .into_iter()
+        .flatten()
+        .map(|p| p.value().to_string());
+    single.into_iter().chain(multi).collect()
+}
+
+/// Normalizes a single tag so it can be written back as `#tag` in the user input
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag
+        .trim()
+        .trim_start_matches('#')
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join("_")
+        .to_lowercase();
+    if tag.is_empty() { None } else { Some(tag) }
+}
+
+/// Merges the `X-TAG` and the `CATEGORIES` of a component into a single tag list
+///
+/// Tags are lowercased and deduplicated, the ones from `X-TAG` go first, followed by
+/// the categories that were not already present, keeping the order of the component.
+/// This way an item tagged in another client keeps the mimido tags and gains the new ones.
+pub fn merge_tags(tag: Option<String>, categories: Vec<String>) -> Option<String> {
+    let mut tags: Vec<String> = Vec::new();
+    tag.into_iter()
+        .chain(categories)
+        .flat_map(|value| {
+            value
+                .replace("\\,", ",")
+                .split(',')
+                .filter_map(normalize_tag)
+                .collect::<Vec<String>>()
+        })
+        .for_each(|t| {
+            if !tags.contains(&t) {
+                tags.push(t);
+            }
+        });
+
+    if tags.is_empty() {
+        None
+    } else {
+        Some(tags.join(","))
+    }
+}
+
+/// Writes the tags as `X-TAG` and as the standard `CATEGORIES`, so other clients can read them
+pub fn add_tag_properties<Cmp: icalendar::Component>(cmp: &mut Cmp, tag: &str) {
+    if let Some(tag) = merge_tags(Some(tag.to_string()), vec![]) {
+        cmp.add_property(ComponentProps::Tag, &tag);
+        cmp.add_property(ComponentProps::Categories, &tag);
+    }
+}
+
+fn get_tags<Cmp: icalendar::Component>(cmp: &Cmp) -> Option<String> {
+    merge_tags(
+        get_string_property(cmp, ComponentProps::Tag),
+        get_all_string_properties(cmp, ComponentProps::Categories),
+    )
+}
+
+/// Gets the uid of the parent from `RELATED-TO`, a missing `RELTYPE` means `PARENT`
+pub fn get_parent_uid<Cmp: icalendar::Component>(cmp: &Cmp) -> Option<String> {
+    let key = ComponentProps::RelatedTo.as_ref();
+    let single = cmp.properties().get(key);
+    let multi = cmp.multi_properties().get(key).into_iter().flatten();
+    single
+        .into_iter()
+        .chain(multi)
+        .find(|p| {
+            p.params()
+                .get(RELTYPE_PARAM)
+                .is_none_or(|reltype| reltype.value().eq_ignore_ascii_case(RELTYPE_PARENT))
+        })
+        .map(|p| p.value().trim().to_string())
+        .filter(|uid| !uid.is_empty())
+}
+
+/// Writes `RELATED-TO;RELTYPE=PARENT`, the way other clients store subtasks
+pub fn add_parent_property<Cmp: icalendar::Component>(cmp: &mut Cmp, parent_uid: &str) {
+    cmp.append_property(
+        icalendar::Property::new(ComponentProps::RelatedTo.as_ref(), parent_uid)
+            .add_parameter(RELTYPE_PARAM, RELTYPE_PARENT)
+            .done(),
+    );
+}
+
+/// Gets the urgency and importance, taking them from `PRIORITY` when it doesn't match
+/// `X-URGENCY` and `X-IMPORTANCE`, which means it was set or changed in another client
+fn get_urgency_and_importance<Cmp: icalendar::Component>(cmp: &Cmp) -> (i32, i32) {
+    let urgency = get_int_property(cmp, ComponentProps::Urgency);
+    let importance = get_int_property(cmp, ComponentProps::Importance);
+    match get_property::<_, u8>(cmp, ComponentProps::Priority) {
+        Some(priority) if priority != to_priority(urgency, importance) => {
+            from_priority(priority).unwrap_or((urgency, importance))
+        }
+        _ => (urgency, importance),
+    }
+}
+
+/// Writes the standard `PRIORITY` matching the urgency and importance, if there is one
+pub fn add_priority_property<Cmp: icalendar::Component>(
+    cmp: &mut Cmp,
+    urgency: i32,
+    importance: i32,
+) {
+    let priority = to_priority(urgency, importance);
+    if priority != NO_PRIORITY {
+        cmp.add_property(ComponentProps::Priority, priority.to_string());
+    }
+}
+
 pub fn get_int_property<Cmp: icalendar::Component>(event: &Cmp, property: ComponentProps) -> i32 {
     event
         .property_value(property.as_ref())
@@ -94,6 +231,8 @@ pub struct GeneralComponentProps {
     pub load: i32,
     pub postponed: i32,
     pub last_modified: DateTime<Utc>,
+    pub parent_uid: Option<String>,
+    pub created: Option<DateTime<Utc>>,
 }
 
 impl TryFrom<&icalendar::Event> for GeneralComponentProps {
@@ -111,14 +250,15 @@ impl TryFrom<&icalendar::Event> for GeneralComponentProps {
         let last_modified = first_event.get_last_modified().unwrap_or(Utc::now());
         let event_type =
             get_property_or_default(first_event, ComponentProps::Type, EventType::Event);
-        let tag = get_string_property(first_event, ComponentProps::Tag);
+        let tag = get_tags(first_event);
         let status =
             get_property_or_default(first_event, ComponentProps::XStatus, EventStatus::Todo);
         let original_text = get_string_property(first_event, ComponentProps::OriginalText);
-        let importance = get_int_property(first_event, ComponentProps::Importance);
-        let urgency = get_int_property(first_event, ComponentProps::Urgency);
+        let (urgency, importance) = get_urgency_and_importance(first_event);
         let load = get_int_property(first_event, ComponentProps::Load);
         let postponed = get_int_property(first_event, ComponentProps::Postponed);
+        let parent_uid = get_parent_uid(first_event);
+        let created = first_event.get_created();
 
         Ok(GeneralComponentProps {
             uid,
@@ -133,6 +273,8 @@ impl TryFrom<&icalendar::Event> for GeneralComponentProps {
             load,
             postponed,
             last_modified,
+            parent_uid,
+            created,
         })
     }
 }
@@ -151,7 +293,7 @@ impl TryFrom<&icalendar::Todo> for GeneralComponentProps {
         let description = first_todo.get_description().map(|d| d.to_string());
         let last_modified = first_todo.get_last_modified().unwrap_or(Utc::now());
         let event_type = get_property_or_default(first_todo, ComponentProps::Type, EventType::Task);
-        let tag = get_string_property(first_todo, ComponentProps::Tag);
+        let tag = get_tags(first_todo);
         let todo_status_completed = first_todo
             .get_status()
             .map(|s| matches!(s, TodoStatus::Completed))
@@ -166,10 +308,11 @@ impl TryFrom<&icalendar::Todo> for GeneralComponentProps {
         });
 
         let original_text = get_string_property(first_todo, ComponentProps::OriginalText);
-        let importance = get_int_property(first_todo, ComponentProps::Importance);
-        let urgency = get_int_property(first_todo, ComponentProps::Urgency);
+        let (urgency, importance) = get_urgency_and_importance(first_todo);
         let load = get_int_property(first_todo, ComponentProps::Load);
         let postponed = get_int_property(first_todo, ComponentProps::Postponed);
+        let parent_uid = get_parent_uid(first_todo);
+        let created = first_todo.get_created();
 
         Ok(GeneralComponentProps {
             uid,
@@ -184,6 +327,155 @@ impl TryFrom<&icalendar::Todo> for GeneralComponentProps {
             load,
             postponed,
             last_modified,
+            parent_uid,
+            created,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo_with(props: &[(ComponentProps, &str)]) -> icalendar::Todo {
+        let mut todo = icalendar::Todo::new().uid("uid").done();
+        for (prop, value) in props {
+            todo.add_property(prop.as_ref(), *value);
+        }
+        todo
+    }
+
+    #[test]
+    fn test_priority_from_other_client() {
+        let todo = todo_with(&[(ComponentProps::Priority, "1")]);
+        let props = GeneralComponentProps::try_from(&todo).unwrap();
+        assert_eq!((props.urgency, props.importance), (3, 3));
+    }
+
+    #[test]
+    fn test_priority_matching_keeps_urgency_and_importance() {
+        // 0 urgency and 3 importance is exported as 4, which maps to (2, 1)
+        let todo = todo_with(&[
+            (ComponentProps::Urgency, "0"),
+            (ComponentProps::Importance, "3"),
+            (ComponentProps::Priority, "4"),
+        ]);
+        let props = GeneralComponentProps::try_from(&todo).unwrap();
+        assert_eq!((props.urgency, props.importance), (0, 3));
+    }
+
+    #[test]
+    fn test_priority_changed_in_other_client() {
+        let todo = todo_with(&[
+            (ComponentProps::Urgency, "0"),
+            (ComponentProps::Importance, "3"),
+            (ComponentProps::Priority, "9"),
+        ]);
+        let props = GeneralComponentProps::try_from(&todo).unwrap();
+        assert_eq!((props.urgency, props.importance), (0, -3));
+    }
+
+    #[test]
+    fn test_undefined_priority_keeps_urgency_and_importance() {
+        let todo = todo_with(&[
+            (ComponentProps::Urgency, "2"),
+            (ComponentProps::Priority, "0"),
+        ]);
+        let props = GeneralComponentProps::try_from(&todo).unwrap();
+        assert_eq!((props.urgency, props.importance), (2, 0));
+    }
+
+    #[test]
+    fn test_add_priority_property() {
+        let mut todo = icalendar::Todo::new();
+        add_priority_property(&mut todo, 0, 0);
+        assert_eq!(todo.property_value("PRIORITY"), None);
+        add_priority_property(&mut todo, 3, 2);
+        assert_eq!(todo.property_value("PRIORITY"), Some("2"));
+    }
+
+    #[test]
+    fn test_merge_tags_only_x_tag() {
+        assert_eq!(
+            merge_tags(Some("health,work".to_string()), vec![]),
+            Some("health,work".to_string())
+        );
+    }
+
+    #[test]
+    fn test_merge_tags_only_categories() {
+        assert_eq!(
+            merge_tags(None, vec!["Work, Deep Focus".to_string()]),
+            Some("work,deep_focus".to_string())
+        );
+    }
+
+    #[test]
+    fn test_merge_tags_is_deterministic() {
+        let merged = merge_tags(
+            Some("health".to_string()),
+            vec!["HEALTH,errands".to_string(), "home".to_string()],
+        );
+        assert_eq!(merged, Some("health,errands,home".to_string()));
+    }
+
+    #[test]
+    fn test_merge_tags_empty() {
+        assert_eq!(
+            merge_tags(Some(" ".to_string()), vec![",".to_string()]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_writes_tag_and_categories() {
+        let mut event = icalendar::Event::new();
+        add_tag_properties(&mut event, "Health,work");
+        assert_eq!(
+            event.property_value(ComponentProps::Tag.as_ref()),
+            Some("health,work")
+        );
+        assert_eq!(
+            event.property_value(ComponentProps::Categories.as_ref()),
+            Some("health,work")
+        );
+    }
+
+    #[test]
+    fn test_reads_categories_from_todo() {
+        let todo = icalendar::Todo::new()
+            .uid("uid")
+            .add_property(ComponentProps::Tag, "health")
+            .add_property(ComponentProps::Categories, "Errands,health")
+            .done();
+        let props = GeneralComponentProps::try_from(&todo).unwrap();
+        assert_eq!(props.tag, Some("health,errands".to_string()));
+    }
+
+    #[test]
+    fn test_reads_parent_uid() {
+        let todo = icalendar::Todo::new()
+            .uid("child")
+            .append_property(
+                icalendar::Property::new(ComponentProps::RelatedTo.as_ref(), "sibling")
+                    .add_parameter(RELTYPE_PARAM, "SIBLING")
+                    .done(),
+            )
+            .done();
+        assert_eq!(get_parent_uid(&todo), None);
+
+        let mut todo = icalendar::Todo::new().uid("child").done();
+        add_parent_property(&mut todo, "parent");
+        let props = GeneralComponentProps::try_from(&todo).unwrap();
+        assert_eq!(props.parent_uid, Some("parent".to_string()));
+    }
+
+    #[test]
+    fn test_reltype_defaults_to_parent() {
+        let todo = icalendar::Todo::new()
+            .uid("child")
+            .add_property(ComponentProps::RelatedTo, "parent")
+            .done();
+        assert_eq!(get_parent_uid(&todo), Some("parent".to_string()));
+    }
+}
@@ -15,9 +15,18 @@ enum DateExpressionCases {
     RelativeTime,
 }
 
-#[derive(Copy, Clone, Debug, strum_macros::EnumString)]
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    specta::Type,
+    strum_macros::EnumString,
+)]
 #[strum(serialize_all = "lowercase")]
-enum NamedTime {
+pub enum NamedTime {
     Morning,
     Afternoon,
     Evening,
@@ -26,6 +35,20 @@ enum NamedTime {
     Midnight,
 }
 
+impl NamedTime {
+    /// Hours of the day, start and end, that count as this time
+    pub fn period_hours(&self) -> (u32, u32) {
+        match self {
+            NamedTime::Morning => (6, 12),
+            NamedTime::Afternoon => (12, 18),
+            NamedTime::Evening => (18, 22),
+            NamedTime::Night => (22, 24),
+            NamedTime::Noon => (11, 14),
+            NamedTime::Midnight => (0, 2),
+        }
+    }
+}
+
 const WEEKDAYS: &[&str] = &[
     "Monday",
     "Tuesday",
@@ -46,7 +69,8 @@ const WEEKDAYS: &[&str] = &[
 const TIME_RE: &str = r"at +(?P<time>\d{1,2}(?::\d{2})?)";
 const FROM_TO_RE: &str =
     r"(at +)?(?P<start>\d{1,2}(?::\d{2})?) *(:?-|to|until) *(?P<end>\d{1,2}(?::\d{2})?)";
-const NAMED_TIME_RE: &str = r"(at +)?(?P<time>morning|noon|afternoon|night|evening|midnight)";
+pub(super) const NAMED_TIME_RE: &str =
+    r"(at +)?(?P<time>morning|noon|afternoon|night|evening|midnight)";
 
 impl From<DateExpressionCases> for Regex {
     fn from(value: DateExpressionCases) -> Self {
@@ -165,7 +189,7 @@ fn calculate_date<Tz: TimeZone>(
     Some((start, end))
 }
 
-fn remove_matched(input: &str, matched: Match<'_>) -> String {
+pub(super) fn remove_matched(input: &str, matched: Match<'_>) -> String {
     format!("{} {}", &input[0..matched.start()], &input[matched.end()..])
         .trim()
         .to_string()
@@ -1,17 +1,20 @@
-use chrono::{DateTime, TimeDelta, TimeZone};
+use chrono::{DateTime, TimeZone};
 
 mod date_parser;
 mod rrule_parser;
+mod slot_query;
 
 use self::date_parser::extract_start_end;
+pub(crate) use self::date_parser::start_end_to_natural;
 use crate::calendar_items::{
-    event_date::date_parser::start_end_to_natural,
     event_type::EventType,
     input_traits::{ExtractedInput, FromUserInput, ToUserInput},
 };
 
 // Re-export
+pub use self::date_parser::NamedTime;
 pub use self::rrule_parser::EventRecurrence;
+pub use self::slot_query::SlotQuery;
 
 #[derive(Clone)]
 pub struct EventDateInfo<Tz: TimeZone> {
@@ -41,13 +44,7 @@ impl<Tz: TimeZone> EventDateInfo<Tz> {
         if let Some(end) = &self.end {
             end.clone()
         } else {
-            let duration = match event_type {
-                EventType::Event => TimeDelta::hours(1),
-                EventType::Block => TimeDelta::hours(1),
-                EventType::Reminder => TimeDelta::minutes(15),
-                EventType::Task => TimeDelta::minutes(30),
-            };
-            self.start.clone() + duration
+            self.start.clone() + event_type.default_duration()
         }
     }
 }
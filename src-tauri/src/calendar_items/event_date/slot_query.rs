@@ -0,0 +1,137 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Days, TimeDelta, TimeZone};
+use now::DateTimeNow;
+use regex::{Regex, RegexBuilder};
+
+use super::date_parser::{NAMED_TIME_RE, NamedTime, remove_matched};
+
+const SLOT_QUERY_RE: &str = r"(?P<number>\d+) *(?P<unit>hours?|h|minutes?|mins?|m) +sometime +(?P<window>today|tomorrow|this week|next week)";
+
+/// Request for a free slot written in the input, like `1h sometime next week`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SlotQuery<Tz: TimeZone> {
+    pub duration: TimeDelta,
+    pub from: DateTime<Tz>,
+    pub to: DateTime<Tz>,
+    pub time_of_day: Option<NamedTime>,
+}
+
+impl<Tz: TimeZone> SlotQuery<Tz> {
+    /// Query found in `input` and the input without it
+    pub fn extract_from_input(date_of_input: DateTime<Tz>, input: &str) -> Option<(Self, String)> {
+        let re = RegexBuilder::new(SLOT_QUERY_RE)
+            .case_insensitive(true)
+            .build()
+            .expect("Regex to compile");
+        let caps = re.captures(input)?;
+        let number = caps.name("number")?.as_str().parse::<i64>().ok()?;
+        let duration = match caps.name("unit")?.as_str().to_lowercase().as_str() {
+            "h" | "hour" | "hours" => TimeDelta::hours(number),
+            _ => TimeDelta::minutes(number),
+        };
+        let window = caps.name("window")?.as_str().to_lowercase();
+        let (from, to) = window_for(&window, date_of_input)?;
+        let stripped = remove_matched(input, caps.get(0)?);
+
+        let time_re = Regex::new(NAMED_TIME_RE).expect("To Compile Regex");
+        let (time_of_day, stripped) = match time_re.captures(&stripped) {
+            Some(caps) => {
+                let time_of_day = NamedTime::from_str(caps.name("time")?.as_str()).ok()?;
+                (Some(time_of_day), remove_matched(&stripped, caps.get(0)?))
+            }
+            None => (None, stripped),
+        };
+
+        Some((
+            Self {
+                duration,
+                from,
+                to,
+                time_of_day,
+            },
+            stripped,
+        ))
+    }
+}
+
+/// Start and end of a named window, nothing before `date_of_input` is included
+fn window_for<Tz: TimeZone>(
+    window: &str,
+    date_of_input: DateTime<Tz>,
+) -> Option<(DateTime<Tz>, DateTime<Tz>)> {
+    let days_to_week_end = u64::from(6 - date_of_input.weekday().num_days_from_monday());
+    match window {
+        "today" => Some((date_of_input.clone(), date_of_input.end_of_day())),
+        "tomorrow" => {
+            let tomorrow = date_of_input.checked_add_days(Days::new(1))?;
+            Some((tomorrow.beginning_of_day(), tomorrow.end_of_day()))
+        }
+        "this week" => {
+            let week_end = date_of_input
+                .clone()
+                .checked_add_days(Days::new(days_to_week_end))?;
+            Some((date_of_input, week_end.end_of_day()))
+        }
+        "next week" => {
+            let monday = date_of_input.checked_add_days(Days::new(days_to_week_end + 1))?;
+            let sunday = monday.clone().checked_add_days(Days::new(6))?;
+            Some((monday.beginning_of_day(), sunday.end_of_day()))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wednesday 2024-06-05 at 10:00
+    fn date_of_input() -> DateTime<chrono_tz::Tz> {
+        chrono_tz::Tz::UTC
+            .with_ymd_and_hms(2024, 6, 5, 10, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_next_week_query() {
+        let (query, stripped) =
+            SlotQuery::extract_from_input(date_of_input(), "Dentist #health 1h sometime next week")
+                .unwrap();
+        assert_eq!(stripped, "Dentist #health");
+        assert_eq!(query.duration, TimeDelta::hours(1));
+        assert_eq!(
+            query.from,
+            chrono_tz::Tz::UTC
+                .with_ymd_and_hms(2024, 6, 10, 0, 0, 0)
+                .unwrap()
+        );
+        assert_eq!(
+            query.to.date_naive(),
+            query.from.date_naive() + Days::new(6)
+        );
+        assert_eq!(query.time_of_day, None);
+    }
+
+    #[test]
+    fn test_query_with_time_of_day() {
+        let (query, stripped) = SlotQuery::extract_from_input(
+            date_of_input(),
+            "Call 30 min sometime this week afternoon",
+        )
+        .unwrap();
+        assert_eq!(stripped, "Call");
+        assert_eq!(query.duration, TimeDelta::minutes(30));
+        assert_eq!(query.from, date_of_input());
+        assert_eq!(
+            query.to.date_naive(),
+            date_of_input().date_naive() + Days::new(4)
+        );
+        assert_eq!(query.time_of_day, Some(NamedTime::Afternoon));
+    }
+
+    #[test]
+    fn test_no_query() {
+        assert!(SlotQuery::extract_from_input(date_of_input(), "Call tomorrow at 10").is_none());
+    }
+}
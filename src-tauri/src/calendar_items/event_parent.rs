@@ -0,0 +1,81 @@
+use chrono::{DateTime, TimeZone};
+use regex::Regex;
+
+use crate::calendar_items::input_traits::ExtractedInput;
+
+use super::input_traits::{FromUserInput, ToUserInput};
+
+/// Id of the todo a new todo is a subtask of, written as `^id` in the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventParent(pub Option<i32>);
+
+const EVENT_PARENT_RE: &str = r"(?:^|\s)\^(?P<parent_id>\d+)\b";
+
+impl<Tz: TimeZone> FromUserInput<Tz> for EventParent {
+    fn extract_from_input(
+        _: DateTime<Tz>,
+        input: &str,
+    ) -> anyhow::Result<impl Into<ExtractedInput<Self>>> {
+        let re = Regex::new(EVENT_PARENT_RE)?;
+
+        let Some(captured) = re.captures(input) else {
+            return Ok((EventParent(None), input.to_string()));
+        };
+        let whole_cap = captured.get(0).expect("Already check if it's some");
+        let parent_id = captured
+            .name("parent_id")
+            .expect("Already check if it's some")
+            .as_str()
+            .parse::<i32>()?;
+
+        let stripped = format!(
+            "{} {}",
+            &input[0..whole_cap.start()],
+            &input[whole_cap.end()..]
+        )
+        .trim()
+        .to_string();
+        Ok((EventParent(Some(parent_id)), stripped))
+    }
+}
+
+impl<Tz: TimeZone> ToUserInput<Tz> for EventParent {
+    fn to_input(&self, _: &DateTime<Tz>) -> String {
+        match self.0 {
+            Some(id) => format!("^{id}"),
+            None => "".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn reference_date() -> DateTime<chrono_tz::Tz> {
+        chrono_tz::America::Argentina::Buenos_Aires
+            .with_ymd_and_hms(2025, 10, 10, 10, 30, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parent() {
+        let ExtractedInput(parent, stripped) =
+            EventParent::extract_from_input(reference_date(), "^12 buy the paint #home")
+                .unwrap()
+                .into();
+        assert_eq!(parent, EventParent(Some(12)));
+        assert_eq!(stripped, "buy the paint #home");
+    }
+
+    #[test]
+    fn test_no_parent() {
+        let ExtractedInput(parent, stripped) =
+            EventParent::extract_from_input(reference_date(), "raise 2^10 by hand")
+                .unwrap()
+                .into();
+        assert_eq!(parent, EventParent(None));
+        assert_eq!(stripped, "raise 2^10 by hand");
+    }
+}
@@ -25,6 +25,7 @@ use super::{
     Clone,
     PartialEq,
     serde::Serialize,
+    serde::Deserialize,
     strum_macros::AsRefStr,
     strum_macros::Display,
     FromSqlRow,
@@ -1,5 +1,5 @@
 use anyhow::anyhow;
-use chrono::{DateTime, TimeZone};
+use chrono::{DateTime, TimeDelta, TimeZone};
 use diesel::{
     deserialize::{FromSql, FromSqlRow},
     expression::AsExpression,
@@ -23,6 +23,7 @@ use super::{
     Clone,
     Copy,
     serde::Serialize,
+    serde::Deserialize,
     strum_macros::AsRefStr,
     strum_macros::EnumString,
     strum_macros::Display,
@@ -39,6 +40,18 @@ pub enum EventType {
     Task,
 }
 
+impl EventType {
+    /// Duration of an item of this type when it has no end
+    pub fn default_duration(&self) -> TimeDelta {
+        match self {
+            EventType::Event => TimeDelta::hours(1),
+            EventType::Block => TimeDelta::hours(1),
+            EventType::Reminder => TimeDelta::minutes(15),
+            EventType::Task => TimeDelta::minutes(30),
+        }
+    }
+}
+
 impl FromSql<Text, Sqlite> for EventType {
     fn from_sql(bytes: SqliteValue) -> diesel::deserialize::Result<Self> {
         let t = <String as FromSql<Text, Sqlite>>::from_sql(bytes)?;
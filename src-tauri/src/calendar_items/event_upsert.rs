@@ -2,6 +2,7 @@ use chrono::{DateTime, TimeZone};
 
 use super::input_traits::FromUserInput;
 use crate::calendar_items::event_date::EventDateOption;
+use crate::calendar_items::event_parent::EventParent;
 use crate::calendar_items::event_status::EventStatus;
 use crate::calendar_items::event_tags::EventTags;
 use crate::calendar_items::event_type::EventType;
@@ -18,6 +19,7 @@ pub struct EventUpsertInfo<Tz: TimeZone> {
     pub load: i32,
     pub importance: i32,
     pub tag: EventTags,
+    pub parent: EventParent,
 }
 
 impl<Tz: TimeZone> FromUserInput<Tz> for EventUpsertInfo<Tz> {
@@ -25,8 +27,10 @@ impl<Tz: TimeZone> FromUserInput<Tz> for EventUpsertInfo<Tz> {
         date_of_input: DateTime<Tz>,
         input: &str,
     ) -> anyhow::Result<impl Into<ExtractedInput<Self>>> {
+        let ExtractedInput(parent, input) =
+            EventParent::extract_from_input(date_of_input.clone(), input)?.into();
         let ExtractedInput(date_info, input) =
-            EventDateOption::extract_from_input(date_of_input.clone(), input)?.into();
+            EventDateOption::extract_from_input(date_of_input.clone(), &input)?.into();
         let ExtractedInput(status, input) =
             EventStatus::extract_from_input(date_of_input.clone(), &input)?.into();
         let ExtractedInput(event_type, input) =
@@ -45,6 +49,7 @@ impl<Tz: TimeZone> FromUserInput<Tz> for EventUpsertInfo<Tz> {
                 load: 0,
                 importance: 0,
                 tag,
+                parent,
             },
             input,
         ))
@@ -176,6 +181,22 @@ mod tests {
         assert_eq!(info.tag, EventTags(Some("hello".to_string())))
     }
 
+    #[test]
+    fn should_parse_subtask() {
+        let date_of_input = chrono_tz::America::Buenos_Aires
+            .with_ymd_and_hms(2025, 3, 6, 10, 30, 0)
+            .unwrap();
+        let input = "@task ^42 sand the walls #home";
+        let ExtractedInput(info, _) = EventUpsertInfo::extract_from_input(date_of_input, input)
+            .expect("To parse string")
+            .into();
+
+        assert_eq!(info.summary, "sand the walls");
+        assert_eq!(info.event_type, EventType::Task);
+        assert_eq!(info.parent, EventParent(Some(42)));
+        assert!(info.date_info.0.is_none());
+    }
+
     #[test]
     fn should_parse_full_example() {
         let date_of_input = chrono_tz::America::Buenos_Aires
@@ -2,18 +2,28 @@ use chrono::{DateTime, NaiveTime, TimeDelta, TimeZone, Utc};
 use icalendar::{CalendarComponent, Component, DatePerhapsTime, EventLike};
 use log::warn;
 
-use crate::calendar_items::{
-    component_props::ComponentProps, event_date::EventRecurrence, event_status::EventStatus,
-    event_type::EventType, event_upsert::EventUpsertInfo,
+use crate::{
+    calendar_items::{
+        component_props::{ComponentProps, add_priority_property, add_tag_properties},
+        event_date::EventRecurrence,
+        event_status::EventStatus,
+        event_type::EventType,
+        event_upsert::EventUpsertInfo,
+        timezone::CalendarTimezones,
+    },
+    models::{conflict::ConflictingItem, day_summary::DaySummary},
 };
 
 pub(crate) mod component_props;
 pub(crate) mod event_date;
+pub(crate) mod event_parent;
 pub(crate) mod event_status;
 pub(crate) mod event_tags;
 pub(crate) mod event_type;
 pub(crate) mod event_upsert;
 pub(crate) mod input_traits;
+pub(crate) mod priority;
+pub(crate) mod timezone;
 
 impl<Tz: TimeZone> From<&EventUpsertInfo<Tz>> for CalendarComponent {
     fn from(value: &EventUpsertInfo<Tz>) -> Self {
@@ -35,6 +45,12 @@ impl<Tz: TimeZone> From<&EventUpsertInfo<Tz>> for CalendarComponent {
                         event.add_property(ComponentProps::RRule, recurrence);
                     }
 
+                    add_priority_property(&mut event, value.urgency, value.importance);
+
+                    if let Some(tag) = &value.tag.0 {
+                        add_tag_properties(&mut event, tag);
+                    }
+
                     event.into()
                 }
                 None => {
@@ -62,6 +78,12 @@ impl<Tz: TimeZone> From<&EventUpsertInfo<Tz>> for CalendarComponent {
                     }
                 }
 
+                add_priority_property(&mut todo, value.urgency, value.importance);
+
+                if let Some(tag) = &value.tag.0 {
+                    add_tag_properties(&mut todo, tag);
+                }
+
                 todo.into()
             }
         }
@@ -82,6 +104,16 @@ pub struct DisplayUpsertInfo {
     pub load: i32,
     pub importance: i32,
     pub tag: Option<String>,
+    /// Id of the todo it will be a subtask of
+    pub parent_id: Option<i32>,
+    /// Set when adding it puts its day over the load budget
+    pub budget_warning: Option<DaySummary>,
+    /// Existing items taking the same time
+    pub conflicts: Vec<ConflictingItem>,
+    /// Input after expanding the template it started with
+    pub expanded_input: Option<String>,
+    /// Why the input couldn't be fully understood, like a slot query without a free slot
+    pub warning: Option<String>,
 }
 
 impl<Tz: TimeZone> From<EventUpsertInfo<Tz>> for DisplayUpsertInfo {
@@ -110,33 +142,32 @@ impl<Tz: TimeZone> From<EventUpsertInfo<Tz>> for DisplayUpsertInfo {
             load: value.load,
             importance: value.importance,
             tag: value.tag.0,
+            parent_id: value.parent.0,
+            budget_warning: None,
+            conflicts: vec![],
+            expanded_input: None,
+            warning: None,
         }
     }
 }
 
+/// Converts a date of a component to UTC, resolving its TZID with the calendar [`CalendarTimezones`]
 pub fn date_from_calendar_to_utc(
     original: DatePerhapsTime,
-    timezone: chrono_tz::Tz,
+    timezones: &CalendarTimezones,
 ) -> Option<DateTime<Utc>> {
     match original {
         DatePerhapsTime::DateTime(calendar_date_time) => match calendar_date_time {
-            icalendar::CalendarDateTime::Floating(floating) => floating
-                .and_local_timezone(timezone)
-                .earliest()
-                .map(|d| d.to_utc()),
+            icalendar::CalendarDateTime::Floating(floating) => timezones.to_utc(None, &floating),
             icalendar::CalendarDateTime::Utc(date_time) => Some(date_time),
             icalendar::CalendarDateTime::WithTimezone { date_time, tzid } => {
-                let tz: chrono_tz::Tz = tzid.parse().ok()?;
-                tz.from_local_datetime(&date_time)
-                    .earliest()
-                    .map(|d| d.to_utc())
+                timezones.to_utc(Some(&tzid), &date_time)
             }
         },
-        DatePerhapsTime::Date(naive_date) => naive_date
-            .and_time(NaiveTime::from_hms_opt(0, 0, 0).unwrap())
-            .and_local_timezone(timezone)
-            .earliest()
-            .map(|d| d.to_utc()),
+        DatePerhapsTime::Date(naive_date) => timezones.to_utc(
+            None,
+            &naive_date.and_time(NaiveTime::from_hms_opt(0, 0, 0).unwrap()),
+        ),
     }
 }
 
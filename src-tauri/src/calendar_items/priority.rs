@@ -0,0 +1,108 @@
+//! Two-way mapping between the standard `PRIORITY` (RFC 5545 3.8.1.9) and the
+//! urgency (0 to 3) and importance (-3 to 3) of mimido.
+//!
+//! `PRIORITY` goes from 1, the highest, to 9, the lowest, and 0 means undefined.
+//! Importing a priority uses this table:
+//!
+//! | PRIORITY | urgency | importance |
+//! |----------|---------|------------|
+//! | 1        | 3       | 3          |
+//! | 2        | 3       | 2          |
+//! | 3        | 2       | 2          |
+//! | 4        | 2       | 1          |
+//! | 5        | 1       | 1          |
+//! | 6        | 1       | 0          |
+//! | 7        | 0       | -1         |
+//! | 8        | 0       | -2         |
+//! | 9        | 0       | -3         |
+//!
+//! Exporting uses the sum of urgency and importance, a sum of 1 or more is
+//! `7 - sum` and a lower one is `6 - sum` capped at 9, so every row of the table
+//! maps back to its own priority. No urgency and no importance means undefined.
+
+use icalendar::Component;
+
+use crate::calendar_items::component_props::ComponentProps;
+
+/// Undefined priority
+pub const NO_PRIORITY: u8 = 0;
+
+/// Urgency and importance for each `PRIORITY`, starting at 1
+const PRIORITY_TABLE: [(i32, i32); 9] = [
+    (3, 3),
+    (3, 2),
+    (2, 2),
+    (2, 1),
+    (1, 1),
+    (1, 0),
+    (0, -1),
+    (0, -2),
+    (0, -3),
+];
+
+/// Urgency and importance for a `PRIORITY`, [`None`] when it's undefined or out of range
+pub fn from_priority(priority: u8) -> Option<(i32, i32)> {
+    match priority {
+        1..=9 => Some(PRIORITY_TABLE[usize::from(priority - 1)]),
+        _ => None,
+    }
+}
+
+/// `PRIORITY` for an urgency and importance
+pub fn to_priority(urgency: i32, importance: i32) -> u8 {
+    if urgency == 0 && importance == 0 {
+        return NO_PRIORITY;
+    }
+    let sum = urgency + importance;
+    let priority = if sum >= 1 { 7 - sum } else { 6 - sum };
+    // Clamped to 1..=9, so it always fits
+    priority.clamp(1, 9) as u8
+}
+
+/// `PRIORITY` of the first todo or event of an ical string
+pub fn priority_from_ical_data(ical_data: &str) -> Option<u8> {
+    let calendar: icalendar::Calendar = ical_data.parse().ok()?;
+    calendar.components.iter().find_map(|cmp| {
+        let value = match (cmp.as_todo(), cmp.as_event()) {
+            (Some(todo), _) => todo.property_value(ComponentProps::Priority.as_ref()),
+            (_, Some(event)) => event.property_value(ComponentProps::Priority.as_ref()),
+            _ => None,
+        };
+        value.and_then(|v| v.trim().parse::<u8>().ok())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_maps_back_to_itself() {
+        for priority in 1..=9 {
+            let (urgency, importance) = from_priority(priority).unwrap();
+            assert_eq!(to_priority(urgency, importance), priority);
+        }
+    }
+
+    #[test]
+    fn test_undefined_priority() {
+        assert_eq!(from_priority(NO_PRIORITY), None);
+        assert_eq!(from_priority(10), None);
+        assert_eq!(to_priority(0, 0), NO_PRIORITY);
+    }
+
+    #[test]
+    fn test_out_of_table_values() {
+        assert_eq!(to_priority(1, -1), 6);
+        assert_eq!(to_priority(3, 3), 1);
+        assert_eq!(to_priority(0, 3), 4);
+        assert_eq!(to_priority(0, -3), 9);
+    }
+
+    #[test]
+    fn test_priority_from_ical_data() {
+        let ics = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTODO\r\nUID:1\r\nPRIORITY:2\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+        assert_eq!(priority_from_ical_data(ics), Some(2));
+        assert_eq!(priority_from_ical_data("not a calendar"), None);
+    }
+}
@@ -0,0 +1,716 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc,
+    Weekday,
+};
+use log::warn;
+
+/// Windows timezone names, as sent by Outlook and Exchange, mapped to their IANA equivalent
+/// Taken from the `001` territory of the CLDR `windowsZones.xml`
+const WINDOWS_ZONES: &[(&str, &str)] = &[
+    ("Dateline Standard Time", "Etc/GMT+12"),
+    ("UTC-11", "Etc/GMT+11"),
+    ("Aleutian Standard Time", "America/Adak"),
+    ("Hawaiian Standard Time", "Pacific/Honolulu"),
+    ("Marquesas Standard Time", "Pacific/Marquesas"),
+    ("Alaskan Standard Time", "America/Anchorage"),
+    ("UTC-09", "Etc/GMT+9"),
+    ("Pacific Standard Time (Mexico)", "America/Tijuana"),
+    ("UTC-08", "Etc/GMT+8"),
+    ("Pacific Standard Time", "America/Los_Angeles"),
+    ("US Mountain Standard Time", "America/Phoenix"),
+    ("Mountain Standard Time (Mexico)", "America/Mazatlan"),
+    ("Mountain Standard Time", "America/Denver"),
+    ("Yukon Standard Time", "America/Whitehorse"),
+    ("Central America Standard Time", "America/Guatemala"),
+    ("Central Standard Time", "America/Chicago"),
+    ("Easter Island Standard Time", "Pacific/Easter"),
+    ("Central Standard Time (Mexico)", "America/Mexico_City"),
+    ("Canada Central Standard Time", "America/Regina"),
+    ("SA Pacific Standard Time", "America/Bogota"),
+    ("Eastern Standard Time (Mexico)", "America/Cancun"),
+    ("Eastern Standard Time", "America/New_York"),
+    ("Haiti Standard Time", "America/Port-au-Prince"),
+    ("Cuba Standard Time", "America/Havana"),
+    ("US Eastern Standard Time", "America/Indiana/Indianapolis"),
+    ("Turks And Caicos Standard Time", "America/Grand_Turk"),
+    ("Paraguay Standard Time", "America/Asuncion"),
+    ("Atlantic Standard Time", "America/Halifax"),
+    ("Venezuela Standard Time", "America/Caracas"),
+    ("Central Brazilian Standard Time", "America/Cuiaba"),
+    ("SA Western Standard Time", "America/La_Paz"),
+    ("Pacific SA Standard Time", "America/Santiago"),
+    ("Newfoundland Standard Time", "America/St_Johns"),
+    ("Tocantins Standard Time", "America/Araguaina"),
+    ("E. South America Standard Time", "America/Sao_Paulo"),
+    ("SA Eastern Standard Time", "America/Cayenne"),
+    ("Argentina Standard Time", "America/Argentina/Buenos_Aires"),
+    ("Greenland Standard Time", "America/Nuuk"),
+    ("Montevideo Standard Time", "America/Montevideo"),
+    ("Magallanes Standard Time", "America/Punta_Arenas"),
+    ("Saint Pierre Standard Time", "America/Miquelon"),
+    ("Bahia Standard Time", "America/Bahia"),
+    ("UTC-02", "Etc/GMT+2"),
+    ("Mid-Atlantic Standard Time", "Etc/GMT+2"),
+    ("Azores Standard Time", "Atlantic/Azores"),
+    ("Cape Verde Standard Time", "Atlantic/Cape_Verde"),
+    ("UTC", "Etc/UTC"),
+    ("GMT Standard Time", "Europe/London"),
+    ("Greenwich Standard Time", "Atlantic/Reykjavik"),
+    ("Sao Tome Standard Time", "Africa/Sao_Tome"),
+    ("Morocco Standard Time", "Africa/Casablanca"),
+    ("W. Europe Standard Time", "Europe/Berlin"),
+    ("Central Europe Standard Time", "Europe/Budapest"),
+    ("Romance Standard Time", "Europe/Paris"),
+    ("Central European Standard Time", "Europe/Warsaw"),
+    ("W. Central Africa Standard Time", "Africa/Lagos"),
+    ("Jordan Standard Time", "Asia/Amman"),
+    ("GTB Standard Time", "Europe/Bucharest"),
+    ("Middle East Standard Time", "Asia/Beirut"),
+    ("Egypt Standard Time", "Africa/Cairo"),
+    ("E. Europe Standard Time", "Europe/Chisinau"),
+    ("Syria Standard Time", "Asia/Damascus"),
+    ("West Bank Standard Time", "Asia/Hebron"),
+    ("South Africa Standard Time", "Africa/Johannesburg"),
+    ("FLE Standard Time", "Europe/Kyiv"),
+    ("Israel Standard Time", "Asia/Jerusalem"),
+    ("South Sudan Standard Time", "Africa/Juba"),
+    ("Kaliningrad Standard Time", "Europe/Kaliningrad"),
+    ("Sudan Standard Time", "Africa/Khartoum"),
+    ("Libya Standard Time", "Africa/Tripoli"),
+    ("Namibia Standard Time", "Africa/Windhoek"),
+    ("Arabic Standard Time", "Asia/Baghdad"),
+    ("Turkey Standard Time", "Europe/Istanbul"),
+    ("Arab Standard Time", "Asia/Riyadh"),
+    ("Belarus Standard Time", "Europe/Minsk"),
+    ("Russian Standard Time", "Europe/Moscow"),
+    ("E. Africa Standard Time", "Africa/Nairobi"),
+    ("Volgograd Standard Time", "Europe/Volgograd"),
+    ("Iran Standard Time", "Asia/Tehran"),
+    ("Arabian Standard Time", "Asia/Dubai"),
+    ("Astrakhan Standard Time", "Europe/Astrakhan"),
+    ("Azerbaijan Standard Time", "Asia/Baku"),
+    ("Russia Time Zone 3", "Europe/Samara"),
+    ("Mauritius Standard Time", "Indian/Mauritius"),
+    ("Saratov Standard Time", "Europe/Saratov"),
+    ("Georgian Standard Time", "Asia/Tbilisi"),
+    ("Caucasus Standard Time", "Asia/Yerevan"),
+    ("Afghanistan Standard Time", "Asia/Kabul"),
+    ("West Asia Standard Time", "Asia/Tashkent"),
+    ("Ekaterinburg Standard Time", "Asia/Yekaterinburg"),
+    ("Pakistan Standard Time", "Asia/Karachi"),
+    ("Qyzylorda Standard Time", "Asia/Qyzylorda"),
+    ("India Standard Time", "Asia/Kolkata"),
+    ("Sri Lanka Standard Time", "Asia/Colombo"),
+    ("Nepal Standard Time", "Asia/Kathmandu"),
+    ("Central Asia Standard Time", "Asia/Almaty"),
+    ("Bangladesh Standard Time", "Asia/Dhaka"),
+    ("Omsk Standard Time", "Asia/Omsk"),
+    ("Myanmar Standard Time", "Asia/Yangon"),
+    ("SE Asia Standard Time", "Asia/Bangkok"),
+    ("Altai Standard Time", "Asia/Barnaul"),
+    ("W. Mongolia Standard Time", "Asia/Hovd"),
+    ("North Asia Standard Time", "Asia/Krasnoyarsk"),
+    ("N. Central Asia Standard Time", "Asia/Novosibirsk"),
+    ("Tomsk Standard Time", "Asia/Tomsk"),
+    ("China Standard Time", "Asia/Shanghai"),
+    ("North Asia East Standard Time", "Asia/Irkutsk"),
+    ("Singapore Standard Time", "Asia/Singapore"),
+    ("W. Australia Standard Time", "Australia/Perth"),
+    ("Taipei Standard Time", "Asia/Taipei"),
+    ("Ulaanbaatar Standard Time", "Asia/Ulaanbaatar"),
+    ("Aus Central W. Standard Time", "Australia/Eucla"),
+    ("Transbaikal Standard Time", "Asia/Chita"),
+    ("Tokyo Standard Time", "Asia/Tokyo"),
+    ("North Korea Standard Time", "Asia/Pyongyang"),
+    ("Korea Standard Time", "Asia/Seoul"),
+    ("Yakutsk Standard Time", "Asia/Yakutsk"),
+    ("Cen. Australia Standard Time", "Australia/Adelaide"),
+    ("AUS Central Standard Time", "Australia/Darwin"),
+    ("E. Australia Standard Time", "Australia/Brisbane"),
+    ("AUS Eastern Standard Time", "Australia/Sydney"),
+    ("West Pacific Standard Time", "Pacific/Port_Moresby"),
+    ("Tasmania Standard Time", "Australia/Hobart"),
+    ("Vladivostok Standard Time", "Asia/Vladivostok"),
+    ("Lord Howe Standard Time", "Australia/Lord_Howe"),
+    ("Bougainville Standard Time", "Pacific/Bougainville"),
+    ("Russia Time Zone 10", "Asia/Srednekolymsk"),
+    ("Magadan Standard Time", "Asia/Magadan"),
+    ("Norfolk Standard Time", "Pacific/Norfolk"),
+    ("Sakhalin Standard Time", "Asia/Sakhalin"),
+    ("Central Pacific Standard Time", "Pacific/Guadalcanal"),
+    ("Russia Time Zone 11", "Asia/Kamchatka"),
+    ("New Zealand Standard Time", "Pacific/Auckland"),
+    ("UTC+12", "Etc/GMT-12"),
+    ("Fiji Standard Time", "Pacific/Fiji"),
+    ("Chatham Islands Standard Time", "Pacific/Chatham"),
+    ("UTC+13", "Etc/GMT-13"),
+    ("Tonga Standard Time", "Pacific/Tongatapu"),
+    ("Samoa Standard Time", "Pacific/Apia"),
+    ("Line Islands Standard Time", "Pacific/Kiritimati"),
+];
+
+/// Maps a Windows timezone name (ie: "Argentina Standard Time") to the IANA timezone
+pub fn windows_to_iana(name: &str) -> Option<chrono_tz::Tz> {
+    let name = name.trim();
+    WINDOWS_ZONES
+        .iter()
+        .find(|(windows, _)| windows.eq_ignore_ascii_case(name))
+        .and_then(|(_, iana)| iana.parse().ok())
+}
+
+/// Tries to get an IANA timezone from a TZID
+///
+/// Handles IANA names, Windows names, and prefixed ids like
+/// `/citadel.org/20190914_1/Europe/Berlin` used by some servers
+pub fn tzid_to_iana(tzid: &str) -> Option<chrono_tz::Tz> {
+    let tzid = tzid.trim().trim_matches('"');
+    if let Ok(tz) = tzid.parse::<chrono_tz::Tz>() {
+        return Some(tz);
+    }
+    if let Some(tz) = windows_to_iana(tzid) {
+        return Some(tz);
+    }
+    // Try the last segments of prefixed ids, `Area/Location` and `Area/Sub/Location`
+    let segments = tzid.split('/').collect::<Vec<&str>>();
+    (2..=3)
+        .filter(|len| segments.len() > *len)
+        .find_map(|len| segments[segments.len() - len..].join("/").parse().ok())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct YearlyRule {
+    month: Option<u32>,
+    /// Ordinal and weekday, a negative ordinal counts from the end of the month
+    weekday: Option<(Option<i32>, Weekday)>,
+    month_days: Vec<u32>,
+    until: Option<NaiveDateTime>,
+}
+
+impl YearlyRule {
+    /// Parses the subset of RRULE used by VTIMEZONE definitions, only `FREQ=YEARLY` is supported
+    fn parse(rrule: &str) -> Option<Self> {
+        let parts = rrule
+            .split(';')
+            .filter_map(|part| part.split_once('='))
+            .map(|(k, v)| (k.to_uppercase(), v.to_string()))
+            .collect::<HashMap<String, String>>();
+
+        if parts.get("FREQ").map(|f| f.to_uppercase()) != Some("YEARLY".to_string()) {
+            warn!("Unsupported timezone rule {rrule}");
+            return None;
+        }
+
+        let month = parts.get("BYMONTH").and_then(|m| m.parse().ok());
+        let weekday = parts.get("BYDAY").and_then(|d| parse_by_day(d));
+        let month_days = parts
+            .get("BYMONTHDAY")
+            .map(|days| days.split(',').filter_map(|d| d.parse().ok()).collect())
+            .unwrap_or_default();
+        let until = parts.get("UNTIL").and_then(|u| parse_ical_date_time(u));
+
+        Some(Self {
+            month,
+            weekday,
+            month_days,
+            until,
+        })
+    }
+
+    fn date_in_year(&self, year: i32, default_month: u32, default_day: u32) -> Option<NaiveDate> {
+        let month = self.month.unwrap_or(default_month);
+        match self.weekday {
+            Some((Some(n), weekday)) => nth_weekday_of_month(year, month, weekday, n),
+            Some((None, weekday)) if !self.month_days.is_empty() => self
+                .month_days
+                .iter()
+                .filter_map(|d| NaiveDate::from_ymd_opt(year, month, *d))
+                .find(|d| d.weekday() == weekday),
+            Some((None, weekday)) => nth_weekday_of_month(year, month, weekday, 1),
+            None => NaiveDate::from_ymd_opt(
+                year,
+                month,
+                self.month_days.first().copied().unwrap_or(default_day),
+            ),
+        }
+    }
+}
+
+fn parse_weekday(day: &str) -> Option<Weekday> {
+    match day.to_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Parses a `BYDAY` like `2SU`, `-1SU` or `SU`
+fn parse_by_day(by_day: &str) -> Option<(Option<i32>, Weekday)> {
+    let by_day = by_day.split(',').next()?.trim();
+    let split_at = by_day.len().checked_sub(2)?;
+    let (ordinal, day) = by_day.split_at(split_at);
+    let weekday = parse_weekday(day)?;
+    if ordinal.is_empty() {
+        Some((None, weekday))
+    } else {
+        Some((Some(ordinal.trim_start_matches('+').parse().ok()?), weekday))
+    }
+}
+
+fn nth_weekday_of_month(year: i32, month: u32, weekday: Weekday, n: i32) -> Option<NaiveDate> {
+    if n > 0 {
+        return NaiveDate::from_weekday_of_month_opt(year, month, weekday, n as u8);
+    }
+    let first_of_next = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)
+    }?;
+    let last = first_of_next.pred_opt()?;
+    let back = (7 + last.weekday().num_days_from_monday() as i64
+        - weekday.num_days_from_monday() as i64)
+        % 7;
+    let date = last - Duration::days(back + 7 * (-n as i64 - 1));
+    (date.month() == month).then_some(date)
+}
+
+fn parse_ical_date_time(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim().trim_end_matches('Z');
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y%m%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })
+}
+
+/// Parses an UTC offset like `-0300` or `+053000`
+fn parse_offset(value: &str) -> Option<FixedOffset> {
+    let value = value.trim();
+    let (sign, digits) = match value.split_at_checked(1)? {
+        ("-", digits) => (-1, digits),
+        ("+", digits) => (1, digits),
+        _ => (1, value),
+    };
+    if digits.len() < 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[0..2].parse().ok()?;
+    let minutes: i32 = digits[2..4].parse().ok()?;
+    let seconds: i32 = digits.get(4..6).and_then(|s| s.parse().ok()).unwrap_or(0);
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+/// A `STANDARD` or `DAYLIGHT` block of a VTIMEZONE
+#[derive(Debug, Clone, PartialEq)]
+struct Observance {
+    start: NaiveDateTime,
+    offset_from: FixedOffset,
+    offset_to: FixedOffset,
+    rule: Option<YearlyRule>,
+    rdates: Vec<NaiveDateTime>,
+}
+
+impl Observance {
+    fn onset_in_year(&self, year: i32) -> Option<NaiveDateTime> {
+        if year < self.start.year() {
+            return None;
+        }
+        let rule = self.rule.as_ref()?;
+        let date = rule.date_in_year(year, self.start.month(), self.start.day())?;
+        let onset = date.and_time(self.start.time());
+        if onset < self.start {
+            return None;
+        }
+        if let Some(until) = rule.until
+            && onset > until
+        {
+            return None;
+        }
+        Some(onset)
+    }
+
+    /// The last time this observance started before the local date
+    fn last_onset_before(&self, local: &NaiveDateTime) -> Option<NaiveDateTime> {
+        let yearly = [local.year() - 1, local.year()]
+            .into_iter()
+            .filter_map(|year| self.onset_in_year(year));
+        std::iter::once(self.start)
+            .chain(self.rdates.iter().copied())
+            .chain(yearly)
+            .filter(|onset| onset <= local)
+            .max()
+    }
+}
+
+/// A VTIMEZONE definition sent by the server
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct VTimezone {
+    pub tzid: String,
+    observances: Vec<Observance>,
+}
+
+impl VTimezone {
+    /// Offset used by this timezone at a local (wall clock) date
+    pub fn offset_at(&self, local: &NaiveDateTime) -> Option<FixedOffset> {
+        self.observances
+            .iter()
+            .filter_map(|o| o.last_onset_before(local).map(|onset| (onset, o)))
+            .max_by_key(|(onset, _)| *onset)
+            .map(|(_, o)| o.offset_to)
+            .or_else(|| {
+                self.observances
+                    .iter()
+                    .min_by_key(|o| o.start)
+                    .map(|o| o.offset_from)
+            })
+    }
+
+    /// IANA timezone with the same offset as this definition on every day of the year,
+    /// the ones known by Windows are preferred
+    pub fn iana_equivalent(&self, year: i32) -> Option<chrono_tz::Tz> {
+        let days = NaiveDate::from_ymd_opt(year, 1, 1)?
+            .iter_days()
+            .take_while(|d| d.year() == year)
+            .filter_map(|d| d.and_hms_opt(12, 0, 0))
+            .collect::<Vec<NaiveDateTime>>();
+        let offsets = days
+            .iter()
+            .map(|d| self.offset_at(d))
+            .collect::<Option<Vec<FixedOffset>>>()?;
+        let same_offset = |tz: &chrono_tz::Tz, i: usize| {
+            tz.offset_from_local_datetime(&days[i])
+                .single()
+                .map(|o| o.fix())
+                == Some(offsets[i])
+        };
+        // Most timezones already differ in january or july, only the ones matching both
+        // are compared on every day
+        let samples = [0, days.len() / 2];
+
+        WINDOWS_ZONES
+            .iter()
+            .filter_map(|(_, iana)| iana.parse::<chrono_tz::Tz>().ok())
+            .chain(chrono_tz::TZ_VARIANTS.iter().copied())
+            .filter(|tz| samples.iter().all(|i| same_offset(tz, *i)))
+            .find(|tz| (0..days.len()).all(|i| same_offset(tz, i)))
+    }
+}
+
+#[derive(Default)]
+struct ObservanceBuilder {
+    start: Option<NaiveDateTime>,
+    offset_from: Option<FixedOffset>,
+    offset_to: Option<FixedOffset>,
+    rule: Option<YearlyRule>,
+    rdates: Vec<NaiveDateTime>,
+}
+
+impl ObservanceBuilder {
+    fn build(self) -> Option<Observance> {
+        let offset_to = self.offset_to?;
+        Some(Observance {
+            start: self.start?,
+            offset_from: self.offset_from.unwrap_or(offset_to),
+            offset_to,
+            rule: self.rule,
+            rdates: self.rdates,
+        })
+    }
+}
+
+/// Splits a content line into the name, without parameters, and the value
+fn split_content_line(line: &str) -> Option<(String, &str)> {
+    let mut in_quotes = false;
+    let (colon, _) = line.char_indices().find(|(_, c)| {
+        if *c == '"' {
+            in_quotes = !in_quotes;
+        }
+        *c == ':' && !in_quotes
+    })?;
+    let name = line[..colon].split(';').next()?.trim().to_uppercase();
+    Some((name, &line[colon + 1..]))
+}
+
+fn unfold_lines(ical_data: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ical_data.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Timezone information of a calendar resource, used to resolve the dates of its components
+#[derive(Debug, Clone, Default)]
+pub struct CalendarTimezones {
+    /// Value of `X-WR-TIMEZONE`, used for floating dates
+    default_tzid: Option<String>,
+    definitions: HashMap<String, VTimezone>,
+    /// Results of [`VTimezone::iana_equivalent`] by TZID and year
+    equivalents: RefCell<HashMap<(String, i32), Option<chrono_tz::Tz>>>,
+}
+
+impl CalendarTimezones {
+    /// Reads the `X-WR-TIMEZONE` and every `VTIMEZONE` from the raw ical data
+    pub fn from_ical_data(ical_data: &str) -> Self {
+        let mut timezones = Self::default();
+        let mut current: Option<VTimezone> = None;
+        let mut observance: Option<ObservanceBuilder> = None;
+
+        for line in unfold_lines(ical_data) {
+            let Some((name, value)) = split_content_line(&line) else {
+                continue;
+            };
+            let value = value.trim();
+            match (name.as_str(), value.to_uppercase().as_str()) {
+                ("BEGIN", "VTIMEZONE") => current = Some(VTimezone::default()),
+                ("END", "VTIMEZONE") => {
+                    if let Some(tz) = current.take()
+                        && !tz.tzid.is_empty()
+                    {
+                        timezones.definitions.insert(tz.tzid.clone(), tz);
+                    }
+                }
+                ("BEGIN", "STANDARD" | "DAYLIGHT") if current.is_some() => {
+                    observance = Some(ObservanceBuilder::default())
+                }
+                ("END", "STANDARD" | "DAYLIGHT") => {
+                    if let Some(o) = observance.take().and_then(ObservanceBuilder::build)
+                        && let Some(tz) = current.as_mut()
+                    {
+                        tz.observances.push(o);
+                    }
+                }
+                ("X-WR-TIMEZONE", _) if current.is_none() => {
+                    timezones.default_tzid = Some(value.to_string())
+                }
+                _ => match (observance.as_mut(), current.as_mut()) {
+                    (Some(o), _) => match name.as_str() {
+                        "DTSTART" => o.start = parse_ical_date_time(value),
+                        "TZOFFSETFROM" => o.offset_from = parse_offset(value),
+                        "TZOFFSETTO" => o.offset_to = parse_offset(value),
+                        "RRULE" => o.rule = YearlyRule::parse(value),
+                        "RDATE" => o
+                            .rdates
+                            .extend(value.split(',').filter_map(parse_ical_date_time)),
+                        _ => {}
+                    },
+                    (None, Some(tz)) if name == "TZID" => {
+                        tz.tzid = value.trim_matches('"').to_string()
+                    }
+                    _ => {}
+                },
+            }
+        }
+        timezones
+    }
+
+    fn definition(&self, tzid: &str) -> Option<&VTimezone> {
+        let tzid = tzid.trim().trim_matches('"');
+        self.definitions.get(tzid).or_else(|| {
+            self.definitions
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(tzid))
+                .map(|(_, v)| v)
+        })
+    }
+
+    /// Gets the IANA timezone for the TZID, or for floating dates when `tzid` is [`None`]
+    ///
+    /// Returns [`None`] when the TZID can only be resolved with its VTIMEZONE definition
+    pub fn iana_timezone(&self, tzid: Option<&str>) -> Option<chrono_tz::Tz> {
+        tzid.or(self.default_tzid.as_deref()).and_then(tzid_to_iana)
+    }
+
+    /// Gets the IANA timezone that follows the VTIMEZONE definition of the TZID in the year
+    /// of the local date, when `tzid` is [`None`] the calendar default is used
+    pub fn iana_equivalent(
+        &self,
+        tzid: Option<&str>,
+        local: &NaiveDateTime,
+    ) -> Option<chrono_tz::Tz> {
+        let tzid = tzid.or(self.default_tzid.as_deref())?;
+        let definition = self.definition(tzid)?;
+        let key = (definition.tzid.clone(), local.year());
+        if let Some(tz) = self.equivalents.borrow().get(&key) {
+            return *tz;
+        }
+        let tz = definition.iana_equivalent(local.year());
+        self.equivalents.borrow_mut().insert(key, tz);
+        tz
+    }
+
+    /// Converts a local date in the TZID to UTC, when `tzid` is [`None`] the date is floating
+    ///
+    /// IANA and Windows names are preferred, the VTIMEZONE definition of the calendar
+    /// is used for any other TZID. Unknown timezones fall back to the calendar default or UTC.
+    pub fn to_utc(&self, tzid: Option<&str>, local: &NaiveDateTime) -> Option<DateTime<Utc>> {
+        let tzid = tzid.or(self.default_tzid.as_deref());
+        let Some(tzid) = tzid else {
+            return Some(local.and_utc());
+        };
+
+        if let Some(tz) = tzid_to_iana(tzid) {
+            return tz.from_local_datetime(local).earliest().map(|d| d.to_utc());
+        }
+
+        if let Some(definition) = self.definition(tzid) {
+            let offset = definition.offset_at(local)?;
+            return offset
+                .from_local_datetime(local)
+                .earliest()
+                .map(|d| d.to_utc());
+        }
+
+        warn!("Unknown timezone {tzid}, falling back to the calendar default");
+        match self.iana_timezone(None) {
+            Some(tz) => tz.from_local_datetime(local).earliest().map(|d| d.to_utc()),
+            None => Some(local.and_utc()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Timelike;
+
+    use super::*;
+
+    const CUSTOM_TZ: &str = "BEGIN:VCALENDAR\r
+BEGIN:VTIMEZONE\r
+TZID:Customized Time Zone\r
+BEGIN:STANDARD\r
+DTSTART:16010101T030000\r
+TZOFFSETFROM:+0200\r
+TZOFFSETTO:+0100\r
+RRULE:FREQ=YEARLY;INTERVAL=1;BYDAY=-1SU;BYMONTH=10\r
+END:STANDARD\r
+BEGIN:DAYLIGHT\r
+DTSTART:16010101T020000\r
+TZOFFSETFROM:+0100\r
+TZOFFSETTO:+0200\r
+RRULE:FREQ=YEARLY;INTERVAL=1;BYDAY=-1SU;BYMONTH=3\r
+END:DAYLIGHT\r
+END:VTIMEZONE\r
+END:VCALENDAR\r
+";
+
+    fn naive(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_windows_zones_are_valid_iana() {
+        for (windows, iana) in WINDOWS_ZONES {
+            assert!(
+                iana.parse::<chrono_tz::Tz>().is_ok(),
+                "{windows} maps to invalid {iana}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_tzid_to_iana() {
+        assert_eq!(
+            tzid_to_iana("Argentina Standard Time"),
+            Some(chrono_tz::America::Argentina::Buenos_Aires)
+        );
+        assert_eq!(
+            tzid_to_iana("\"Europe/Berlin\""),
+            Some(chrono_tz::Europe::Berlin)
+        );
+        assert_eq!(
+            tzid_to_iana("/citadel.org/20190914_1/America/Argentina/Buenos_Aires"),
+            Some(chrono_tz::America::Argentina::Buenos_Aires)
+        );
+        assert_eq!(tzid_to_iana("Customized Time Zone"), None);
+    }
+
+    #[test]
+    fn test_last_sunday_of_month() {
+        assert_eq!(
+            nth_weekday_of_month(2025, 3, Weekday::Sun, -1),
+            NaiveDate::from_ymd_opt(2025, 3, 30)
+        );
+        assert_eq!(
+            nth_weekday_of_month(2025, 10, Weekday::Sun, -1),
+            NaiveDate::from_ymd_opt(2025, 10, 26)
+        );
+        assert_eq!(
+            nth_weekday_of_month(2025, 3, Weekday::Sun, 2),
+            NaiveDate::from_ymd_opt(2025, 3, 9)
+        );
+    }
+
+    #[test]
+    fn test_parse_offset() {
+        assert_eq!(parse_offset("-0300"), FixedOffset::west_opt(3 * 3600));
+        assert_eq!(
+            parse_offset("+0530"),
+            FixedOffset::east_opt(5 * 3600 + 30 * 60)
+        );
+        assert_eq!(parse_offset("nope"), None);
+    }
+
+    #[test]
+    fn test_custom_definition() {
+        let timezones = CalendarTimezones::from_ical_data(CUSTOM_TZ);
+        let summer = timezones
+            .to_utc(Some("Customized Time Zone"), &naive(2025, 7, 1, 9, 0))
+            .unwrap();
+        assert_eq!(summer.hour(), 7);
+        let winter = timezones
+            .to_utc(Some("\"Customized Time Zone\""), &naive(2025, 12, 1, 9, 0))
+            .unwrap();
+        assert_eq!(winter.hour(), 8);
+    }
+
+    #[test]
+    fn test_iana_equivalent_of_custom_definition() {
+        let timezones = CalendarTimezones::from_ical_data(CUSTOM_TZ);
+        assert_eq!(
+            timezones.iana_equivalent(Some("Customized Time Zone"), &naive(2025, 7, 1, 9, 0)),
+            Some(chrono_tz::Europe::Berlin)
+        );
+        assert_eq!(
+            timezones.iana_equivalent(Some("Customized Time Zone"), &naive(2025, 12, 1, 9, 0)),
+            Some(chrono_tz::Europe::Berlin)
+        );
+        assert_eq!(timezones.equivalents.borrow().len(), 1);
+        assert_eq!(
+            timezones.iana_equivalent(Some("Nowhere Standard Time"), &naive(2025, 7, 1, 9, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_floating_uses_default() {
+        let timezones = CalendarTimezones::from_ical_data(
+            "BEGIN:VCALENDAR\nX-WR-TIMEZONE:Argentina Standard Time\nEND:VCALENDAR\n",
+        );
+        let date = timezones.to_utc(None, &naive(2025, 3, 10, 10, 0)).unwrap();
+        assert_eq!(date.hour(), 13);
+    }
+
+    #[test]
+    fn test_unknown_timezone_falls_back_to_utc() {
+        let timezones = CalendarTimezones::default();
+        let date = timezones
+            .to_utc(Some("Nowhere Standard Time"), &naive(2025, 3, 10, 10, 0))
+            .unwrap();
+        assert_eq!(date.hour(), 10);
+    }
+}
@@ -0,0 +1,108 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset, TimeDelta, Utc};
+use tauri::State;
+
+use crate::{
+    app_state::AppState,
+    calendar_items::event_status::EventStatus,
+    commands::errors::CommandError,
+    models::{
+        Calendar, VCmpId,
+        bulk::{BulkAction, BulkItemResult},
+    },
+    util::DateTimeStr,
+};
+
+/// Sets the status of the components, for the recurring ones only the one of their
+/// occurrence on the day of `day`
+#[tauri::command()]
+#[specta::specta]
+pub async fn bulk_set_status(
+    state: State<'_, AppState>,
+    vcmp_ids: Vec<VCmpId>,
+    status: String,
+    day: String,
+    date_of_change: String,
+) -> Result<Vec<BulkItemResult>, CommandError> {
+    let day: DateTime<FixedOffset> = DateTimeStr(day).try_into()?;
+    let updated_at: DateTime<FixedOffset> = DateTimeStr(date_of_change).try_into()?;
+    let status = EventStatus::from_str(status.as_ref())?;
+    let results = BulkAction::SetStatus { status, day }
+        .apply(state.db.clone(), vcmp_ids, updated_at.to_utc())
+        .await?;
+    Ok(results)
+}
+
+/// Moves the components to the calendar, they're pushed there and deleted from their old
+/// calendar on the next sync
+#[tauri::command()]
+#[specta::specta]
+pub async fn bulk_move_to_calendar(
+    state: State<'_, AppState>,
+    vcmp_ids: Vec<VCmpId>,
+    calendar_id: i32,
+) -> Result<Vec<BulkItemResult>, CommandError> {
+    let conn = state.db.clone();
+    let (_, calendar) = Calendar::by_id_with_server(conn.clone(), calendar_id).await?;
+    let results = BulkAction::MoveToCalendar {
+        calendar_id,
+        calendar_url: calendar.url,
+    }
+    .apply(conn, vcmp_ids, Utc::now())
+    .await?;
+    Ok(results)
+}
+
+#[tauri::command()]
+#[specta::specta]
+pub async fn bulk_add_tag(
+    state: State<'_, AppState>,
+    vcmp_ids: Vec<VCmpId>,
+    tag: String,
+) -> Result<Vec<BulkItemResult>, CommandError> {
+    let results = BulkAction::AddTag(tag)
+        .apply(state.db.clone(), vcmp_ids, Utc::now())
+        .await?;
+    Ok(results)
+}
+
+#[tauri::command()]
+#[specta::specta]
+pub async fn bulk_remove_tag(
+    state: State<'_, AppState>,
+    vcmp_ids: Vec<VCmpId>,
+    tag: String,
+) -> Result<Vec<BulkItemResult>, CommandError> {
+    let results = BulkAction::RemoveTag(tag)
+        .apply(state.db.clone(), vcmp_ids, Utc::now())
+        .await?;
+    Ok(results)
+}
+
+/// Moves the components by `minutes`, negative to move them earlier
+#[tauri::command()]
+#[specta::specta]
+pub async fn bulk_reschedule(
+    state: State<'_, AppState>,
+    vcmp_ids: Vec<VCmpId>,
+    minutes: i32,
+) -> Result<Vec<BulkItemResult>, CommandError> {
+    let results = BulkAction::Reschedule(TimeDelta::minutes(i64::from(minutes)))
+        .apply(state.db.clone(), vcmp_ids, Utc::now())
+        .await?;
+    Ok(results)
+}
+
+/// Deletes the components like [`super::components::delete_vcmp`], they go to the trash
+#[tauri::command()]
+#[specta::specta]
+pub async fn bulk_delete(
+    state: State<'_, AppState>,
+    vcmp_ids: Vec<VCmpId>,
+) -> Result<Vec<BulkItemResult>, CommandError> {
+    let results = BulkAction::Delete
+        .apply(state.db.clone(), vcmp_ids, Utc::now())
+        .await?;
+    Ok(results)
+}
@@ -1,7 +1,9 @@
 use crate::{
+    app_state::AppState,
     caldav::{
         Caldav,
         get_sync_report::{GetSyncReportResponse, SyncResult},
+        util::is_precondition_failed,
     },
     commands::errors::CommandError,
     db_conn::DbConn,
@@ -18,58 +20,63 @@ use anyhow::anyhow;
 use chrono::Utc;
 use diesel::{dsl::update, prelude::*};
 use futures::future::join_all;
+use http::StatusCode;
 use itertools::Itertools;
-use tauri::async_runtime::spawn_blocking;
+use tauri::State;
 
 #[tauri::command(rename_all = "snake_case")]
 #[specta::specta]
-pub async fn sync_all_calendars() -> Result<(), CommandError> {
+pub async fn sync_all_calendars(state: State<'_, AppState>) -> Result<(), CommandError> {
     use crate::schema::servers::dsl as server_dsl;
 
-    let conn = DbConn::new().await?;
+    let conn = state.db.clone();
     // Get all servers and fetch their calendars
     let servers = Server::list_all(conn.clone()).await?;
     let syncs = servers
         .iter()
-        .map(|server| fetch_calendars_from_caldav(server.id));
+        .map(|server| internal_fetch_calendars_from_caldav(conn.clone(), server.id));
     join_all(syncs)
         .await
         .into_iter()
         .collect::<Result<Vec<Vec<Calendar>>, CommandError>>()?;
 
-    let calendars = list_calendars().await?;
+    let calendars = Calendar::list_all(conn.clone()).await?;
 
     // Sync them sequentially
     for cal in calendars {
-        sync_calendar(cal.id).await?;
+        internal_sync_calendar(conn.clone(), cal.id).await?;
     }
 
-    spawn_blocking(move || {
+    conn.run(move |conn| {
         let now = chrono::Utc::now().timestamp();
-        let conn = &mut *conn.0.lock().unwrap();
-
         update(server_dsl::servers)
             .filter(server_dsl::id.eq_any(servers.iter().map(|s| s.id)))
             .set(server_dsl::last_sync.eq(now))
             .execute(conn)
     })
-    .await??;
+    .await?;
 
     Ok(())
 }
 
 #[tauri::command()]
 #[specta::specta]
-pub async fn sync_calendar(calendar_id: i32) -> Result<(), CommandError> {
-    let conn = DbConn::new().await?;
+pub async fn sync_calendar(
+    state: State<'_, AppState>,
+    calendar_id: i32,
+) -> Result<(), CommandError> {
+    internal_sync_calendar(state.db.clone(), calendar_id).await
+}
 
+pub async fn internal_sync_calendar(conn: DbConn, calendar_id: i32) -> Result<(), CommandError> {
     let (server, calendar) = Calendar::by_id_with_server(conn.clone(), calendar_id).await?;
 
     let caldav = Caldav::new(server).await?;
+    push_deletes(conn.clone(), &caldav, &calendar).await?;
 
     let items = caldav.get_calendar_items(&calendar.url).await?;
 
-    // Clean the events from that calendar
+    // Clean the events from that calendar, the pending deletes are kept
     VEvent::delete_all_by_calendar(conn.clone(), calendar_id).await?;
     VTodo::delete_all_by_calendar(conn.clone(), calendar_id).await?;
 
@@ -93,26 +100,32 @@ pub async fn sync_calendar(calendar_id: i32) -> Result<(), CommandError> {
 
 #[tauri::command(rename_all = "snake_case")]
 #[specta::specta]
-pub async fn list_calendars() -> Result<Vec<Calendar>, CommandError> {
-    let calendars = Calendar::list_all().await?;
+pub async fn list_calendars(state: State<'_, AppState>) -> Result<Vec<Calendar>, CommandError> {
+    let calendars = Calendar::list_all(state.db.clone()).await?;
     Ok(calendars)
 }
 
 #[tauri::command()]
 #[specta::specta]
-pub async fn set_default_calendar(calendar_id: i32) -> Result<(), CommandError> {
-    Calendar::set_default_calendar(calendar_id).await?;
+pub async fn set_default_calendar(
+    state: State<'_, AppState>,
+    calendar_id: i32,
+) -> Result<(), CommandError> {
+    Calendar::set_default_calendar(state.db.clone(), calendar_id).await?;
     Ok(())
 }
 
-pub async fn internal_super_sync_calendar(calendar_id: i32) -> Result<(), CommandError> {
-    let conn = DbConn::new().await?;
+pub async fn internal_super_sync_calendar(
+    conn: DbConn,
+    calendar_id: i32,
+) -> Result<(), CommandError> {
     let (server, calendar) = Calendar::by_id_with_server(conn.clone(), calendar_id).await?;
     let Some(sync_token) = calendar.sync_token.clone() else {
         return Ok(());
     };
     let cal_href = Href(calendar.url.clone());
     let caldav = Caldav::new(server).await?;
+    push_deletes(conn.clone(), &caldav, &calendar).await?;
 
     let not_sync_cmp = {
         let not_synced_vevent = VEvent::by_calendar_id_and_not_sync(conn.clone(), calendar_id)
@@ -135,7 +148,7 @@ pub async fn internal_super_sync_calendar(calendar_id: i32) -> Result<(), Comman
     let synced_at = Utc::now();
     for vcmp in not_sync_cmp {
         let uid = vcmp.get_uid();
-        let cal = icalendar::Calendar::new().push(vcmp.clone()).done();
+        let cal = vcmp.to_push_calendar();
         let (_, etag) = caldav
             .create_component(&cal_href, uid.clone(), &cal)
             .await?;
@@ -170,11 +183,20 @@ pub async fn internal_super_sync_calendar(calendar_id: i32) -> Result<(), Comman
         };
         // let vcmp: icalendar::CalendarComponent = vevent.clone().into();
         let cal = icalendar::Calendar::new().push(vcmp.clone()).done();
-        let etag = caldav
-            .update_component(&Href(href), &Etag(etag), &cal)
-            .await;
-
-        let etag = etag?;
+        let (vcmp, etag) = match caldav
+            .update_component(&Href(href.clone()), &Etag(etag), &cal)
+            .await
+        {
+            Ok(etag) => (vcmp, etag),
+            Err(e) if is_precondition_failed(&e) => {
+                log::warn!("{href} changed on the server, merging it with the local changes");
+                resolve_update_conflict(conn.clone(), &caldav, &cal_href, vcmp).await?
+            }
+            Err(e) => {
+                log::warn!("Update of {href} failed, will retry on the next sync: {e}");
+                continue;
+            }
+        };
         vcmp.set_synced_at(conn.clone(), etag, synced_at).await?;
     }
 
@@ -192,11 +214,10 @@ pub async fn internal_super_sync_calendar(calendar_id: i32) -> Result<(), Comman
                 SyncResult::Upserted(_, _) => None,
                 SyncResult::Deleted(href) => Some(href),
             })
-            .map(async move |href| {
-                let conn = DbConn::new().await?;
+            .map(async |href| {
                 // Try to delete both entries since we don't know which type it is
                 let vtodo_del = VTodo::try_delete_by_href(conn.clone(), href).await;
-                let vevent_del = VEvent::try_delete_by_href(conn, href).await;
+                let vevent_del = VEvent::try_delete_by_href(conn.clone(), href).await;
                 vevent_del.and(vtodo_del)
             }),
     )
@@ -223,33 +244,136 @@ pub async fn internal_super_sync_calendar(calendar_id: i32) -> Result<(), Comman
         .into_iter()
         .collect::<Vec<VCmp>>();
 
-    calendar.update_sync_token(&new_sync_token).await?;
+    calendar
+        .update_sync_token(conn.clone(), &new_sync_token)
+        .await?;
+
+    Ok(())
+}
+
+/// Retries an update rejected because the resource changed on the server
+///
+/// The fields changed in another client since the last fetch are taken from the server
+/// copy and the other local changes are kept, see [`VCmp::merged_with_server`]
+async fn resolve_update_conflict(
+    conn: DbConn,
+    caldav: &Caldav,
+    cal_href: &Href,
+    vcmp: VCmp,
+) -> anyhow::Result<(VCmp, Option<Etag>)> {
+    let href = Href(
+        vcmp.get_href()
+            .ok_or(anyhow!("{} has no href", vcmp.get_id()))?,
+    );
+    let fetched = caldav
+        .fetch_resource(cal_href, &href)
+        .await?
+        .ok_or(anyhow!("{href} is not on the server anymore"))?;
+    let content = fetched
+        .content
+        .map_err(|status| anyhow!("Resource returned {status}"))?;
+
+    let vcmp = vcmp.merged_with_server(&content.data)?.update(conn).await?;
 
+    let cal = icalendar::Calendar::new().push(vcmp.clone()).done();
+    let etag = caldav
+        .update_component(&href, &Etag(content.etag), &cal)
+        .await?;
+    Ok((vcmp, etag))
+}
+
+/// Sends the deletes made locally, the ones that fail stay hidden and are retried on the next sync
+async fn push_deletes(conn: DbConn, caldav: &Caldav, calendar: &Calendar) -> anyhow::Result<()> {
+    let cal_href = Href(calendar.url.clone());
+    let deleted_vevents = VEvent::by_calendar_id_and_deleted(conn.clone(), calendar.id)
+        .await?
+        .into_iter()
+        .map(VCmp::Event);
+    let deleted_vtodos = VTodo::by_calendar_id_and_deleted(conn.clone(), calendar.id)
+        .await?
+        .into_iter()
+        .map(VCmp::Todo);
+
+    for vcmp in deleted_vevents.chain(deleted_vtodos) {
+        match delete_on_server(caldav, &cal_href, &vcmp).await {
+            Ok(()) => {
+                vcmp.delete(conn.clone()).await?;
+            }
+            Err(e) => log::warn!(
+                "Could not delete {} on the server, will retry on the next sync: {e}",
+                vcmp.get_id()
+            ),
+        }
+    }
     Ok(())
 }
 
+/// Deletes the resource, if the etag doesn't match anymore the latest one is
+/// fetched and the delete retried
+pub(crate) async fn delete_on_server(
+    caldav: &Caldav,
+    cal_href: &Href,
+    vcmp: &VCmp,
+) -> anyhow::Result<()> {
+    let Some(href) = vcmp.get_href() else {
+        return Ok(());
+    };
+    if let Some(etag) = vcmp.get_etag() {
+        match caldav.delete_resource(href.clone(), etag).await {
+            Ok(()) => return Ok(()),
+            Err(e) => log::warn!("Delete of {href} failed, retrying with the latest etag: {e}"),
+        }
+    }
+
+    let href = Href(href);
+    let Some(fetched) = caldav.fetch_resource(cal_href, &href).await? else {
+        return Ok(());
+    };
+    match fetched.content {
+        Ok(content) => caldav.delete_resource(href, content.etag).await,
+        // Already deleted on the server
+        Err(status) if status == StatusCode::NOT_FOUND => Ok(()),
+        Err(status) => Err(anyhow!("Resource returned {status}")),
+    }
+}
+
 #[tauri::command()]
 #[specta::specta]
-pub async fn super_sync_calendar(calendar_id: i32) -> Result<(), CommandError> {
-    internal_super_sync_calendar(calendar_id).await
+pub async fn super_sync_calendar(
+    state: State<'_, AppState>,
+    calendar_id: i32,
+) -> Result<(), CommandError> {
+    internal_super_sync_calendar(state.db.clone(), calendar_id).await
 }
 
 #[tauri::command()]
 #[specta::specta]
-pub async fn fetch_calendars_from_caldav(server_id: i32) -> Result<Vec<Calendar>, CommandError> {
-    let conn = DbConn::new().await?;
+pub async fn fetch_calendars_from_caldav(
+    state: State<'_, AppState>,
+    server_id: i32,
+) -> Result<Vec<Calendar>, CommandError> {
+    internal_fetch_calendars_from_caldav(state.db.clone(), server_id).await
+}
 
-    let server = Server::by_id(conn, server_id)
+pub async fn internal_fetch_calendars_from_caldav(
+    conn: DbConn,
+    server_id: i32,
+) -> Result<Vec<Calendar>, CommandError> {
+    let server = Server::by_id(conn.clone(), server_id)
         .await?
         .ok_or(anyhow!("Server {server_id} not found"))?;
 
     let caldav = Caldav::new(server).await?;
     let found_calendars = caldav.list_caldav_calendars().await?;
 
-    let calendars = join_all(found_calendars.into_iter().map(Calendar::create_or_update))
-        .await
-        .into_iter()
-        .flatten()
-        .collect::<Vec<Calendar>>();
+    let calendars = join_all(
+        found_calendars
+            .into_iter()
+            .map(|new_cal| Calendar::create_or_update(conn.clone(), new_cal)),
+    )
+    .await
+    .into_iter()
+    .flatten()
+    .collect::<Vec<Calendar>>();
     Ok(calendars)
 }
@@ -1,163 +1,380 @@
 use std::str::FromStr;
 
 use crate::{
+    app_state::AppState,
     caldav::Caldav,
     calendar_items::{
         DisplayUpsertInfo,
+        event_date::EventDateOption,
+        event_parent::EventParent,
         event_status::EventStatus,
+        event_type::EventType,
         event_upsert::EventUpsertInfo,
         input_traits::{ExtractedInput, FromUserInput},
     },
     commands::{
+        calendar::delete_on_server,
+        conflicts::{conflicts_with, flag_conflicts},
+        day_summary::{
+            EventsForDay, TodosForDay, budget_warning, events_on_day, summary_of, todos_on_day,
+        },
         errors::CommandError,
         extended_event::ExtendedEvent,
         extended_todo::{ExtendedTodo, UnscheduledTodo},
+        slots::{FilledInput, fill_slot_query},
+        templates::expand_template,
     },
     db_conn::DbConn,
     models::{
-        Calendar, VCmp, VCmpBuilder,
-        model_traits::{ById, ListForDayOrRecurring},
+        Calendar, VCmp, VCmpBuilder, VCmpId,
+        day_summary::DaySummary,
+        journal::JournalEntry,
+        model_traits::{ListForDayOrRecurring, SetSyncedAt},
+        patch::VCmpPatch,
+        settings::Settings,
+        todo_occurrence::TodoOccurrence,
+        trash::TrashEntry,
         vevent::VEvent,
         vtodo::VTodo,
     },
     util::{DateTimeStr, Href},
 };
 use anyhow::anyhow;
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use diesel::{Connection, SqliteConnection};
+use now::DateTimeNow;
+use tauri::State;
 use uuid::Uuid;
 
 #[tauri::command()]
 #[specta::specta]
 pub async fn list_unscheduled_todos(
+    state: State<'_, AppState>,
     include_done: bool,
 ) -> Result<Vec<UnscheduledTodo>, CommandError> {
-    let conn = DbConn::new().await?;
+    let conn = state.db.clone();
 
-    let todos = VTodo::list_unscheduled(conn, include_done).await?;
+    let todos = VTodo::list_unscheduled(conn.clone(), include_done).await?;
+    let progress =
+        VTodo::subtask_progress(conn, todos.iter().map(|t| t.todo.uid.clone()).collect()).await?;
+    let todos = todos
+        .into_iter()
+        .map(|t| UnscheduledTodo {
+            progress: progress.get(&t.todo.uid).cloned(),
+            ..t
+        })
+        .collect::<Vec<UnscheduledTodo>>();
     Ok(todos)
 }
 
+/// Lists the scheduled todos whose day passed without being done
+#[tauri::command()]
+#[specta::specta]
+pub async fn list_overdue_todos(
+    state: State<'_, AppState>,
+    date_of_input_str: String,
+) -> Result<Vec<UnscheduledTodo>, CommandError> {
+    let conn = state.db.clone();
+    let parsed_date: DateTime<FixedOffset> = DateTimeStr(date_of_input_str).try_into()?;
+
+    let todos = VTodo::list_overdue(conn.clone(), parsed_date.beginning_of_day().to_utc()).await?;
+    let progress =
+        VTodo::subtask_progress(conn, todos.iter().map(|t| t.uid.clone()).collect()).await?;
+    let todos = todos
+        .iter()
+        .map(|t| UnscheduledTodo {
+            progress: progress.get(&t.uid).cloned(),
+            ..UnscheduledTodo::on_day(t, &parsed_date)
+        })
+        .collect::<Vec<UnscheduledTodo>>();
+    Ok(todos)
+}
+
+/// Lists the subtasks of a todo
+#[tauri::command()]
+#[specta::specta]
+pub async fn list_subtasks(
+    state: State<'_, AppState>,
+    vcmp_id: VCmpId,
+) -> Result<Vec<UnscheduledTodo>, CommandError> {
+    let conn = state.db.clone();
+    let Some(VCmp::Todo(parent)) = VCmp::by_id(conn.clone(), vcmp_id).await? else {
+        return Err(anyhow!("No todo with id {vcmp_id}").into());
+    };
+
+    let subtasks = VTodo::list_subtasks(conn.clone(), parent.uid).await?;
+    let progress =
+        VTodo::subtask_progress(conn, subtasks.iter().map(|t| t.uid.clone()).collect()).await?;
+    let now = Utc::now();
+    let subtasks = subtasks
+        .iter()
+        .map(|t| UnscheduledTodo {
+            progress: progress.get(&t.uid).cloned(),
+            ..UnscheduledTodo::on_day(t, &now)
+        })
+        .collect::<Vec<UnscheduledTodo>>();
+    Ok(subtasks)
+}
+
 #[tauri::command()]
 #[specta::specta]
 pub async fn set_vcmp_status(
-    vcmp: i32,
+    state: State<'_, AppState>,
+    vcmp_id: VCmpId,
     status: String,
     date_of_change: String,
 ) -> Result<(), CommandError> {
-    let conn = DbConn::new().await?;
+    let conn = state.db.clone();
 
     let updated_at: DateTime<FixedOffset> = DateTimeStr(date_of_change).try_into()?;
     let status = EventStatus::from_str(status.as_ref())?;
-    let vevent = VEvent::by_id(conn.clone(), vcmp).await?;
+    let before = VCmp::by_id(conn.clone(), vcmp_id)
+        .await?
+        .ok_or(anyhow!("No cmp with id {vcmp_id}"))?;
+    // The status of the series would apply to every occurrence, the recurring events that
+    // aren't reminders can still be cancelled as a whole
+    if before.has_rrule()
+        && (matches!(before, VCmp::Todo(_)) || before.get_event_type() == EventType::Reminder)
+    {
+        return Err(anyhow!(
+            "{} repeats, set the status of the occurrence of a day with set_occurrence_status",
+            before.get_summary()
+        )
+        .into());
+    }
 
-    if vevent.is_some() {
-        VEvent::update_status_by_id(conn.clone(), vcmp, status).await?;
-    } else {
-        VTodo::update_status_by_id(conn, vcmp, status, updated_at).await?;
-    };
+    conn.run(move |conn| {
+        conn.transaction::<_, anyhow::Error, _>(|conn| {
+            let after = match vcmp_id {
+                VCmpId::Event(id) => {
+                    VEvent::update_status_by_id_in(conn, id, status)?.map(VCmp::Event)
+                }
+                VCmpId::Todo(id) => {
+                    VTodo::update_status_by_id_in(conn, id, status, updated_at)?.map(VCmp::Todo)
+                }
+            };
+            JournalEntry::record_in(conn, Some(&before), after.as_ref())
+        })
+    })
+    .await?;
     Ok(())
 }
 
+/// Sets the status of the occurrence of a recurring todo or reminder on the day of `day`,
+/// the series stays as is
 #[tauri::command()]
 #[specta::specta]
-pub async fn list_events_for_day(datetime: String) -> Result<Vec<ExtendedEvent>, CommandError> {
-    let conn = DbConn::new().await?;
+pub async fn set_occurrence_status(
+    state: State<'_, AppState>,
+    vcmp_id: VCmpId,
+    day: String,
+    status: String,
+    date_of_change: String,
+) -> Result<(), CommandError> {
+    let conn = state.db.clone();
+    let day: DateTime<FixedOffset> = DateTimeStr(day).try_into()?;
+    let updated_at: DateTime<FixedOffset> = DateTimeStr(date_of_change).try_into()?;
+    let status = EventStatus::from_str(status.as_ref())?;
+    let vcmp = VCmp::by_id(conn.clone(), vcmp_id)
+        .await?
+        .ok_or(anyhow!("No cmp with id {vcmp_id}"))?;
+    TodoOccurrence::set_status(conn, &vcmp, status, &day, updated_at).await?;
+    Ok(())
+}
+
+#[tauri::command()]
+#[specta::specta]
+pub async fn list_events_for_day(
+    state: State<'_, AppState>,
+    datetime: String,
+) -> Result<EventsForDay, CommandError> {
+    let conn = state.db.clone();
     let parsed: DateTime<FixedOffset> = DateTimeStr(datetime).try_into()?;
 
-    let events = VEvent::list_for_day_or_recurring(conn, parsed).await?;
-    let events = events
+    let events = VEvent::list_for_day_or_recurring(conn.clone(), parsed).await?;
+    let occurrences = TodoOccurrence::for_uids_between(
+        conn.clone(),
+        events
+            .iter()
+            .filter(|e| e.has_rrule)
+            .map(|e| e.uid.clone())
+            .collect(),
+        parsed.beginning_of_day().to_utc(),
+        parsed.end_of_day().to_utc(),
+    )
+    .await?;
+    let mut events = events
         .iter()
         .filter_map(|event| ExtendedEvent::on_day(event, &parsed))
+        .map(|e| e.with_occurrence(&occurrences))
         .collect::<Vec<ExtendedEvent>>();
+    flag_conflicts(&mut events);
+    let settings = Settings::load(conn.clone()).await?;
+    let todos = todos_on_day(conn, parsed).await?;
+    let summary = summary_of(&parsed, &settings, &events, &todos);
 
-    Ok(events)
+    Ok(EventsForDay { events, summary })
 }
 
 #[tauri::command(rename_all = "snake_case")]
 #[specta::specta]
-pub async fn list_todos_for_day(datetime: String) -> Result<Vec<ExtendedTodo>, CommandError> {
-    let conn = DbConn::new().await?;
+pub async fn list_todos_for_day(
+    state: State<'_, AppState>,
+    datetime: String,
+) -> Result<TodosForDay, CommandError> {
+    let conn = state.db.clone();
 
     let parsed: DateTime<FixedOffset> = DateTimeStr(datetime).try_into()?;
 
-    let todos = VTodo::list_for_day_or_recurring(conn, parsed).await?;
+    let todos = VTodo::list_for_day_or_recurring(conn.clone(), parsed).await?;
+    let progress =
+        VTodo::subtask_progress(conn.clone(), todos.iter().map(|t| t.uid.clone()).collect())
+            .await?;
+    let occurrences = TodoOccurrence::for_uids_between(
+        conn.clone(),
+        todos
+            .iter()
+            .filter(|t| t.has_rrule)
+            .map(|t| t.uid.clone())
+            .collect(),
+        parsed.beginning_of_day().to_utc(),
+        parsed.end_of_day().to_utc(),
+    )
+    .await?;
     let todos = todos
         .iter()
         .filter_map(|vtodo| ExtendedTodo::on_day(vtodo, &parsed))
+        .map(|t| ExtendedTodo {
+            progress: progress.get(&t.todo.uid).cloned(),
+            ..t.with_occurrence(&occurrences)
+        })
         .collect::<Vec<ExtendedTodo>>();
+    let settings = Settings::load(conn.clone()).await?;
+    let events = events_on_day(conn, parsed).await?;
+    let summary = summary_of(&parsed, &settings, &events, &todos);
 
-    Ok(todos)
+    Ok(TodosForDay { todos, summary })
 }
 
+/// Parses the input to show what would be saved, `editing` is the component the input
+/// replaces when it's an edit
 #[tauri::command()]
 #[specta::specta]
 pub async fn parse_event(
+    state: State<'_, AppState>,
     date_of_input_str: String,
     component_input: String,
+    editing: Option<VCmpId>,
 ) -> Result<DisplayUpsertInfo, CommandError> {
     let parsed_date: DateTime<FixedOffset> = DateTimeStr(date_of_input_str).try_into()?;
+    let expanded_input = expand_template(state.db.clone(), &component_input).await?;
+    let component_input = expanded_input.clone().unwrap_or(component_input);
+    let FilledInput {
+        input: component_input,
+        warning,
+    } = fill_slot_query(state.db.clone(), parsed_date, &component_input).await?;
 
     let ExtractedInput(data, _) =
         EventUpsertInfo::extract_from_input(parsed_date, &component_input)?.into();
-    Ok(data.into())
+    let budget_warning = budget_warning(state.db.clone(), &data, editing).await?;
+    let conflicts = conflicts_with(state.db.clone(), &data, editing).await?;
+    Ok(DisplayUpsertInfo {
+        budget_warning,
+        conflicts,
+        expanded_input,
+        warning,
+        ..data.into()
+    })
 }
 
+/// Creates the component, returns the summary of its day when it puts that day over the
+/// load budget
 #[tauri::command()]
 #[specta::specta]
 pub async fn create_component(
+    state: State<'_, AppState>,
     calendar_id: i32,
     date_of_input_str: String,
     component_input: String,
-) -> Result<(), CommandError> {
-    let conn = DbConn::new().await?;
-
-    let (_, calendar) = Calendar::by_id_with_server(conn.clone(), calendar_id).await?;
+) -> Result<Option<DaySummary>, CommandError> {
+    let conn = state.db.clone();
     let parsed_date: DateTime<FixedOffset> = DateTimeStr(date_of_input_str).try_into()?;
+    let component_input = expand_template(conn.clone(), &component_input)
+        .await?
+        .unwrap_or(component_input);
+    let filled = fill_slot_query(conn.clone(), parsed_date, &component_input).await?;
+    if let Some(warning) = filled.warning {
+        return Err(anyhow!(warning).into());
+    }
+    let component_input = filled.input;
 
     let ExtractedInput(data, _) =
         EventUpsertInfo::extract_from_input(parsed_date, &component_input)?.into();
+    let budget_warning = budget_warning(conn.clone(), &data, None).await?;
+    create_from_upsert(conn, calendar_id, &data).await?;
+
+    Ok(budget_warning)
+}
+
+/// Creates a component in the calendar and records it in the journal
+pub(crate) async fn create_from_upsert<Tz: TimeZone>(
+    conn: DbConn,
+    calendar_id: i32,
+    data: &EventUpsertInfo<Tz>,
+) -> anyhow::Result<VCmp> {
+    // Subtasks live in the calendar of their parent
+    let parent = find_parent(conn.clone(), data.parent).await?;
+    let calendar_id = parent.as_ref().map_or(calendar_id, |p| p.calendar_id);
+    let (_, calendar) = Calendar::by_id_with_server(conn.clone(), calendar_id).await?;
 
     let uid = Uuid::new_v4().to_string();
 
-    let builder = VCmpBuilder::from(&data)
+    let mut builder = VCmpBuilder::from(data)
         .calendar_id(calendar_id)
         .uid(&uid)
         .calendar_href(Href(calendar.url));
+    if let Some(parent) = parent {
+        builder = builder.parent_uid(parent.uid);
+    }
 
-    builder.build_new()?.create(conn).await?;
-
-    Ok(())
+    let new_vcmp = builder.build_new()?;
+    conn.run(move |conn| {
+        conn.transaction::<_, anyhow::Error, _>(|conn| {
+            let created = new_vcmp.create_in(conn)?;
+            JournalEntry::record_in(conn, None, Some(&created))?;
+            Ok(created)
+        })
+    })
+    .await
 }
 
 #[tauri::command()]
 #[specta::specta]
-pub async fn delete_vcmp(vevent_id: i32) -> Result<(), CommandError> {
-    let conn = DbConn::new().await?;
-    let cmp = VCmp::by_id(conn.clone(), vevent_id)
+pub async fn delete_vcmp(state: State<'_, AppState>, vcmp_id: VCmpId) -> Result<(), CommandError> {
+    let conn = state.db.clone();
+    let cmp = VCmp::by_id(conn.clone(), vcmp_id)
         .await?
-        .ok_or(anyhow!("No cmp with id {vevent_id}"))?;
-
-    let (server, _) = Calendar::by_id_with_server(conn.clone(), cmp.get_calendar_id()).await?;
-    let caldav = Caldav::new(server).await?;
-    // Delete cmp if possible
-    if let Some(href) = cmp.get_href()
-        && let Some(etag) = cmp.get_etag()
-    {
-        caldav.delete_resource(href, etag).await?;
-    }
+        .ok_or(anyhow!("No cmp with id {vcmp_id}"))?;
 
-    cmp.delete(conn).await?;
+    conn.run(move |conn| {
+        conn.transaction::<_, anyhow::Error, _>(|conn| {
+            remove_vcmp_in(conn, &cmp)?;
+            JournalEntry::record_in(conn, Some(&cmp), None)
+        })
+    })
+    .await?;
     Ok(())
 }
 
 #[tauri::command()]
 #[specta::specta]
 pub async fn update_vcmp(
-    vcmp_id: i32,
+    state: State<'_, AppState>,
+    vcmp_id: VCmpId,
     date_of_input_str: String,
     component_input: String,
 ) -> Result<(), CommandError> {
-    let conn = DbConn::new().await?;
+    let conn = state.db.clone();
     let parsed_date: DateTime<FixedOffset> = DateTimeStr(date_of_input_str).try_into()?;
 
     let ExtractedInput(data, _) =
@@ -165,8 +382,232 @@ pub async fn update_vcmp(
 
     let vcmp = VCmp::by_id(conn.clone(), vcmp_id).await?;
     let vcmp = vcmp.ok_or(anyhow!("No cmp with id {vcmp_id}"))?;
-    let updated = vcmp.apply_upsert(&component_input, data, parsed_date, Some(true))?;
-    updated.update(conn).await?;
+    let parent = find_parent(conn.clone(), data.parent).await?;
+    let mut updated = vcmp.apply_upsert(&component_input, data, parsed_date, Some(true))?;
+    match (parent, &mut updated) {
+        (Some(_), VCmp::Event(_)) => {
+            return Err(anyhow!("Only todos can be subtasks").into());
+        }
+        (Some(parent), VCmp::Todo(vtodo)) => {
+            if parent.id == vtodo.id {
+                return Err(anyhow!("A todo can't be a subtask of itself").into());
+            }
+            let ancestors = VTodo::ancestor_uids(conn.clone(), parent.uid.clone()).await?;
+            if ancestors.contains(&vtodo.uid) {
+                return Err(anyhow!(
+                    "{} can't be a subtask of one of its own subtasks",
+                    vtodo.summary
+                )
+                .into());
+            }
+            // Subtasks live in the calendar of their parent
+            vtodo.calendar_id = parent.calendar_id;
+            vtodo.parent_uid = Some(parent.uid);
+        }
+        // The `^id` was removed from the input
+        (None, VCmp::Todo(vtodo)) => vtodo.parent_uid = None,
+        (None, VCmp::Event(_)) => {}
+    }
+    if convert_kind(conn.clone(), &vcmp, &updated).await?
+        || move_calendar(conn.clone(), &vcmp, &updated).await?
+    {
+        return Ok(());
+    }
+    update_journaled(conn, &vcmp, updated).await?;
+
+    Ok(())
+}
 
+/// Changes only the fields set in the patch, the other ones keep their value
+#[tauri::command()]
+#[specta::specta]
+pub async fn update_vcmp_fields(
+    state: State<'_, AppState>,
+    vcmp_id: VCmpId,
+    date_of_change: String,
+    patch: VCmpPatch,
+) -> Result<(), CommandError> {
+    let conn = state.db.clone();
+    let updated_at: DateTime<FixedOffset> = DateTimeStr(date_of_change).try_into()?;
+
+    let vcmp = VCmp::by_id(conn.clone(), vcmp_id)
+        .await?
+        .ok_or(anyhow!("No cmp with id {vcmp_id}"))?;
+    let calendar_url = match patch.calendar_id {
+        Some(calendar_id) if calendar_id != vcmp.get_calendar_id() => {
+            let (_, calendar) = Calendar::by_id_with_server(conn.clone(), calendar_id).await?;
+            Some(calendar.url)
+        }
+        _ => None,
+    };
+    let patched = patch.apply_to(&vcmp, calendar_url.as_deref(), updated_at)?;
+    if convert_kind(conn.clone(), &vcmp, &patched).await?
+        || move_calendar(conn.clone(), &vcmp, &patched).await?
+    {
+        return Ok(());
+    }
+    let (before, saved) = (vcmp.clone(), patched.clone());
+    conn.run(move |conn| {
+        conn.transaction::<_, anyhow::Error, _>(|conn| {
+            patch.save_in(conn, &saved)?;
+            JournalEntry::record_in(conn, Some(&before), Some(&saved))
+        })
+    })
+    .await?;
     Ok(())
 }
+
+/// Moves an edited component to its calendar when it left the one of `before`. The copy
+/// in the new calendar is a new component with the same uid, the old one is removed as
+/// if it was deleted so its resource is deleted from the server on the next sync.
+/// Returns whether it was moved, otherwise nothing is saved
+async fn move_calendar(conn: DbConn, before: &VCmp, edited: &VCmp) -> anyhow::Result<bool> {
+    if before.get_calendar_id() == edited.get_calendar_id() {
+        return Ok(false);
+    }
+    let (_, calendar) = Calendar::by_id_with_server(conn.clone(), edited.get_calendar_id()).await?;
+    let moved = edited
+        .into_new_resource(&calendar.url)?
+        .create_in_place_of(conn.clone(), before)
+        .await?;
+    let moved_id = moved.get_id();
+    if let Err(e) = push_vcmp(conn, moved).await {
+        log::warn!("Moved {moved_id} will be pushed on the next sync: {e}");
+    }
+    Ok(true)
+}
+
+/// Moves an edited component to the other table when its type changed to one that
+/// belongs there, like a task turned into an event. The uid and the fields are kept and
+/// the old resource is replaced on the server right away, or on the next sync when that
+/// fails. Returns whether it was moved, otherwise nothing is saved
+async fn convert_kind(conn: DbConn, before: &VCmp, edited: &VCmp) -> anyhow::Result<bool> {
+    if before.get_event_type() == edited.get_event_type() || !edited.belongs_in_other_table() {
+        return Ok(false);
+    }
+    let (_, calendar) = Calendar::by_id_with_server(conn.clone(), edited.get_calendar_id()).await?;
+    let converted = edited
+        .into_new_resource(&calendar.url)?
+        .create_in_place_of(conn.clone(), before)
+        .await?;
+    if let Err(e) = push_conversion(conn, before, converted).await {
+        log::warn!(
+            "The new type of {} will reach the server on the next sync: {e}",
+            before.get_id()
+        );
+    }
+    Ok(true)
+}
+
+/// Deletes the old resource before creating the new one, since both have the same uid
+async fn push_conversion(conn: DbConn, replaced: &VCmp, converted: VCmp) -> anyhow::Result<()> {
+    if replaced.is_on_server() {
+        delete_from_server(conn.clone(), replaced).await?;
+        replaced.delete(conn.clone()).await?;
+    }
+    push_vcmp(conn, converted).await
+}
+
+/// Deletes the resource of the component from the server right away, if it has one
+async fn delete_from_server(conn: DbConn, vcmp: &VCmp) -> anyhow::Result<()> {
+    if !vcmp.is_on_server() {
+        return Ok(());
+    }
+    let (server, calendar) = Calendar::by_id_with_server(conn, vcmp.get_calendar_id()).await?;
+    let caldav = Caldav::new(server).await?;
+    delete_on_server(&caldav, &Href(calendar.url), vcmp).await
+}
+
+/// Moves the component to the date in `to`, like "tomorrow" or "next week", counting it
+/// as postponed when it's later than before
+#[tauri::command()]
+#[specta::specta]
+pub async fn postpone_vcmp(
+    state: State<'_, AppState>,
+    vcmp_id: VCmpId,
+    date_of_input_str: String,
+    to: String,
+) -> Result<(), CommandError> {
+    let conn = state.db.clone();
+    let parsed_date: DateTime<FixedOffset> = DateTimeStr(date_of_input_str).try_into()?;
+
+    let ExtractedInput(EventDateOption(date_info), _) =
+        EventDateOption::extract_from_input(parsed_date, &to)?.into();
+    let date_info = date_info.ok_or(anyhow!("No date found in \"{to}\""))?;
+
+    let vcmp = VCmp::by_id(conn.clone(), vcmp_id)
+        .await?
+        .ok_or(anyhow!("No cmp with id {vcmp_id}"))?;
+    let postponed = vcmp.postpone_to(&date_info, &parsed_date)?;
+    update_journaled(conn, &vcmp, postponed).await?;
+
+    Ok(())
+}
+
+/// Lists the pending todos that were postponed the most
+#[tauri::command()]
+#[specta::specta]
+pub async fn list_most_postponed(
+    state: State<'_, AppState>,
+    limit: i32,
+) -> Result<Vec<UnscheduledTodo>, CommandError> {
+    let conn = state.db.clone();
+    let now = Utc::now();
+    let todos = VTodo::list_most_postponed(conn, limit.into())
+        .await?
+        .iter()
+        .map(|t| UnscheduledTodo::on_day(t, &now))
+        .collect::<Vec<UnscheduledTodo>>();
+    Ok(todos)
+}
+
+/// Finds the todo referenced with `^id` in the input
+async fn find_parent(conn: DbConn, parent: EventParent) -> anyhow::Result<Option<VTodo>> {
+    let Some(parent_id) = parent.0 else {
+        return Ok(None);
+    };
+    match VCmp::by_id(conn, VCmpId::Todo(parent_id)).await? {
+        Some(VCmp::Todo(vtodo)) => Ok(Some(vtodo)),
+        _ => Err(anyhow!("No todo with id {parent_id} to add the subtask to")),
+    }
+}
+
+/// Moves the component to the trash and deletes it locally, if it exists on the
+/// server it is only hidden and the delete is sent on the next sync
+pub(crate) async fn remove_vcmp(conn: DbConn, cmp: &VCmp) -> anyhow::Result<()> {
+    let cmp = cmp.clone();
+    conn.run(move |conn| conn.transaction::<_, anyhow::Error, _>(|conn| remove_vcmp_in(conn, &cmp)))
+        .await
+}
+
+/// Same as [`remove_vcmp`] on a connection that may be in a transaction
+pub(crate) fn remove_vcmp_in(conn: &mut SqliteConnection, cmp: &VCmp) -> anyhow::Result<()> {
+    TrashEntry::put_in(conn, cmp)?;
+    cmp.remove_in(conn)?;
+    Ok(())
+}
+
+/// Writes the edited component and its journal entry in a single transaction
+async fn update_journaled(conn: DbConn, before: &VCmp, edited: VCmp) -> anyhow::Result<VCmp> {
+    let before = before.clone();
+    conn.run(move |conn| {
+        conn.transaction::<_, anyhow::Error, _>(|conn| {
+            let updated = edited.update_in(conn)?;
+            JournalEntry::record_in(conn, Some(&before), Some(&updated))?;
+            Ok(updated)
+        })
+    })
+    .await
+}
+
+/// Creates the component on the server, on failure it stays unsynced
+/// and will be pushed on the next sync
+pub(crate) async fn push_vcmp(conn: DbConn, cmp: VCmp) -> anyhow::Result<()> {
+    let (server, calendar) =
+        Calendar::by_id_with_server(conn.clone(), cmp.get_calendar_id()).await?;
+    let caldav = Caldav::new(server).await?;
+    let (_, etag) = caldav
+        .create_component(&Href(calendar.url), cmp.get_uid(), &cmp.to_push_calendar())
+        .await?;
+    cmp.set_synced_at(conn, etag, Utc::now()).await
+}
@@ -0,0 +1,207 @@
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use tauri::State;
+
+use crate::{
+    app_state::AppState,
+    calendar_items::event_upsert::EventUpsertInfo,
+    commands::{errors::CommandError, extended_event::ExtendedEvent},
+    db_conn::DbConn,
+    models::{
+        Calendar, VCmpId,
+        conflict::{Conflict, ConflictingItem},
+        free_busy::{is_transparent, occurrences},
+        vevent::VEvent,
+    },
+    util::DateTimeStr,
+};
+
+/// Overlapping events between `from` and `to` across all the calendars, recurring
+/// events included
+#[tauri::command()]
+#[specta::specta]
+pub async fn list_conflicts(
+    state: State<'_, AppState>,
+    from: String,
+    to: String,
+) -> Result<Vec<Conflict>, CommandError> {
+    let conn = state.db.clone();
+    let from: DateTime<FixedOffset> = DateTimeStr(from).try_into()?;
+    let to: DateTime<FixedOffset> = DateTimeStr(to).try_into()?;
+    let calendar_ids = all_calendar_ids(conn.clone()).await?;
+    let items = occurrences_between(conn, calendar_ids, from.to_utc(), to.to_utc()).await?;
+    Ok(find_conflicts(items))
+}
+
+/// Existing items the new one would collide with, only its first occurrence is checked.
+/// `editing` is the item the input edits, which doesn't collide with itself
+pub async fn conflicts_with<Tz: TimeZone>(
+    conn: DbConn,
+    data: &EventUpsertInfo<Tz>,
+    editing: Option<VCmpId>,
+) -> anyhow::Result<Vec<ConflictingItem>> {
+    let Some(date_info) = &data.date_info.0 else {
+        return Ok(vec![]);
+    };
+    let start = date_info.start.to_utc();
+    let end = date_info.get_end_or_default(data.event_type).to_utc();
+    let calendar_ids = all_calendar_ids(conn.clone()).await?;
+    let items = occurrences_between(conn, calendar_ids, start, end).await?;
+    Ok(items
+        .into_iter()
+        .filter(|item| Some(item.vcmp_id) != editing)
+        .collect())
+}
+
+/// Sets the conflicts of each event with the other ones of the list
+pub fn flag_conflicts(events: &mut [ExtendedEvent]) {
+    let times = events
+        .iter()
+        .map(|e| (e.vcmp_id, e.starts_at, e.ends_at, is_transparent(&e.event)))
+        .collect::<Vec<(VCmpId, DateTime<Utc>, DateTime<Utc>, bool)>>();
+    for (i, event) in events.iter_mut().enumerate() {
+        let (_, starts_at, ends_at, transparent) = times[i];
+        if transparent {
+            continue;
+        }
+        event.conflicts = times
+            .iter()
+            .enumerate()
+            .filter(|(j, (_, s, e, t))| *j != i && !t && *s < ends_at && *e > starts_at)
+            .map(|(_, (id, ..))| *id)
+            .collect();
+    }
+}
+
+async fn all_calendar_ids(conn: DbConn) -> anyhow::Result<Vec<i32>> {
+    let calendars = Calendar::list_all(conn).await?;
+    Ok(calendars.iter().map(|c| c.id).collect())
+}
+
+/// Occurrences of the events of the calendars overlapping `start` to `end`, the
+/// transparent ones are left out
+async fn occurrences_between(
+    conn: DbConn,
+    calendar_ids: Vec<i32>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> anyhow::Result<Vec<ConflictingItem>> {
+    let events =
+        VEvent::list_in_calendars_between_or_recurring(conn, calendar_ids, start, end).await?;
+    let items = events
+        .iter()
+        .filter(|e| !is_transparent(e))
+        .flat_map(|e| {
+            occurrences(e, start, end)
+                .into_iter()
+                .map(|(starts_at, ends_at)| ConflictingItem {
+                    vcmp_id: VCmpId::Event(e.id),
+                    summary: e.summary.clone(),
+                    starts_at,
+                    ends_at,
+                })
+        })
+        .collect();
+    Ok(items)
+}
+
+/// Every pair of items that overlap, items only touching each other don't conflict
+fn find_conflicts(mut items: Vec<ConflictingItem>) -> Vec<Conflict> {
+    items.sort_by_key(|i| (i.starts_at, i.ends_at));
+    let mut conflicts = Vec::new();
+    for (i, first) in items.iter().enumerate() {
+        for second in items[i + 1..]
+            .iter()
+            .take_while(|second| second.starts_at < first.ends_at)
+        {
+            conflicts.push(Conflict {
+                first: first.clone(),
+                second: second.clone(),
+            });
+        }
+    }
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+    use crate::{
+        calendar_items::input_traits::{ExtractedInput, FromUserInput},
+        test_utils::{create_event, new_opaque_block, new_single_event},
+    };
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, day, hour, 0, 0).unwrap()
+    }
+
+    fn item(id: i32, starts_at: DateTime<Utc>, ends_at: DateTime<Utc>) -> ConflictingItem {
+        ConflictingItem {
+            vcmp_id: VCmpId::Event(id),
+            summary: id.to_string(),
+            starts_at,
+            ends_at,
+        }
+    }
+
+    #[test]
+    fn test_find_conflicts() {
+        let conflicts = find_conflicts(vec![
+            item(3, at(3, 15), at(3, 16)),
+            item(1, at(3, 9), at(3, 12)),
+            item(2, at(3, 10), at(3, 11)),
+            item(4, at(3, 12), at(3, 13)),
+        ]);
+        let pairs = conflicts
+            .iter()
+            .map(|c| (c.first.vcmp_id, c.second.vcmp_id))
+            .collect::<Vec<(VCmpId, VCmpId)>>();
+        // Touching at 12:00 is not a conflict
+        assert_eq!(pairs, vec![(VCmpId::Event(1), VCmpId::Event(2))]);
+    }
+
+    #[test]
+    fn test_recurring_events_conflict() {
+        let conn = DbConn::in_memory().unwrap();
+        // Recurring block from 13:00 to 16:00 every weekday, taking time
+        let block = create_event(conn.clone(), new_opaque_block());
+        let meeting = create_event(
+            conn.clone(),
+            new_single_event("meeting", at(4, 15), at(4, 17)),
+        );
+
+        let items = block_on(occurrences_between(conn, vec![1], at(3, 0), at(5, 0))).unwrap();
+        let conflicts = find_conflicts(items)
+            .iter()
+            .map(|c| (c.first.vcmp_id, c.second.vcmp_id, c.second.starts_at))
+            .collect::<Vec<(VCmpId, VCmpId, DateTime<Utc>)>>();
+        assert_eq!(
+            conflicts,
+            vec![(
+                VCmpId::Event(block.id),
+                VCmpId::Event(meeting.id),
+                at(4, 15)
+            )]
+        );
+    }
+
+    #[test]
+    fn test_edited_event_does_not_conflict_with_itself() {
+        let conn = DbConn::in_memory().unwrap();
+        let block = create_event(conn.clone(), new_opaque_block());
+        let ExtractedInput(data, _) =
+            EventUpsertInfo::extract_from_input(at(3, 9), "@block Work today at 14")
+                .unwrap()
+                .into();
+
+        let conflicts = block_on(conflicts_with(conn.clone(), &data, None)).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        let editing = Some(VCmpId::Event(block.id));
+        assert!(
+            block_on(conflicts_with(conn, &data, editing))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use chrono::{DateTime, FixedOffset, TimeZone};
+
+use crate::{
+    calendar_items::event_upsert::EventUpsertInfo,
+    commands::{extended_event::ExtendedEvent, extended_todo::ExtendedTodo},
+    db_conn::DbConn,
+    models::{
+        VCmpId, day_summary::DaySummary, free_busy::Interval, model_traits::ListForDayOrRecurring,
+        settings::Settings, vevent::VEvent, vtodo::VTodo,
+    },
+};
+
+#[derive(Clone, Debug, serde::Serialize, specta::Type)]
+pub struct EventsForDay {
+    pub events: Vec<ExtendedEvent>,
+    pub summary: DaySummary,
+}
+
+#[derive(Clone, Debug, serde::Serialize, specta::Type)]
+pub struct TodosForDay {
+    pub todos: Vec<ExtendedTodo>,
+    pub summary: DaySummary,
+}
+
+/// Summary of the day from the events and todos already loaded for it
+pub fn summary_of<Tz: TimeZone>(
+    day: &DateTime<Tz>,
+    settings: &Settings,
+    events: &[ExtendedEvent],
+    todos: &[ExtendedTodo],
+) -> DaySummary {
+    let scheduled_load = events.iter().map(|e| e.event.load).sum::<i32>()
+        + todos.iter().map(|t| t.todo.load).sum::<i32>();
+    let busy = events
+        .iter()
+        .map(|e| (e.starts_at, e.ends_at))
+        .collect::<Vec<Interval>>();
+    DaySummary::new(day, settings, scheduled_load, &busy)
+}
+
+pub(crate) async fn events_on_day(
+    conn: DbConn,
+    day: DateTime<FixedOffset>,
+) -> anyhow::Result<Vec<ExtendedEvent>> {
+    let events = VEvent::list_for_day_or_recurring(conn, day)
+        .await?
+        .iter()
+        .filter_map(|e| ExtendedEvent::on_day(e, &day))
+        .collect();
+    Ok(events)
+}
+
+pub(crate) async fn todos_on_day(
+    conn: DbConn,
+    day: DateTime<FixedOffset>,
+) -> anyhow::Result<Vec<ExtendedTodo>> {
+    let todos = VTodo::list_for_day_or_recurring(conn, day)
+        .await?
+        .iter()
+        .filter_map(|t| ExtendedTodo::on_day(t, &day))
+        .collect();
+    Ok(todos)
+}
+
+pub async fn summary_for_day(
+    conn: DbConn,
+    day: DateTime<FixedOffset>,
+) -> anyhow::Result<DaySummary> {
+    let settings = Settings::load(conn.clone()).await?;
+    let events = events_on_day(conn.clone(), day).await?;
+    let todos = todos_on_day(conn, day).await?;
+    Ok(summary_of(&day, &settings, &events, &todos))
+}
+
+/// Summary of the day the item goes to, only when adding it puts that day over budget.
+/// `editing` is the item the input edits, its current load and time don't count
+pub async fn budget_warning<Tz: TimeZone>(
+    conn: DbConn,
+    data: &EventUpsertInfo<Tz>,
+    editing: Option<VCmpId>,
+) -> anyhow::Result<Option<DaySummary>> {
+    let Some(date_info) = &data.date_info.0 else {
+        return Ok(None);
+    };
+    let day = date_info.start.fixed_offset();
+    let settings = Settings::load(conn.clone()).await?;
+    let events = events_on_day(conn.clone(), day)
+        .await?
+        .into_iter()
+        .filter(|e| Some(e.vcmp_id) != editing)
+        .collect::<Vec<ExtendedEvent>>();
+    let todos = todos_on_day(conn, day)
+        .await?
+        .into_iter()
+        .filter(|t| Some(t.vcmp_id) != editing)
+        .collect::<Vec<ExtendedTodo>>();
+    let summary = summary_of(&day, &settings, &events, &todos).with_added_load(data.load);
+    Ok(summary.over_budget.then_some(summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+    use crate::{
+        calendar_items::input_traits::{ExtractedInput, FromUserInput},
+        models::vevent::NewVEvent,
+        test_utils::{create_event, new_event},
+    };
+
+    #[test]
+    fn test_summary_with_block() {
+        let conn = DbConn::in_memory().unwrap();
+        // Recurring block from 13:00 to 16:00 every weekday
+        let block = NewVEvent {
+            load: 3,
+            ..new_event("basic.ics")
+        };
+        create_event(conn.clone(), block);
+        let settings = Settings {
+            daily_load_budget: 4,
+            ..Default::default()
+        };
+        block_on(settings.save(conn.clone())).unwrap();
+
+        let monday = FixedOffset::east_opt(0)
+            .unwrap()
+            .with_ymd_and_hms(2024, 6, 3, 9, 0, 0)
+            .unwrap();
+        let summary = block_on(summary_for_day(conn, monday)).unwrap();
+        assert_eq!(summary.scheduled_load, 3);
+        assert_eq!(summary.load_budget, 4);
+        // From 8:00 to 20:00 minus the three hours of the block
+        assert_eq!(summary.free_minutes, 9 * 60);
+        assert!(!summary.over_budget);
+        assert!(summary.with_added_load(2).over_budget);
+    }
+
+    #[test]
+    fn test_edited_item_load_does_not_count_twice() {
+        let conn = DbConn::in_memory().unwrap();
+        // Recurring block from 13:00 to 16:00 every weekday
+        let block = NewVEvent {
+            load: 3,
+            ..new_event("basic.ics")
+        };
+        let block = create_event(conn.clone(), block);
+        let settings = Settings {
+            daily_load_budget: 4,
+            ..Default::default()
+        };
+        block_on(settings.save(conn.clone())).unwrap();
+
+        let monday = FixedOffset::east_opt(0)
+            .unwrap()
+            .with_ymd_and_hms(2024, 6, 3, 9, 0, 0)
+            .unwrap();
+        let ExtractedInput(mut data, _) =
+            EventUpsertInfo::extract_from_input(monday, "@block Work today at 14")
+                .unwrap()
+                .into();
+        data.load = 3;
+
+        let warning = block_on(budget_warning(conn.clone(), &data, None)).unwrap();
+        assert_eq!(warning.map(|s| s.scheduled_load), Some(6));
+        let editing = Some(VCmpId::Event(block.id));
+        assert_eq!(
+            block_on(budget_warning(conn, &data, editing)).unwrap(),
+            None
+        );
+    }
+}
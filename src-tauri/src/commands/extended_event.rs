@@ -1,15 +1,25 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, TimeZone, Utc};
 use now::DateTimeNow;
 
 use crate::{
     calendar_items::input_traits::ToUserInput,
-    models::vevent::{VEvent, VEventTrait},
+    models::{
+        VCmpId,
+        todo_occurrence::TodoOccurrence,
+        vevent::{VEvent, VEventTrait},
+    },
 };
 
 #[derive(Clone, Debug, serde::Serialize, specta::Type)]
 pub struct ExtendedEvent {
+    /// Id to reference the event in other commands
+    pub vcmp_id: VCmpId,
     /// Date when the extended event was calculated
     pub query_date: DateTime<Utc>,
+    /// For recurring events with a status on this occurrence, like the reminders followed
+    /// as habits, the status is the one of this occurrence
     pub event: VEvent,
     /// The start date of the event, if recurrent the value for the current query
     pub starts_at: DateTime<Utc>,
@@ -17,6 +27,10 @@ pub struct ExtendedEvent {
     pub ends_at: DateTime<Utc>,
     pub natural_recurrence: Option<String>,
     pub natural_string: String,
+    /// Other events taking the same time, set by [`flag_conflicts`]
+    ///
+    /// [`flag_conflicts`]: crate::commands::conflicts::flag_conflicts
+    pub conflicts: Vec<VCmpId>,
 }
 
 impl ExtendedEvent {
@@ -32,15 +46,35 @@ impl ExtendedEvent {
         let (starts_at, ends_at) = event.get_start_end_for_date(&base);
         if starts_at > base && starts_at < query_date.end_of_day() {
             Some(Self {
+                vcmp_id: VCmpId::Event(event.id),
                 query_date: query_date.to_utc(),
                 event: event.clone(),
                 starts_at: starts_at.to_utc(),
                 ends_at: ends_at.to_utc(),
                 natural_recurrence: None,
                 natural_string: event.to_input(query_date),
+                conflicts: vec![],
             })
         } else {
             None
         }
     }
+
+    /// Same event with the status of its occurrence when it has one of its own, the other
+    /// occurrences keep the status of the series
+    pub fn with_occurrence(
+        self,
+        occurrences: &HashMap<(String, DateTime<Utc>), TodoOccurrence>,
+    ) -> Self {
+        match occurrences.get(&(self.event.uid.clone(), self.starts_at)) {
+            Some(occurrence) if self.event.has_rrule => Self {
+                event: VEvent {
+                    status: occurrence.status,
+                    ..self.event
+                },
+                ..self
+            },
+            _ => self,
+        }
+    }
 }
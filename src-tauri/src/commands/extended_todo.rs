@@ -1,15 +1,24 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, TimeZone, Utc};
 use now::DateTimeNow;
 
 use crate::{
     calendar_items::input_traits::ToUserInput,
-    models::vtodo::{VTodo, VTodoTrait},
+    models::{
+        VCmpId,
+        todo_occurrence::TodoOccurrence,
+        vtodo::{SubtaskProgress, VTodo, VTodoTrait},
+    },
 };
 
 #[derive(Clone, Debug, serde::Serialize, specta::Type)]
 pub struct ExtendedTodo {
+    /// Id to reference the todo in other commands
+    pub vcmp_id: VCmpId,
     /// Date when the extended event was calculated
     pub query_date: DateTime<Utc>,
+    /// For recurring todos the status and completion are the ones of this occurrence
     pub todo: VTodo,
     /// The start date of the event, if recurrent the value for the current query
     pub starts_at: DateTime<Utc>,
@@ -17,6 +26,8 @@ pub struct ExtendedTodo {
     pub ends_at: DateTime<Utc>,
     pub natural_recurrence: Option<String>,
     pub natural_string: String,
+    /// Progress of the subtasks, if it has any
+    pub progress: Option<SubtaskProgress>,
 }
 
 impl ExtendedTodo {
@@ -33,23 +44,48 @@ impl ExtendedTodo {
         let (starts_at, ends_at) = todo.get_start_end_for_date(&base)?;
         if starts_at > base && starts_at < query_date.end_of_day() {
             Some(Self {
+                vcmp_id: VCmpId::Todo(todo.id),
                 query_date: query_date.to_utc(),
                 todo: todo.clone(),
                 starts_at: starts_at.to_utc(),
                 ends_at: ends_at.to_utc(),
                 natural_recurrence: None,
                 natural_string: todo.to_input(query_date),
+                progress: None,
             })
         } else {
             None
         }
     }
+
+    /// Same todo with the status of its occurrence when it has one of its own, the other
+    /// occurrences keep the status of the series
+    pub fn with_occurrence(
+        self,
+        occurrences: &HashMap<(String, DateTime<Utc>), TodoOccurrence>,
+    ) -> Self {
+        match occurrences.get(&(self.todo.uid.clone(), self.starts_at)) {
+            Some(occurrence) if self.todo.has_rrule => Self {
+                todo: VTodo {
+                    status: occurrence.status,
+                    completed: occurrence.completed,
+                    ..self.todo
+                },
+                ..self
+            },
+            _ => self,
+        }
+    }
 }
 
 #[derive(Clone, Debug, serde::Serialize, specta::Type)]
 pub struct UnscheduledTodo {
+    /// Id to reference the todo in other commands
+    pub vcmp_id: VCmpId,
     pub todo: VTodo,
     pub natural_string: String,
+    /// Progress of the subtasks, if it has any
+    pub progress: Option<SubtaskProgress>,
 }
 
 impl UnscheduledTodo {
@@ -57,8 +93,10 @@ impl UnscheduledTodo {
     // It will also help with relative dates for completing in the future
     pub fn on_day<Tz: TimeZone>(todo: &VTodo, query_date: &DateTime<Tz>) -> Self {
         Self {
+            vcmp_id: VCmpId::Todo(todo.id),
             todo: todo.clone(),
             natural_string: todo.to_input(query_date),
+            progress: None,
         }
     }
 }
@@ -0,0 +1,36 @@
+use anyhow::anyhow;
+use chrono::{DateTime, FixedOffset, Utc};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState, calendar_items::event_type::EventType, commands::errors::CommandError,
+    models::free_busy::FreeBusy, util::DateTimeStr,
+};
+
+/// Writes the busy time of the calendars from `from` to `to` as a `VFREEBUSY` file at
+/// `path`, events of the `excluded_types` are left out
+#[tauri::command()]
+#[specta::specta]
+pub async fn export_free_busy(
+    state: State<'_, AppState>,
+    calendar_ids: Vec<i32>,
+    from: String,
+    to: String,
+    excluded_types: Vec<EventType>,
+    path: String,
+) -> Result<(), CommandError> {
+    let from: DateTime<FixedOffset> = DateTimeStr(from).try_into()?;
+    let to: DateTime<FixedOffset> = DateTimeStr(to).try_into()?;
+    let free_busy = FreeBusy::for_range(
+        state.db.clone(),
+        calendar_ids,
+        from.to_utc(),
+        to.to_utc(),
+        &excluded_types,
+    )
+    .await?;
+    let ics = free_busy.to_ics(&Uuid::new_v4().to_string(), Utc::now());
+    std::fs::write(&path, ics).map_err(|e| anyhow!("Could not write {path}: {e}"))?;
+    Ok(())
+}
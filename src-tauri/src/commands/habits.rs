@@ -0,0 +1,20 @@
+use chrono::{DateTime, FixedOffset};
+use tauri::State;
+
+use crate::{
+    app_state::AppState, commands::errors::CommandError, models::habit::Habit, util::DateTimeStr,
+};
+
+/// Streaks and history of the recurring reminders and tasks up to the day of `datetime`,
+/// the completion rate is taken over the `last_occurrences` of each one
+#[tauri::command()]
+#[specta::specta]
+pub async fn list_habits(
+    state: State<'_, AppState>,
+    datetime: String,
+    last_occurrences: u32,
+) -> Result<Vec<Habit>, CommandError> {
+    let now: DateTime<FixedOffset> = DateTimeStr(datetime).try_into()?;
+    let habits = Habit::list(state.db.clone(), now, last_occurrences as usize).await?;
+    Ok(habits)
+}
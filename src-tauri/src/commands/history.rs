@@ -0,0 +1,364 @@
+use anyhow::anyhow;
+use log::warn;
+use tauri::State;
+
+use crate::{
+    app_state::AppState,
+    commands::{
+        components::{push_vcmp, remove_vcmp},
+        errors::CommandError,
+    },
+    db_conn::DbConn,
+    models::{
+        VCmp, VCmpId, journal::JournalEntry, todo_occurrence::TodoOccurrence, trash::TrashEntry,
+        vevent::VEvent, vtodo::VTodo,
+    },
+};
+
+/// Undoes the last mutation, returns the id of the affected component
+#[tauri::command()]
+#[specta::specta]
+pub async fn undo(state: State<'_, AppState>) -> Result<Option<VCmpId>, CommandError> {
+    let res = undo_last(state.db.clone()).await?;
+    Ok(res)
+}
+
+/// Redoes the last undone mutation, returns the id of the affected component
+#[tauri::command()]
+#[specta::specta]
+pub async fn redo(state: State<'_, AppState>) -> Result<Option<VCmpId>, CommandError> {
+    let res = redo_last(state.db.clone()).await?;
+    Ok(res)
+}
+
+pub async fn undo_last(conn: DbConn) -> anyhow::Result<Option<VCmpId>> {
+    let Some(entry) = JournalEntry::last_done(conn.clone()).await? else {
+        return Ok(None);
+    };
+    let vcmp_id = if entry.occurrence_start.is_some() {
+        restore_occurrence(conn.clone(), entry.get_after()?, entry.get_before()?).await?;
+        entry.get_vcmp_id()?
+    } else {
+        restore(conn.clone(), entry.get_after()?, entry.get_before()?).await?
+    };
+    entry.set_undone(conn, true).await?;
+    Ok(Some(vcmp_id))
+}
+
+pub async fn redo_last(conn: DbConn) -> anyhow::Result<Option<VCmpId>> {
+    let Some(entry) = JournalEntry::first_undone(conn.clone()).await? else {
+        return Ok(None);
+    };
+    let vcmp_id = if entry.occurrence_start.is_some() {
+        restore_occurrence(conn.clone(), entry.get_before()?, entry.get_after()?).await?;
+        entry.get_vcmp_id()?
+    } else {
+        restore(conn.clone(), entry.get_before()?, entry.get_after()?).await?
+    };
+    entry.set_undone(conn, false).await?;
+    Ok(Some(vcmp_id))
+}
+
+/// Keeps what the server knows about the component, so the restored values
+/// are pushed as an update on the next sync. The calendar goes with it, so a move
+/// to another calendar is not undone
+fn keep_sync_state(target: VCmp, current: &VCmp) -> VCmp {
+    match (target, current) {
+        (VCmp::Event(target), VCmp::Event(current)) => VCmp::Event(VEvent {
+            calendar_id: current.calendar_id,
+            href: current.href.clone(),
+            etag: current.etag.clone(),
+            ical_data: current.ical_data.clone(),
+            synced_at: current.synced_at,
+            out_of_sync: true,
+            deleted_at: None,
+            ..target
+        }),
+        (VCmp::Todo(target), VCmp::Todo(current)) => VCmp::Todo(VTodo {
+            calendar_id: current.calendar_id,
+            href: current.href.clone(),
+            etag: current.etag.clone(),
+            ical_data: current.ical_data.clone(),
+            synced_at: current.synced_at,
+            out_of_sync: true,
+            deleted_at: None,
+            ..target
+        }),
+        (target, _) => target,
+    }
+}
+
+/// Brings the component from the `current` state of the entry to the `target` one and
+/// returns its id. A change of type is a single entry with each state in a different
+/// table, so the component in the table it leaves is removed
+async fn restore(
+    conn: DbConn,
+    current: Option<VCmp>,
+    target: Option<VCmp>,
+) -> anyhow::Result<VCmpId> {
+    let current_id = current.as_ref().map(VCmp::get_id);
+    let target_id = target.as_ref().map(VCmp::get_id);
+    if let (Some(current_id), Some(target_id)) = (current_id, target_id)
+        && current_id != target_id
+    {
+        restore_state(conn.clone(), current_id, None).await?;
+        // Restoring it from the trash would duplicate the restored component
+        TrashEntry::delete_by_vcmp_id(conn.clone(), current_id).await?;
+    }
+    let vcmp_id = target_id
+        .or(current_id)
+        .ok_or(anyhow!("A journal entry needs a component"))?;
+    restore_state(conn, vcmp_id, target).await?;
+    Ok(vcmp_id)
+}
+
+/// Sets the status of an occurrence back, it's removed when the occurrence had none
+async fn restore_occurrence(
+    conn: DbConn,
+    current: Option<TodoOccurrence>,
+    target: Option<TodoOccurrence>,
+) -> anyhow::Result<()> {
+    match (current, target) {
+        (_, Some(target)) => target.save(conn).await,
+        (Some(current), None) => current.delete(conn).await,
+        (None, None) => Ok(()),
+    }
+}
+
+async fn restore_state(conn: DbConn, vcmp_id: VCmpId, target: Option<VCmp>) -> anyhow::Result<()> {
+    if target.is_some() {
+        // The component is back, it can't be restored from the trash anymore
+        TrashEntry::delete_by_vcmp_id(conn.clone(), vcmp_id).await?;
+    }
+    let current = VCmp::by_id_with_deleted(conn.clone(), vcmp_id).await?;
+    match (current, target) {
+        (Some(current), Some(target)) => {
+            keep_sync_state(target, &current).replace(conn).await?;
+        }
+        (None, Some(target)) => {
+            let restored = target.into_unsynced();
+            restored.replace(conn.clone()).await?;
+            if let Err(e) = push_vcmp(conn, restored).await {
+                warn!("Restored {vcmp_id} will be pushed on the next sync: {e}");
+            }
+        }
+        (Some(current), None) => remove_vcmp(conn, &current).await?,
+        (None, None) => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use now::DateTimeNow;
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+    use crate::{
+        calendar_items::{event_status::EventStatus, event_type::EventType},
+        models::{model_traits::CalendarAndSyncStatus, vtodo::NewVTodo},
+        test_utils::{create_todo, get_todo, new_local_todo, new_synced_todo, new_todo},
+    };
+
+    /// Creates a todo that was never synced, so nothing is sent to a server
+    fn create_journaled_todo(conn: DbConn) -> VCmp {
+        let todo = NewVTodo {
+            href: None,
+            ..new_local_todo("todo.ics")
+        };
+        let created = VCmp::Todo(create_todo(conn.clone(), todo));
+        block_on(JournalEntry::record(conn, None, Some(&created))).unwrap();
+        created
+    }
+
+    fn set_status(conn: DbConn, vcmp: &VCmp, status: EventStatus) -> VCmp {
+        let VCmp::Todo(vtodo) = vcmp else {
+            panic!("Expected a todo");
+        };
+        let updated = block_on(VTodo::update_status_by_id(
+            conn.clone(),
+            vtodo.id,
+            status,
+            Utc::now().fixed_offset(),
+        ))
+        .unwrap()
+        .map(VCmp::Todo)
+        .unwrap();
+        block_on(JournalEntry::record(conn, Some(vcmp), Some(&updated))).unwrap();
+        updated
+    }
+
+    #[test]
+    fn test_undo_and_redo_edit() {
+        let conn = DbConn::in_memory().unwrap();
+        let created = create_journaled_todo(conn.clone());
+        let vcmp_id = created.get_id();
+        set_status(conn.clone(), &created, EventStatus::InProgress);
+
+        assert_eq!(block_on(undo_last(conn.clone())).unwrap(), Some(vcmp_id));
+        let todo = get_todo(conn.clone(), vcmp_id).unwrap();
+        assert_eq!(todo.status, EventStatus::Done);
+        assert!(todo.out_of_sync);
+
+        assert_eq!(block_on(redo_last(conn.clone())).unwrap(), Some(vcmp_id));
+        let todo = get_todo(conn.clone(), vcmp_id).unwrap();
+        assert_eq!(todo.status, EventStatus::InProgress);
+        assert_eq!(block_on(redo_last(conn)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_undo_create_and_delete() {
+        let conn = DbConn::in_memory().unwrap();
+        let created = create_journaled_todo(conn.clone());
+        let vcmp_id = created.get_id();
+
+        block_on(created.delete(conn.clone())).unwrap();
+        block_on(JournalEntry::record(conn.clone(), Some(&created), None)).unwrap();
+        assert!(get_todo(conn.clone(), vcmp_id).is_none());
+
+        // Undo the delete, the todo comes back with the same id
+        block_on(undo_last(conn.clone())).unwrap();
+        let todo = get_todo(conn.clone(), vcmp_id).unwrap();
+        assert!(todo.synced_at.is_none());
+
+        // Undo the creation
+        block_on(undo_last(conn.clone())).unwrap();
+        assert!(get_todo(conn.clone(), vcmp_id).is_none());
+        assert_eq!(block_on(undo_last(conn)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_new_mutation_clears_redo() {
+        let conn = DbConn::in_memory().unwrap();
+        let created = create_journaled_todo(conn.clone());
+        let updated = set_status(conn.clone(), &created, EventStatus::InProgress);
+
+        block_on(undo_last(conn.clone())).unwrap();
+        set_status(conn.clone(), &updated, EventStatus::Backlog);
+
+        assert_eq!(block_on(redo_last(conn)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_remove_synced_keeps_tombstone() {
+        let conn = DbConn::in_memory().unwrap();
+        let created = VCmp::Todo(create_todo(conn.clone(), new_synced_todo("todo.ics")));
+        let vcmp_id = created.get_id();
+
+        block_on(remove_vcmp(conn.clone(), &created)).unwrap();
+        block_on(JournalEntry::record(conn.clone(), Some(&created), None)).unwrap();
+        assert!(get_todo(conn.clone(), vcmp_id).is_none());
+        assert!(
+            block_on(VTodo::list_unscheduled(conn.clone(), true))
+                .unwrap()
+                .is_empty()
+        );
+        let pending = block_on(VTodo::by_calendar_id_and_deleted(conn.clone(), 1)).unwrap();
+        assert_eq!(pending.len(), 1);
+
+        // Undoing the delete means nothing has to be deleted on the server anymore
+        block_on(undo_last(conn.clone())).unwrap();
+        let todo = get_todo(conn.clone(), vcmp_id).unwrap();
+        assert!(todo.deleted_at.is_none());
+        assert!(
+            block_on(VTodo::by_calendar_id_and_deleted(conn, 1))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_remove_unsynced_deletes_row() {
+        let conn = DbConn::in_memory().unwrap();
+        let created = create_journaled_todo(conn.clone());
+        let vcmp_id = created.get_id();
+
+        block_on(remove_vcmp(conn.clone(), &created)).unwrap();
+        assert!(
+            block_on(VCmp::by_id_with_deleted(conn, vcmp_id))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_undo_change_of_type_in_one_step() {
+        let conn = DbConn::in_memory().unwrap();
+        // Synced todo from 13:00 to 16:00
+        let todo = VCmp::Todo(create_todo(conn.clone(), new_todo("todo_date.ics")));
+        let VCmp::Todo(vtodo) = &todo else {
+            panic!("Expected a todo");
+        };
+        let edited = VCmp::Todo(VTodo {
+            event_type: EventType::Block,
+            ..vtodo.clone()
+        });
+        let event = edited.into_new_resource("/cal/").unwrap();
+        let event = block_on(event.create_in_place_of(conn.clone(), &todo)).unwrap();
+
+        assert_eq!(
+            block_on(undo_last(conn.clone())).unwrap(),
+            Some(todo.get_id())
+        );
+        let restored = get_todo(conn.clone(), todo.get_id()).unwrap();
+        assert_eq!(restored.event_type, vtodo.event_type);
+        assert!(restored.deleted_at.is_none());
+        assert!(
+            block_on(VCmp::by_id(conn.clone(), event.get_id()))
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(block_on(undo_last(conn.clone())).unwrap(), None);
+
+        assert_eq!(
+            block_on(redo_last(conn.clone())).unwrap(),
+            Some(event.get_id())
+        );
+        assert!(get_todo(conn.clone(), todo.get_id()).is_none());
+        assert!(
+            block_on(VCmp::by_id(conn, event.get_id()))
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_undo_occurrence_status() {
+        let conn = DbConn::in_memory().unwrap();
+        // Daily reminder at 08:00 from 20/05/24
+        let vtodo = create_todo(conn.clone(), new_todo("todo_recurring.ics"));
+        let day = Utc.with_ymd_and_hms(2024, 5, 21, 10, 0, 0).unwrap();
+        let status_of_day = |conn: DbConn| {
+            let occurrences = block_on(TodoOccurrence::for_uids_between(
+                conn,
+                vec![vtodo.uid.clone()],
+                day.beginning_of_day(),
+                day.end_of_day(),
+            ))
+            .unwrap();
+            occurrences.into_values().next().map(|o| o.status)
+        };
+        block_on(TodoOccurrence::set_status(
+            conn.clone(),
+            &VCmp::Todo(vtodo.clone()),
+            EventStatus::Done,
+            &day,
+            day,
+        ))
+        .unwrap();
+        assert_eq!(status_of_day(conn.clone()), Some(EventStatus::Done));
+
+        assert_eq!(
+            block_on(undo_last(conn.clone())).unwrap(),
+            Some(VCmpId::Todo(vtodo.id))
+        );
+        assert_eq!(status_of_day(conn.clone()), None);
+        // The series is left as is
+        let series = get_todo(conn.clone(), VCmpId::Todo(vtodo.id)).unwrap();
+        assert_eq!(series.status, vtodo.status);
+
+        block_on(redo_last(conn.clone())).unwrap();
+        assert_eq!(status_of_day(conn), Some(EventStatus::Done));
+    }
+}
@@ -1,21 +1,39 @@
+use tauri::State;
+
 use crate::{
+    app_state::AppState,
     commands::errors::CommandError,
-    db_conn::DbConn,
     models::{
         Calendar,
         server::{NewServer, Server},
     },
 };
 
+pub(crate) mod bulk;
 pub(crate) mod calendar;
 pub(crate) mod components;
+pub mod conflicts;
+pub mod day_summary;
 pub mod errors;
 pub mod extended_event;
 pub mod extended_todo;
+pub(crate) mod free_busy;
+pub(crate) mod habits;
+pub(crate) mod history;
+pub(crate) mod rollover;
+pub(crate) mod routines;
+pub(crate) mod scheduler;
+pub(crate) mod settings;
+pub(crate) mod shift_day;
+pub(crate) mod slots;
+pub(crate) mod statistics;
+pub(crate) mod templates;
+pub(crate) mod trash;
 
 #[tauri::command()]
 #[specta::specta]
 pub async fn create_server(
+    state: State<'_, AppState>,
     server_url: String,
     user: String,
     password: String,
@@ -27,14 +45,16 @@ pub async fn create_server(
         last_sync: None,
     };
 
-    let server = new_server.save().await?;
+    let server = new_server.save(state.db.clone()).await?;
     Ok(server)
 }
 
 #[tauri::command(rename_all = "snake_case")]
 #[specta::specta]
-pub async fn list_servers() -> Result<Vec<(Server, Vec<Calendar>)>, CommandError> {
-    let conn = DbConn::new().await?;
+pub async fn list_servers(
+    state: State<'_, AppState>,
+) -> Result<Vec<(Server, Vec<Calendar>)>, CommandError> {
+    let conn = state.db.clone();
 
     let res = Server::list_all_with_calendars(conn).await?;
     Ok(res)
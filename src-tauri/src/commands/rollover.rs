@@ -0,0 +1,202 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Days, Local, NaiveDate, TimeZone, Utc};
+use diesel::Connection;
+use log::{info, warn};
+use now::DateTimeNow;
+use tokio::time::{MissedTickBehavior, interval};
+
+use crate::{
+    db_conn::DbConn,
+    models::{VCmp, settings::Settings, vtodo::VTodo},
+};
+
+/// How often the local date is checked, so the rollover happens soon after midnight
+/// even when the computer was asleep
+const DATE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Moves the overdue todos of the opted in calendars and tags to the day of `today`,
+/// keeping their time of day, returns how many were moved. The moves aren't journaled,
+/// they weren't made by the user and would drop the changes that can be redone
+pub(crate) async fn rollover_overdue_todos<Tz: TimeZone>(
+    conn: DbConn,
+    today: DateTime<Tz>,
+) -> anyhow::Result<usize> {
+    let settings = Settings::load(conn.clone()).await?;
+    if settings.rollover_calendar_ids.is_empty() && settings.rollover_tags.is_empty() {
+        return Ok(0);
+    }
+
+    let overdue = VTodo::list_overdue(conn.clone(), today.beginning_of_day().to_utc()).await?;
+    let rolled = overdue
+        .into_iter()
+        .filter(|t| settings.rolls_over(t.calendar_id, t.tag.as_deref()))
+        .filter_map(|todo| {
+            let id = todo.id;
+            let Some(rolled) = roll_to_day(todo, &today) else {
+                warn!("Could not roll over todo {id}");
+                return None;
+            };
+            Some(VCmp::Todo(rolled))
+        })
+        .collect::<Vec<VCmp>>();
+    let moved = rolled.len();
+    conn.run(move |conn| {
+        conn.transaction::<_, anyhow::Error, _>(|conn| {
+            for rolled in &rolled {
+                rolled.replace_in(conn)?;
+            }
+            Ok(())
+        })
+    })
+    .await?;
+    Ok(moved)
+}
+
+/// Rolls over the overdue todos now and then each time the local date changes, meant to
+/// be spawned on the async runtime
+pub(crate) async fn run_daily_rollover(conn: DbConn) {
+    let mut rolled_on: Option<NaiveDate> = None;
+    let mut checks = interval(DATE_CHECK_INTERVAL);
+    checks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        checks.tick().await;
+        let now = Local::now();
+        if rolled_on == Some(now.date_naive()) {
+            continue;
+        }
+        // On failure it's tried again on the next check
+        match rollover_overdue_todos(conn.clone(), now).await {
+            Ok(moved) => {
+                info!("Rolled over {moved} overdue todos");
+                rolled_on = Some(now.date_naive());
+            }
+            Err(e) => warn!("Could not roll over the overdue todos: {e}"),
+        }
+    }
+}
+
+/// Same todo moved to the day of `today`, counted as postponed
+fn roll_to_day<Tz: TimeZone>(todo: VTodo, today: &DateTime<Tz>) -> Option<VTodo> {
+    let tz = today.timezone();
+    let starts_at = todo.starts_at?;
+    let days = (today.date_naive() - starts_at.with_timezone(&tz).date_naive()).num_days();
+    let days = Days::new(u64::try_from(days).ok()?);
+    let shift = |date: DateTime<Utc>| {
+        date.with_timezone(&tz)
+            .checked_add_days(days)
+            .map(|d| d.to_utc())
+    };
+
+    let ends_at = match todo.ends_at {
+        Some(ends_at) => Some(shift(ends_at)?),
+        None => None,
+    };
+    Some(VTodo {
+        starts_at: Some(shift(starts_at)?),
+        ends_at,
+        postponed: todo.postponed + 1,
+        out_of_sync: true,
+        ..todo
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use tauri::async_runtime::block_on;
+
+    use crate::{
+        calendar_items::event_status::EventStatus,
+        commands::history::{redo_last, undo_last},
+        models::{journal::JournalEntry, model_traits::ById, vtodo::NewVTodo},
+        test_utils::{create_todo, get_todo, new_todo},
+    };
+
+    use super::*;
+
+    /// Todo of the 20/05/24 from 13:00 to 16:00, not done
+    fn create_overdue_todo(conn: DbConn) -> VTodo {
+        let todo = NewVTodo {
+            status: EventStatus::Todo,
+            ..new_todo("todo_date.ics")
+        };
+        create_todo(conn, todo)
+    }
+
+    fn today() -> DateTime<chrono_tz::Tz> {
+        chrono_tz::Tz::UTC
+            .with_ymd_and_hms(2024, 5, 22, 9, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_rollover_opted_in_calendar() {
+        let conn = DbConn::in_memory().unwrap();
+        let todo = create_overdue_todo(conn.clone());
+        let settings = Settings {
+            rollover_calendar_ids: vec![1],
+            ..Default::default()
+        };
+        block_on(settings.save(conn.clone())).unwrap();
+
+        assert_eq!(
+            block_on(rollover_overdue_todos(conn.clone(), today())).unwrap(),
+            1
+        );
+        let rolled = block_on(VTodo::by_id(conn.clone(), todo.id))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            rolled.starts_at,
+            Some(Utc.with_ymd_and_hms(2024, 5, 22, 13, 0, 0).unwrap())
+        );
+        assert_eq!(
+            rolled.ends_at,
+            Some(Utc.with_ymd_and_hms(2024, 5, 22, 16, 0, 0).unwrap())
+        );
+        assert_eq!(rolled.postponed, todo.postponed + 1);
+        assert!(rolled.out_of_sync);
+        assert!(
+            block_on(JournalEntry::last_done(conn.clone()))
+                .unwrap()
+                .is_none()
+        );
+
+        // Not overdue anymore
+        assert_eq!(block_on(rollover_overdue_todos(conn, today())).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_redo_after_rollover() {
+        let conn = DbConn::in_memory().unwrap();
+        create_overdue_todo(conn.clone());
+        let settings = Settings {
+            rollover_calendar_ids: vec![1],
+            ..Default::default()
+        };
+        block_on(settings.save(conn.clone())).unwrap();
+        // Unscheduled, it doesn't roll over
+        let created = VCmp::Todo(create_todo(conn.clone(), new_todo("todo.ics")));
+        block_on(JournalEntry::record(conn.clone(), None, Some(&created))).unwrap();
+        let vcmp_id = created.get_id();
+        assert_eq!(block_on(undo_last(conn.clone())).unwrap(), Some(vcmp_id));
+        assert!(get_todo(conn.clone(), vcmp_id).is_none());
+
+        assert_eq!(
+            block_on(rollover_overdue_todos(conn.clone(), today())).unwrap(),
+            1
+        );
+        assert_eq!(block_on(redo_last(conn.clone())).unwrap(), Some(vcmp_id));
+        assert!(get_todo(conn, vcmp_id).is_some());
+    }
+
+    #[test]
+    fn test_no_rollover_without_opt_in() {
+        let conn = DbConn::in_memory().unwrap();
+        create_overdue_todo(conn.clone());
+
+        let overdue = block_on(VTodo::list_overdue(conn.clone(), today().to_utc())).unwrap();
+        assert_eq!(overdue.len(), 1);
+        assert_eq!(block_on(rollover_overdue_todos(conn, today())).unwrap(), 0);
+    }
+}
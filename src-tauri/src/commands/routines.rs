@@ -0,0 +1,127 @@
+use anyhow::anyhow;
+use chrono::{DateTime, FixedOffset, TimeDelta};
+use tauri::State;
+
+use crate::{
+    app_state::AppState,
+    calendar_items::{
+        event_date::{EventDateInfo, EventDateOption, EventRecurrence},
+        event_upsert::EventUpsertInfo,
+        input_traits::{ExtractedInput, FromUserInput},
+    },
+    commands::{components::create_from_upsert, errors::CommandError},
+    models::{
+        VCmpId,
+        model_traits::ListAll,
+        named_item::NamedItem,
+        routine::{Routine, RoutineItem},
+    },
+    util::DateTimeStr,
+};
+
+#[tauri::command()]
+#[specta::specta]
+pub async fn list_routines(state: State<'_, AppState>) -> Result<Vec<Routine>, CommandError> {
+    let routines = Routine::list_all(state.db.clone()).await?;
+    Ok(routines)
+}
+
+/// Creates the routine or replaces the one with the same name
+#[tauri::command()]
+#[specta::specta]
+pub async fn save_routine(
+    state: State<'_, AppState>,
+    routine: Routine,
+) -> Result<(), CommandError> {
+    if routine.name.trim().is_empty() {
+        return Err(anyhow!("A routine needs a name").into());
+    }
+    routine.save(state.db.clone()).await?;
+    Ok(())
+}
+
+#[tauri::command()]
+#[specta::specta]
+pub async fn delete_routine(state: State<'_, AppState>, name: String) -> Result<(), CommandError> {
+    if !Routine::delete_by_name(state.db.clone(), &name).await? {
+        return Err(anyhow!("No routine named {name}").into());
+    }
+    Ok(())
+}
+
+/// Creates every item of the routine in the calendar, the routine starts at
+/// `date_of_input_str` and each item at its offset from it
+#[tauri::command()]
+#[specta::specta]
+pub async fn instantiate_routine(
+    state: State<'_, AppState>,
+    name: String,
+    date_of_input_str: String,
+    calendar_id: i32,
+) -> Result<Vec<VCmpId>, CommandError> {
+    let conn = state.db.clone();
+    let start: DateTime<FixedOffset> = DateTimeStr(date_of_input_str).try_into()?;
+    let routine = Routine::by_name(conn.clone(), &name)
+        .await?
+        .ok_or(anyhow!("No routine named {name}"))?;
+
+    // Every input is parsed before creating anything, so a bad one doesn't leave the
+    // routine half done
+    let items = routine
+        .items
+        .iter()
+        .map(|item| item_upsert_info(item, start))
+        .collect::<anyhow::Result<Vec<EventUpsertInfo<FixedOffset>>>>()?;
+    let mut created = Vec::new();
+    for data in items {
+        let vcmp = create_from_upsert(conn.clone(), calendar_id, &data).await?;
+        created.push(vcmp.get_id());
+    }
+    Ok(created)
+}
+
+/// Parsed input of the item, dated at its offset from `start` when the input has no date
+fn item_upsert_info(
+    item: &RoutineItem,
+    start: DateTime<FixedOffset>,
+) -> anyhow::Result<EventUpsertInfo<FixedOffset>> {
+    let item_start = start + TimeDelta::minutes(i64::from(item.offset_minutes));
+    let ExtractedInput(mut data, _) =
+        EventUpsertInfo::extract_from_input(item_start, &item.input)?.into();
+    if data.date_info.0.is_none() {
+        data.date_info = EventDateOption(Some(EventDateInfo {
+            start: item_start,
+            end: None,
+            recurrence: EventRecurrence::none(),
+        }));
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::calendar_items::event_type::EventType;
+
+    #[test]
+    fn test_items_at_their_offset() {
+        let start = FixedOffset::east_opt(0)
+            .unwrap()
+            .with_ymd_and_hms(2024, 6, 3, 7, 0, 0)
+            .unwrap();
+        let item = RoutineItem {
+            input: "@reminder Water the plants".to_string(),
+            offset_minutes: 45,
+        };
+        let data = item_upsert_info(&item, start).unwrap();
+        let date_info = data.date_info.0.clone().unwrap();
+        assert_eq!(date_info.start, start + TimeDelta::minutes(45));
+        assert_eq!(data.event_type, EventType::Reminder);
+        assert_eq!(
+            date_info.get_end_or_default(data.event_type),
+            start + TimeDelta::minutes(60)
+        );
+    }
+}
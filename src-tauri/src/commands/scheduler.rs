@@ -0,0 +1,323 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Days, FixedOffset, TimeDelta, Utc};
+use tauri::State;
+
+use crate::{
+    app_state::AppState,
+    calendar_items::event_type::EventType,
+    commands::{errors::CommandError, extended_event::ExtendedEvent, extended_todo::ExtendedTodo},
+    db_conn::DbConn,
+    models::{
+        VCmp, VCmpId,
+        free_busy::{Interval, at_hour, free_between},
+        model_traits::ListForDayOrRecurring,
+        reschedule::{self, TimeChange},
+        settings::Settings,
+        vevent::VEvent,
+        vtodo::VTodo,
+    },
+    util::DateTimeStr,
+};
+
+/// Most days ahead the scheduler looks for free time
+const MAX_DAYS: u64 = 14;
+
+/// Slot proposed for an unscheduled todo
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, specta::Type)]
+pub struct ProposedSlot {
+    pub vcmp_id: VCmpId,
+    pub summary: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    /// Placed inside a block with a matching tag
+    pub in_block: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, specta::Type)]
+pub struct SchedulePreview {
+    pub slots: Vec<ProposedSlot>,
+    /// Todos that didn't fit in the free time
+    pub not_scheduled: Vec<VCmpId>,
+}
+
+/// Working hours of a day, with what is already taking them
+struct DayPlan {
+    window: Interval,
+    /// Events and scheduled todos, blocks are not included
+    busy: Vec<Interval>,
+    blocks: Vec<(Interval, Option<String>)>,
+}
+
+/// Proposes slots in the working hours of the next `days` for the selected todos,
+/// nothing is saved until [`apply_schedule`] is called with them
+#[tauri::command()]
+#[specta::specta]
+pub async fn preview_schedule(
+    state: State<'_, AppState>,
+    vcmp_ids: Vec<VCmpId>,
+    date_of_input_str: String,
+    days: u32,
+) -> Result<SchedulePreview, CommandError> {
+    let conn = state.db.clone();
+    let from: DateTime<FixedOffset> = DateTimeStr(date_of_input_str).try_into()?;
+
+    let mut todos = Vec::new();
+    for vcmp_id in vcmp_ids {
+        match VCmp::by_id(conn.clone(), vcmp_id).await? {
+            Some(VCmp::Todo(vtodo)) if vtodo.starts_at.is_none() => todos.push(vtodo),
+            _ => return Err(anyhow!("{vcmp_id} is not an unscheduled todo").into()),
+        }
+    }
+    let days = load_day_plans(conn, from, days).await?;
+    Ok(plan(todos, days))
+}
+
+/// Schedules the todos in the slots of a preview, nothing is scheduled when one of them
+/// isn't unscheduled anymore
+#[tauri::command()]
+#[specta::specta]
+pub async fn apply_schedule(
+    state: State<'_, AppState>,
+    slots: Vec<ProposedSlot>,
+) -> Result<(), CommandError> {
+    let changes = slots
+        .into_iter()
+        .map(|slot| TimeChange {
+            vcmp_id: slot.vcmp_id,
+            starts_at: None,
+            new_starts_at: slot.starts_at,
+            new_ends_at: slot.ends_at,
+        })
+        .collect();
+    reschedule::apply(state.db.clone(), changes).await?;
+    Ok(())
+}
+
+async fn load_day_plans(
+    conn: DbConn,
+    from: DateTime<FixedOffset>,
+    days: u32,
+) -> anyhow::Result<Vec<DayPlan>> {
+    let settings = Settings::load(conn.clone()).await?;
+    let mut plans = Vec::new();
+    for offset in 0..u64::from(days).min(MAX_DAYS) {
+        let day = from
+            .checked_add_days(Days::new(offset))
+            .ok_or(anyhow!("Date out of range"))?;
+        let (Some(start), Some(end)) = (
+            at_hour(&day, settings.day_start_hour),
+            at_hour(&day, settings.day_end_hour),
+        ) else {
+            continue;
+        };
+        // Nothing is placed in the past
+        let window = (start.max(from.to_utc()), end);
+
+        let events = VEvent::list_for_day_or_recurring(conn.clone(), day)
+            .await?
+            .iter()
+            .filter_map(|e| ExtendedEvent::on_day(e, &day))
+            .collect::<Vec<ExtendedEvent>>();
+        let todos = VTodo::list_for_day_or_recurring(conn.clone(), day)
+            .await?
+            .iter()
+            .filter_map(|t| ExtendedTodo::on_day(t, &day))
+            .map(|t| (t.starts_at, t.ends_at))
+            .collect::<Vec<Interval>>();
+
+        let (blocks, events): (Vec<&ExtendedEvent>, Vec<&ExtendedEvent>) = events
+            .iter()
+            .partition(|e| e.event.event_type == EventType::Block);
+        plans.push(DayPlan {
+            window,
+            busy: events
+                .iter()
+                .map(|e| (e.starts_at, e.ends_at))
+                .chain(todos)
+                .collect(),
+            blocks: blocks
+                .iter()
+                .map(|b| ((b.starts_at, b.ends_at), b.event.tag.clone()))
+                .collect(),
+        });
+    }
+    Ok(plans)
+}
+
+/// Places the most urgent and important todos first, each one in the earliest block
+/// with a matching tag where it fits, or else in the earliest free time. A slot lasts
+/// the default duration of the type of the todo, its load is weighed against the daily
+/// budget and doesn't say how long it takes
+fn plan(mut todos: Vec<VTodo>, mut days: Vec<DayPlan>) -> SchedulePreview {
+    todos.sort_by_key(|t| {
+        (
+            std::cmp::Reverse(t.urgency + t.importance),
+            std::cmp::Reverse(t.urgency),
+            t.id,
+        )
+    });
+
+    let mut preview = SchedulePreview {
+        slots: vec![],
+        not_scheduled: vec![],
+    };
+    for todo in todos {
+        let duration = todo.event_type.default_duration();
+        let found = find_in_blocks(&days, todo.tag.as_deref(), duration)
+            .map(|found| (found, true))
+            .or_else(|| find_free(&days, duration).map(|found| (found, false)));
+        let Some(((day, (starts_at, ends_at)), in_block)) = found else {
+            preview.not_scheduled.push(VCmpId::Todo(todo.id));
+            continue;
+        };
+        days[day].busy.push((starts_at, ends_at));
+        preview.slots.push(ProposedSlot {
+            vcmp_id: VCmpId::Todo(todo.id),
+            summary: todo.summary,
+            starts_at,
+            ends_at,
+            in_block,
+        });
+    }
+    preview
+}
+
+fn find_in_blocks(
+    days: &[DayPlan],
+    tag: Option<&str>,
+    duration: TimeDelta,
+) -> Option<(usize, Interval)> {
+    days.iter().enumerate().find_map(|(i, day)| {
+        day.blocks
+            .iter()
+            .filter(|(_, block_tag)| shares_tag(tag, block_tag.as_deref()))
+            .find_map(|(block, _)| {
+                let window = (block.0.max(day.window.0), block.1.min(day.window.1));
+                fit(window, &day.busy, duration)
+            })
+            .map(|slot| (i, slot))
+    })
+}
+
+fn find_free(days: &[DayPlan], duration: TimeDelta) -> Option<(usize, Interval)> {
+    days.iter().enumerate().find_map(|(i, day)| {
+        let busy = day
+            .busy
+            .iter()
+            .copied()
+            .chain(day.blocks.iter().map(|(block, _)| *block))
+            .collect::<Vec<Interval>>();
+        fit(day.window, &busy, duration).map(|slot| (i, slot))
+    })
+}
+
+/// Earliest slot of `duration` inside `window` that doesn't overlap `busy`
+fn fit(window: Interval, busy: &[Interval], duration: TimeDelta) -> Option<Interval> {
+    free_between(window, busy.to_vec())
+        .into_iter()
+        .find(|(s, e)| *e - *s >= duration)
+        .map(|(s, _)| (s, s + duration))
+}
+
+fn shares_tag(tag: Option<&str>, other: Option<&str>) -> bool {
+    let (Some(tag), Some(other)) = (tag, other) else {
+        return false;
+    };
+    tag.split(',').any(|t| other.split(',').any(|o| o == t))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::{
+        models::vtodo::NewVTodo,
+        test_utils::{create_todo, new_todo},
+    };
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 3, hour, minute, 0).unwrap()
+    }
+
+    fn create_task(conn: DbConn, uid: &str, urgency: i32, tag: Option<&str>) -> VTodo {
+        let todo = NewVTodo {
+            uid: uid.to_string(),
+            href: Some(format!("/{uid}")),
+            event_type: EventType::Task,
+            urgency,
+            tag: tag.map(|t| t.to_string()),
+            ..new_todo("todo.ics")
+        };
+        create_todo(conn, todo)
+    }
+
+    #[test]
+    fn test_slot_length_ignores_load() {
+        let conn = DbConn::in_memory().unwrap();
+        let light = VTodo {
+            load: 1,
+            ..create_task(conn.clone(), "light", 1, None)
+        };
+        let heavy = VTodo {
+            load: 8,
+            ..create_task(conn, "heavy", 0, None)
+        };
+        let day = DayPlan {
+            window: (at(8, 0), at(18, 0)),
+            busy: vec![],
+            blocks: vec![],
+        };
+
+        let preview = plan(vec![light, heavy], vec![day]);
+        let lengths = preview
+            .slots
+            .iter()
+            .map(|s| s.ends_at - s.starts_at)
+            .collect::<Vec<TimeDelta>>();
+        let duration = EventType::Task.default_duration();
+        assert_eq!(lengths, vec![duration, duration]);
+    }
+
+    #[test]
+    fn test_fit_skips_busy_time() {
+        let busy = vec![(at(9, 0), at(10, 0)), (at(10, 15), at(11, 0))];
+        let slot = fit((at(9, 0), at(12, 0)), &busy, TimeDelta::minutes(30));
+        assert_eq!(slot, Some((at(11, 0), at(11, 30))));
+        let slot = fit((at(9, 0), at(11, 0)), &busy, TimeDelta::minutes(30));
+        assert_eq!(slot, None);
+    }
+
+    #[test]
+    fn test_plan_prefers_urgent_and_matching_blocks() {
+        let conn = DbConn::in_memory().unwrap();
+        let relaxed = create_task(conn.clone(), "relaxed", 0, None);
+        let urgent = create_task(conn.clone(), "urgent", 3, None);
+        let health = create_task(conn, "health", 1, Some("health"));
+
+        let day = DayPlan {
+            window: (at(8, 0), at(10, 0)),
+            busy: vec![(at(8, 0), at(8, 30))],
+            blocks: vec![((at(9, 0), at(10, 0)), Some("health".to_string()))],
+        };
+        let preview = plan(
+            vec![relaxed.clone(), health.clone(), urgent.clone()],
+            vec![day],
+        );
+
+        let slots = preview
+            .slots
+            .iter()
+            .map(|s| (s.vcmp_id, s.starts_at, s.in_block))
+            .collect::<Vec<(VCmpId, DateTime<Utc>, bool)>>();
+        assert_eq!(
+            slots,
+            vec![
+                (VCmpId::Todo(urgent.id), at(8, 30), false),
+                (VCmpId::Todo(health.id), at(9, 0), true),
+            ]
+        );
+        // The only free time left is inside the block
+        assert_eq!(preview.not_scheduled, vec![VCmpId::Todo(relaxed.id)]);
+    }
+}
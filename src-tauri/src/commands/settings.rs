@@ -0,0 +1,21 @@
+use tauri::State;
+
+use crate::{app_state::AppState, commands::errors::CommandError, models::settings::Settings};
+
+#[tauri::command()]
+#[specta::specta]
+pub async fn get_settings(state: State<'_, AppState>) -> Result<Settings, CommandError> {
+    let settings = Settings::load(state.db.clone()).await?;
+    Ok(settings)
+}
+
+#[tauri::command()]
+#[specta::specta]
+pub async fn update_settings(
+    state: State<'_, AppState>,
+    settings: Settings,
+) -> Result<(), CommandError> {
+    settings.validate()?;
+    settings.save(state.db.clone()).await?;
+    Ok(())
+}
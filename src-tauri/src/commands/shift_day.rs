@@ -0,0 +1,175 @@
+use chrono::{DateTime, FixedOffset, TimeDelta, Utc};
+use tauri::State;
+
+use crate::{
+    app_state::AppState,
+    calendar_items::{event_status::EventStatus, event_type::EventType},
+    commands::{errors::CommandError, extended_event::ExtendedEvent, extended_todo::ExtendedTodo},
+    db_conn::DbConn,
+    models::{
+        VCmpId,
+        model_traits::ListForDayOrRecurring,
+        reschedule::{self, TimeChange},
+        vevent::VEvent,
+        vtodo::VTodo,
+    },
+    util::DateTimeStr,
+};
+
+/// Item of the day with the times it would move to
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, specta::Type)]
+pub struct ShiftedItem {
+    pub vcmp_id: VCmpId,
+    pub summary: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub new_starts_at: DateTime<Utc>,
+    pub new_ends_at: DateTime<Utc>,
+}
+
+/// Flexible items of the day starting from `date_of_input` on, moved by `minutes`,
+/// nothing is saved until [`apply_shift_day`] is called with them
+///
+/// Flexible items are the `Block`, `Task` and `Reminder` ones that are not done and
+/// don't repeat, `Event`s stay where they are
+#[tauri::command()]
+#[specta::specta]
+pub async fn preview_shift_day(
+    state: State<'_, AppState>,
+    date_of_input_str: String,
+    minutes: i32,
+) -> Result<Vec<ShiftedItem>, CommandError> {
+    let from: DateTime<FixedOffset> = DateTimeStr(date_of_input_str).try_into()?;
+    let items = flexible_items_from(state.db.clone(), from).await?;
+    Ok(shift(items, TimeDelta::minutes(i64::from(minutes))))
+}
+
+/// Moves the items of a preview to their new times, they are synced as any other change.
+/// Nothing moves when one of them was moved since the preview
+#[tauri::command()]
+#[specta::specta]
+pub async fn apply_shift_day(
+    state: State<'_, AppState>,
+    items: Vec<ShiftedItem>,
+) -> Result<(), CommandError> {
+    let changes = items
+        .into_iter()
+        .map(|item| TimeChange {
+            vcmp_id: item.vcmp_id,
+            starts_at: Some(item.starts_at),
+            new_starts_at: item.new_starts_at,
+            new_ends_at: item.new_ends_at,
+        })
+        .collect();
+    reschedule::apply(state.db.clone(), changes).await?;
+    Ok(())
+}
+
+/// Items of the day of `from` that can be moved and start from it on, sorted by start
+async fn flexible_items_from(
+    conn: DbConn,
+    from: DateTime<FixedOffset>,
+) -> anyhow::Result<Vec<ShiftedItem>> {
+    let events = VEvent::list_for_day_or_recurring(conn.clone(), from).await?;
+    let events = events
+        .iter()
+        .filter_map(|e| ExtendedEvent::on_day(e, &from))
+        .filter(|e| {
+            e.event.event_type != EventType::Event
+                && !e.event.has_rrule
+                && e.event.status != EventStatus::Done
+        })
+        .map(|e| (e.vcmp_id, e.event.summary, e.starts_at, e.ends_at));
+    let todos = VTodo::list_for_day_or_recurring(conn, from).await?;
+    let todos = todos
+        .iter()
+        .filter_map(|t| ExtendedTodo::on_day(t, &from))
+        .filter(|t| {
+            t.todo.event_type != EventType::Event
+                && !t.todo.has_rrule
+                && t.todo.status != EventStatus::Done
+        })
+        .map(|t| (t.vcmp_id, t.todo.summary, t.starts_at, t.ends_at));
+
+    let mut items = events
+        .chain(todos)
+        .filter(|(_, _, starts_at, _)| *starts_at >= from.to_utc())
+        .map(|(vcmp_id, summary, starts_at, ends_at)| ShiftedItem {
+            vcmp_id,
+            summary,
+            starts_at,
+            ends_at,
+            new_starts_at: starts_at,
+            new_ends_at: ends_at,
+        })
+        .collect::<Vec<ShiftedItem>>();
+    items.sort_by_key(|i| i.starts_at);
+    Ok(items)
+}
+
+/// Moves every item by the same amount, so their order, durations and the time
+/// between them stay the same
+fn shift(items: Vec<ShiftedItem>, by: TimeDelta) -> Vec<ShiftedItem> {
+    items
+        .into_iter()
+        .map(|item| ShiftedItem {
+            new_starts_at: item.starts_at + by,
+            new_ends_at: item.ends_at + by,
+            ..item
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+    use crate::{
+        models::{vevent::NewVEvent, vtodo::NewVTodo},
+        test_utils::{create_event, create_todo, new_single_event, new_todo},
+    };
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 20, hour, minute, 0).unwrap()
+    }
+
+    /// Event of an hour starting at `start`
+    fn create_hour(conn: DbConn, uid: &str, event_type: EventType, start: u32) -> VEvent {
+        let event = NewVEvent {
+            event_type,
+            ..new_single_event(uid, at(start, 0), at(start + 1, 0))
+        };
+        create_event(conn, event)
+    }
+
+    #[test]
+    fn test_shift_keeps_fixed_events() {
+        let conn = DbConn::in_memory().unwrap();
+        // One starts before the input and the other one is fixed, neither moves
+        create_hour(conn.clone(), "earlier", EventType::Block, 9);
+        create_hour(conn.clone(), "meeting", EventType::Event, 11);
+        let block = create_hour(conn.clone(), "block", EventType::Block, 14);
+        // Todo from 13:00 to 16:00, not done
+        let todo = NewVTodo {
+            status: EventStatus::Todo,
+            event_type: EventType::Task,
+            ..new_todo("todo_date.ics")
+        };
+        let todo = create_todo(conn.clone(), todo);
+
+        let items = block_on(flexible_items_from(conn.clone(), at(10, 0).fixed_offset())).unwrap();
+        let shifted = shift(items, TimeDelta::minutes(30))
+            .iter()
+            .map(|i| (i.vcmp_id, i.new_starts_at, i.new_ends_at))
+            .collect::<Vec<(VCmpId, DateTime<Utc>, DateTime<Utc>)>>();
+        assert_eq!(
+            shifted,
+            vec![
+                (VCmpId::Todo(todo.id), at(13, 30), at(16, 30)),
+                (VCmpId::Event(block.id), at(14, 30), at(15, 30)),
+            ]
+        );
+    }
+}
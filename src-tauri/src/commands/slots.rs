@@ -0,0 +1,290 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Days, FixedOffset, TimeDelta, Utc};
+use now::DateTimeNow;
+use tauri::State;
+
+use crate::{
+    app_state::AppState,
+    calendar_items::event_date::{NamedTime, SlotQuery, start_end_to_natural},
+    commands::errors::CommandError,
+    db_conn::DbConn,
+    models::{
+        Calendar,
+        free_busy::{FreeBusy, Interval, at_hour},
+        settings::Settings,
+    },
+    util::DateTimeStr,
+};
+
+/// Most days searched for free slots
+const MAX_DAYS: usize = 31;
+
+/// Conditions the slots have to meet
+#[derive(Clone, Debug, Default, serde::Deserialize, specta::Type)]
+pub struct SlotConstraints {
+    /// Start of the working hours, the one of the settings when missing
+    pub day_start_hour: Option<u32>,
+    /// End of the working hours, the one of the settings when missing
+    pub day_end_hour: Option<u32>,
+    /// Slots in this time of the day are ranked first
+    pub time_of_day: Option<NamedTime>,
+    /// Minutes kept free before and after other items
+    pub buffer_minutes: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, specta::Type)]
+pub struct FreeSlot {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    /// Fully inside the preferred time of the day
+    pub preferred: bool,
+}
+
+/// Free slots of `duration_minutes` between `from` and `to` across all the calendars,
+/// best ones first
+#[tauri::command()]
+#[specta::specta]
+pub async fn find_slots(
+    state: State<'_, AppState>,
+    duration_minutes: u32,
+    from: String,
+    to: String,
+    constraints: SlotConstraints,
+    limit: u32,
+) -> Result<Vec<FreeSlot>, CommandError> {
+    let from: DateTime<FixedOffset> = DateTimeStr(from).try_into()?;
+    let to: DateTime<FixedOffset> = DateTimeStr(to).try_into()?;
+    let duration = TimeDelta::minutes(i64::from(duration_minutes));
+    let mut slots = find_slots_between(state.db.clone(), duration, from, to, &constraints).await?;
+    slots.truncate(limit as usize);
+    Ok(slots)
+}
+
+/// Input after [`fill_slot_query`]
+pub(crate) struct FilledInput {
+    pub input: String,
+    /// Set when no free slot was found for the query, the input is then left without a date
+    pub warning: Option<String>,
+}
+
+/// Replaces a slot query of the input, like `1h sometime next week`, with the date of
+/// the best free slot for it, other inputs are returned as they are
+pub(crate) async fn fill_slot_query(
+    conn: DbConn,
+    date_of_input: DateTime<FixedOffset>,
+    input: &str,
+) -> anyhow::Result<FilledInput> {
+    let Some((query, stripped)) = SlotQuery::extract_from_input(date_of_input, input) else {
+        return Ok(FilledInput {
+            input: input.to_string(),
+            warning: None,
+        });
+    };
+    let constraints = SlotConstraints {
+        time_of_day: query.time_of_day,
+        ..Default::default()
+    };
+    let slot = find_slots_between(conn, query.duration, query.from, query.to, &constraints)
+        .await?
+        .into_iter()
+        .next();
+    Ok(match slot {
+        Some(slot) => {
+            let date = start_end_to_natural(&date_of_input, &slot.starts_at, &slot.ends_at);
+            FilledInput {
+                input: format!("{stripped} {date}"),
+                warning: None,
+            }
+        }
+        None => FilledInput {
+            input: stripped,
+            warning: Some(format!(
+                "No free slot of {} minutes found",
+                query.duration.num_minutes()
+            )),
+        },
+    })
+}
+
+async fn find_slots_between(
+    conn: DbConn,
+    duration: TimeDelta,
+    from: DateTime<FixedOffset>,
+    to: DateTime<FixedOffset>,
+    constraints: &SlotConstraints,
+) -> anyhow::Result<Vec<FreeSlot>> {
+    let settings = Settings::load(conn.clone()).await?;
+    let calendar_ids = Calendar::list_all(conn.clone())
+        .await?
+        .iter()
+        .map(|c| c.id)
+        .collect();
+    let free_busy =
+        FreeBusy::for_range(conn, calendar_ids, from.to_utc(), to.to_utc(), &[]).await?;
+    slots_in(&free_busy, from, to, duration, constraints, &settings)
+}
+
+/// Every day of the range gives a slot at the start of each free period that is long
+/// enough, and one at the start of the preferred time when it falls inside the period
+fn slots_in(
+    free_busy: &FreeBusy,
+    from: DateTime<FixedOffset>,
+    to: DateTime<FixedOffset>,
+    duration: TimeDelta,
+    constraints: &SlotConstraints,
+    settings: &Settings,
+) -> anyhow::Result<Vec<FreeSlot>> {
+    let start_hour = constraints
+        .day_start_hour
+        .unwrap_or(settings.day_start_hour);
+    let end_hour = constraints.day_end_hour.unwrap_or(settings.day_end_hour);
+    let buffer = TimeDelta::minutes(i64::from(constraints.buffer_minutes));
+
+    let mut slots = Vec::new();
+    let mut day = from.beginning_of_day();
+    let mut days = 0;
+    while day <= to {
+        if days == MAX_DAYS {
+            return Err(anyhow!(
+                "Free slots are searched in at most {MAX_DAYS} days"
+            ));
+        }
+        days += 1;
+        if let (Some(start), Some(end)) = (at_hour(&day, start_hour), at_hour(&day, end_hour)) {
+            let window = (start.max(from.to_utc()), end.min(to.to_utc()));
+            let preferred = constraints.time_of_day.and_then(|t| {
+                let (s, e) = t.period_hours();
+                Some((at_hour(&day, s)?, at_hour(&day, e)?))
+            });
+            for free in free_busy.free_within(window, buffer) {
+                slots.extend(slots_in_free(free, duration, preferred));
+            }
+        }
+        day = day
+            .checked_add_days(Days::new(1))
+            .ok_or(anyhow!("Date out of range"))?;
+    }
+
+    slots.sort_by_key(|s| (!s.preferred, s.starts_at));
+    Ok(slots)
+}
+
+fn slots_in_free(
+    free: Interval,
+    duration: TimeDelta,
+    preferred: Option<Interval>,
+) -> Vec<FreeSlot> {
+    if free.1 - free.0 < duration {
+        return vec![];
+    }
+    let mut starts = vec![free.0];
+    if let Some((preferred_start, _)) = preferred
+        && preferred_start > free.0
+        && free.1 - preferred_start >= duration
+    {
+        starts.push(preferred_start);
+    }
+    starts
+        .into_iter()
+        .map(|starts_at| FreeSlot {
+            starts_at,
+            ends_at: starts_at + duration,
+            preferred: preferred.is_some_and(|(s, e)| starts_at >= s && starts_at + duration <= e),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, day, hour, 0, 0).unwrap()
+    }
+
+    fn free_busy() -> FreeBusy {
+        FreeBusy {
+            start: at(3, 0),
+            end: at(5, 0),
+            busy: vec![(at(3, 8), at(3, 12)), (at(4, 8), at(4, 16))],
+        }
+    }
+
+    #[test]
+    fn test_earliest_slots_first() {
+        let slots = slots_in(
+            &free_busy(),
+            at(3, 0).fixed_offset(),
+            at(4, 23).fixed_offset(),
+            TimeDelta::hours(2),
+            &SlotConstraints {
+                buffer_minutes: 30,
+                ..Default::default()
+            },
+            &Settings::default(),
+        )
+        .unwrap();
+        let starts = slots.iter().map(|s| s.starts_at).collect::<Vec<_>>();
+        assert_eq!(
+            starts,
+            vec![
+                at(3, 12) + TimeDelta::minutes(30),
+                at(4, 16) + TimeDelta::minutes(30)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_preferred_time_ranks_first() {
+        let slots = slots_in(
+            &free_busy(),
+            at(3, 0).fixed_offset(),
+            at(4, 23).fixed_offset(),
+            TimeDelta::hours(1),
+            &SlotConstraints {
+                time_of_day: Some(NamedTime::Evening),
+                ..Default::default()
+            },
+            &Settings::default(),
+        )
+        .unwrap();
+        let ranked = slots
+            .iter()
+            .map(|s| (s.starts_at, s.preferred))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ranked,
+            vec![
+                (at(3, 18), true),
+                (at(4, 18), true),
+                (at(3, 12), false),
+                (at(4, 16), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_range_over_max_days_is_an_error() {
+        let from = at(1, 0);
+        let to = from + TimeDelta::days(MAX_DAYS as i64);
+        let free_busy = FreeBusy {
+            start: from,
+            end: to,
+            busy: vec![],
+        };
+        let slots_until = |to: DateTime<Utc>| {
+            slots_in(
+                &free_busy,
+                from.fixed_offset(),
+                to.fixed_offset(),
+                TimeDelta::hours(1),
+                &SlotConstraints::default(),
+                &Settings::default(),
+            )
+        };
+        assert!(slots_until(to - TimeDelta::hours(1)).is_ok());
+        assert!(slots_until(to).is_err());
+    }
+}
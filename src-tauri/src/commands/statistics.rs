@@ -0,0 +1,21 @@
+use chrono::{DateTime, FixedOffset};
+use tauri::State;
+
+use crate::{
+    app_state::AppState, commands::errors::CommandError, models::statistics::Statistics,
+    util::DateTimeStr,
+};
+
+/// Statistics from the day of `from` to the day of `to`, both included
+#[tauri::command()]
+#[specta::specta]
+pub async fn get_statistics(
+    state: State<'_, AppState>,
+    from: String,
+    to: String,
+) -> Result<Statistics, CommandError> {
+    let from: DateTime<FixedOffset> = DateTimeStr(from).try_into()?;
+    let to: DateTime<FixedOffset> = DateTimeStr(to).try_into()?;
+    let statistics = Statistics::for_range(state.db.clone(), from, to).await?;
+    Ok(statistics)
+}
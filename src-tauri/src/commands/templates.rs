@@ -0,0 +1,49 @@
+use anyhow::anyhow;
+use tauri::State;
+
+use crate::{
+    app_state::AppState,
+    commands::errors::CommandError,
+    db_conn::DbConn,
+    models::{model_traits::ListAll, named_item::NamedItem, template::Template},
+};
+
+#[tauri::command()]
+#[specta::specta]
+pub async fn list_templates(state: State<'_, AppState>) -> Result<Vec<Template>, CommandError> {
+    let templates = Template::list_all(state.db.clone()).await?;
+    Ok(templates)
+}
+
+/// Creates the template or replaces the one with the same name
+#[tauri::command()]
+#[specta::specta]
+pub async fn save_template(
+    state: State<'_, AppState>,
+    template: Template,
+) -> Result<(), CommandError> {
+    Template::validate_name(&template.name)?;
+    template.save(state.db.clone()).await?;
+    Ok(())
+}
+
+#[tauri::command()]
+#[specta::specta]
+pub async fn delete_template(state: State<'_, AppState>, name: String) -> Result<(), CommandError> {
+    if !Template::delete_by_name(state.db.clone(), &name).await? {
+        return Err(anyhow!("No template named {name}").into());
+    }
+    Ok(())
+}
+
+/// Expanded input when it starts with the trigger of a template, like `:standup tomorrow`.
+/// Without a template of that name the input is parsed as it is, it may just start with `:`
+pub(crate) async fn expand_template(conn: DbConn, input: &str) -> anyhow::Result<Option<String>> {
+    let Some((name, args)) = Template::trigger(input) else {
+        return Ok(None);
+    };
+    let Some(template) = Template::by_name(conn, &name).await? else {
+        return Ok(None);
+    };
+    Ok(Some(template.expand(&args)?))
+}
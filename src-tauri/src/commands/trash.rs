@@ -0,0 +1,211 @@
+use anyhow::anyhow;
+use chrono::{DateTime, TimeDelta, Utc};
+use diesel::Connection;
+use log::warn;
+use tauri::State;
+
+use crate::{
+    app_state::AppState,
+    commands::{components::push_vcmp, errors::CommandError},
+    db_conn::DbConn,
+    models::{
+        VCmp, VCmpBuilder, VCmpId,
+        journal::JournalEntry,
+        model_traits::{ById, ListAll},
+        settings::Settings,
+        trash::TrashEntry,
+    },
+};
+
+#[derive(Clone, Debug, serde::Serialize, specta::Type)]
+pub struct TrashItem {
+    /// Id to reference the entry in `restore_from_trash`
+    pub id: i32,
+    /// Id the component had before being deleted
+    pub vcmp_id: VCmpId,
+    pub calendar_id: i32,
+    pub summary: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl TryFrom<&TrashEntry> for TrashItem {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: &TrashEntry) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: entry.id,
+            vcmp_id: entry.get_vcmp_id()?,
+            calendar_id: entry.calendar_id,
+            summary: entry.summary.clone(),
+            deleted_at: entry.deleted_at,
+        })
+    }
+}
+
+#[tauri::command()]
+#[specta::specta]
+pub async fn list_trash(state: State<'_, AppState>) -> Result<Vec<TrashItem>, CommandError> {
+    let conn = state.db.clone();
+    purge_expired_trash(conn.clone()).await?;
+
+    let items = TrashEntry::list_all(conn)
+        .await?
+        .iter()
+        .map(TrashItem::try_from)
+        .collect::<anyhow::Result<Vec<TrashItem>>>()?;
+    Ok(items)
+}
+
+/// Restores the component, returns the id it has now
+#[tauri::command()]
+#[specta::specta]
+pub async fn restore_from_trash(
+    state: State<'_, AppState>,
+    trash_id: i32,
+) -> Result<VCmpId, CommandError> {
+    let res = restore_entry(state.db.clone(), trash_id).await?;
+    Ok(res)
+}
+
+#[tauri::command()]
+#[specta::specta]
+pub async fn empty_trash(state: State<'_, AppState>) -> Result<(), CommandError> {
+    TrashEntry::delete_all(state.db.clone()).await?;
+    Ok(())
+}
+
+/// Deletes the entries older than the retention setting
+pub(crate) async fn purge_expired_trash(conn: DbConn) -> anyhow::Result<usize> {
+    purge_expired_trash_at(conn, Utc::now()).await
+}
+
+/// Deletes the entries that are older than the retention setting at `now`
+pub(crate) async fn purge_expired_trash_at(
+    conn: DbConn,
+    now: DateTime<Utc>,
+) -> anyhow::Result<usize> {
+    let settings = Settings::load(conn.clone()).await?;
+    // Nothing was deleted that long ago
+    let Some(deleted_before) =
+        now.checked_sub_signed(TimeDelta::days(settings.trash_retention_days.into()))
+    else {
+        return Ok(0);
+    };
+    TrashEntry::purge(conn, deleted_before).await
+}
+
+pub(crate) async fn restore_entry(conn: DbConn, trash_id: i32) -> anyhow::Result<VCmpId> {
+    let entry = TrashEntry::by_id(conn.clone(), trash_id)
+        .await?
+        .ok_or(anyhow!("No trash entry with id {trash_id}"))?;
+    let trashed = entry.get_vcmp()?;
+    let (trashed_id, trashed_uid) = (trashed.get_id(), trashed.get_uid());
+    let recreated = VCmpBuilder::from(&trashed.into_unsynced())
+        .ical_data(entry.ical_data.clone())
+        .build_new()?;
+
+    let (restored, created) = conn
+        .run(move |conn| {
+            conn.transaction::<_, anyhow::Error, _>(|conn| {
+                let (restored, created) = match VCmp::by_id_with_deleted_in(conn, trashed_id)? {
+                    // The delete didn't reach the server yet, cancelling it is enough
+                    Some(current) if current.is_deleted() && current.get_uid() == trashed_uid => {
+                        let restored = current.into_undeleted();
+                        restored.replace_in(conn)?;
+                        (restored, false)
+                    }
+                    _ => (recreated.create_in(conn)?, true),
+                };
+                TrashEntry::delete_by_id_in(conn, entry.id)?;
+                JournalEntry::record_in(conn, None, Some(&restored))?;
+                Ok((restored, created))
+            })
+        })
+        .await?;
+
+    let restored_id = restored.get_id();
+    if created && let Err(e) = push_vcmp(conn, restored).await {
+        warn!("Restored {restored_id} will be pushed on the next sync: {e}");
+    }
+    Ok(restored_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+    use crate::{
+        commands::components::remove_vcmp,
+        test_utils::{create_todo, new_local_todo, new_synced_todo},
+    };
+
+    #[test]
+    fn test_restore_cancels_pending_delete() {
+        let conn = DbConn::in_memory().unwrap();
+        let created = VCmp::Todo(create_todo(conn.clone(), new_synced_todo("todo.ics")));
+        block_on(remove_vcmp(conn.clone(), &created)).unwrap();
+
+        let trash = block_on(TrashEntry::list_all(conn.clone())).unwrap();
+        assert_eq!(trash.len(), 1);
+        assert!(trash[0].ical_data.contains("BEGIN:VTODO"));
+
+        let restored = block_on(restore_entry(conn.clone(), trash[0].id)).unwrap();
+        assert_eq!(restored, created.get_id());
+        let vcmp = block_on(VCmp::by_id(conn.clone(), restored))
+            .unwrap()
+            .unwrap();
+        assert_eq!(vcmp.get_etag(), created.get_etag());
+        assert!(block_on(TrashEntry::list_all(conn)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_restore_after_local_delete() {
+        let conn = DbConn::in_memory().unwrap();
+        let created = VCmp::Todo(create_todo(conn.clone(), new_local_todo("todo.ics")));
+        block_on(remove_vcmp(conn.clone(), &created)).unwrap();
+        assert!(
+            block_on(VCmp::by_id_with_deleted(conn.clone(), created.get_id()))
+                .unwrap()
+                .is_none()
+        );
+
+        let trash = block_on(TrashEntry::list_all(conn.clone())).unwrap();
+        // There is no server for the calendar, so the push fails and it stays unsynced
+        let restored = block_on(restore_entry(conn.clone(), trash[0].id)).unwrap();
+        let vcmp = block_on(VCmp::by_id(conn, restored)).unwrap().unwrap();
+        assert_eq!(vcmp.get_uid(), created.get_uid());
+        assert!(vcmp.get_synced_at().is_none());
+    }
+
+    #[test]
+    fn test_purge_uses_retention() {
+        let conn = DbConn::in_memory().unwrap();
+        let created = VCmp::Todo(create_todo(conn.clone(), new_local_todo("todo.ics")));
+        block_on(remove_vcmp(conn.clone(), &created)).unwrap();
+
+        assert_eq!(block_on(purge_expired_trash(conn.clone())).unwrap(), 0);
+        let settings = Settings {
+            trash_retention_days: 7,
+            ..Default::default()
+        };
+        block_on(settings.save(conn.clone())).unwrap();
+        let in_a_week = Utc::now() + TimeDelta::days(7);
+        assert_eq!(
+            block_on(purge_expired_trash_at(
+                conn.clone(),
+                in_a_week - TimeDelta::hours(1)
+            ))
+            .unwrap(),
+            0
+        );
+        assert_eq!(
+            block_on(purge_expired_trash_at(
+                conn,
+                in_a_week + TimeDelta::hours(1)
+            ))
+            .unwrap(),
+            1
+        );
+    }
+}
@@ -1,20 +1,130 @@
-use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use diesel::SqliteConnection;
+use anyhow::anyhow;
+use diesel::{
+    SqliteConnection,
+    connection::SimpleConnection,
+    r2d2::{ConnectionManager, CustomizeConnection, Pool, PooledConnection},
+};
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
 use tauri::async_runtime::spawn_blocking;
 
-use crate::establish_connection;
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
 
+/// How long a connection waits for a lock held by another connection
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub type PooledConn = PooledConnection<ConnectionManager<SqliteConnection>>;
+
+/// Sets the pragmas every connection of the pool needs
+#[derive(Debug)]
+struct ConnectionOptions;
+
+impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for ConnectionOptions {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
+        conn.batch_execute(&format!(
+            "PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL; PRAGMA busy_timeout = {};",
+            BUSY_TIMEOUT.as_millis()
+        ))
+        .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+/// Pool of connections to the database, cheap to clone
 #[derive(Clone)]
-pub struct DbConn(pub Arc<Mutex<SqliteConnection>>);
+pub struct DbConn(Pool<ConnectionManager<SqliteConnection>>);
 
 impl DbConn {
-    pub async fn new() -> anyhow::Result<Self> {
-        let res = spawn_blocking(|| {
-            let conn = establish_connection();
-            Self(Arc::new(Mutex::new(conn)))
+    /// Connects to the database and runs the pending migrations
+    pub fn new(connection_url: &str) -> anyhow::Result<Self> {
+        let pool = Pool::builder()
+            .connection_customizer(Box::new(ConnectionOptions))
+            .build(ConnectionManager::new(connection_url))?;
+        let db = Self(pool);
+        db.run_migrations()?;
+        Ok(db)
+    }
+
+    /// Migrated database living in memory, used for tests
+    ///
+    /// Uses a single connection that is never recycled, since each connection
+    /// to `:memory:` is a different database
+    pub fn in_memory() -> anyhow::Result<Self> {
+        let pool = Pool::builder()
+            .max_size(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connection_customizer(Box::new(ConnectionOptions))
+            .build(ConnectionManager::new(":memory:"))?;
+        let db = Self(pool);
+        db.run_migrations()?;
+        Ok(db)
+    }
+
+    /// Gets a connection from the pool, waiting if all of them are in use
+    pub fn get(&self) -> anyhow::Result<PooledConn> {
+        Ok(self.0.get()?)
+    }
+
+    /// Runs `f` on a blocking thread with a connection of the pool
+    ///
+    /// The connection is taken on that thread too, so waiting for a free one doesn't
+    /// stall the async runtime
+    pub async fn run<T, E, F>(&self, f: F) -> anyhow::Result<T>
+    where
+        T: Send + 'static,
+        E: Into<anyhow::Error>,
+        F: FnOnce(&mut SqliteConnection) -> Result<T, E> + Send + 'static,
+    {
+        let pool = self.0.clone();
+        spawn_blocking(move || {
+            let mut pooled = pool.get()?;
+            f(&mut *pooled).map_err(Into::into)
         })
-        .await?;
-        Ok(res)
+        .await?
+    }
+
+    fn run_migrations(&self) -> anyhow::Result<()> {
+        let mut pooled = self.get()?;
+        let conn: &mut SqliteConnection = &mut pooled;
+        conn.run_pending_migrations(MIGRATIONS)
+            .map_err(|e| anyhow!("{e:?}"))?;
+        log::info!("migration ran correctly");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel::prelude::*;
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+    use crate::{
+        models::{
+            model_traits::ListAll,
+            server::{NewServer, Server},
+        },
+        schema::servers,
+    };
+
+    #[test]
+    fn test_in_memory_is_shared() {
+        let db = DbConn::in_memory().unwrap();
+        let mut pooled = db.get().unwrap();
+        diesel::insert_into(servers::table)
+            .values(NewServer {
+                server_url: "https://example.com".to_string(),
+                user: "user".to_string(),
+                password: "password".to_string(),
+                last_sync: None,
+            })
+            .execute(&mut *pooled)
+            .unwrap();
+        drop(pooled);
+
+        let servers = block_on(Server::list_all(db.clone())).unwrap();
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].server_url, "https://example.com");
     }
 }
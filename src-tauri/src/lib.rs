@@ -1,17 +1,16 @@
-use anyhow::anyhow;
-use diesel::prelude::*;
-use diesel::sqlite::SqliteConnection;
-use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
-use lazy_static::lazy_static;
-use log::info;
+use log::{info, warn};
 use specta_typescript::{BigIntExportBehavior, Typescript};
-use std::error::Error;
 use std::fs::create_dir_all;
-use std::sync::Mutex;
 use tauri::{Listener, Manager, async_runtime, tray::TrayIconBuilder};
 use tauri_specta::{Builder, collect_commands};
 
-use crate::{app_state::AppState, commands::calendar::internal_super_sync_calendar};
+use crate::{
+    app_state::AppState,
+    commands::{
+        calendar::internal_super_sync_calendar, rollover::run_daily_rollover,
+        trash::purge_expired_trash,
+    },
+};
 pub mod app_state;
 pub mod caldav;
 pub mod calendar_items;
@@ -19,31 +18,10 @@ mod commands;
 pub mod db_conn;
 pub mod models;
 pub mod schema;
+#[cfg(test)]
+mod test_utils;
 pub(crate) mod util;
 
-pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
-
-lazy_static! {
-    static ref CONNECTION_URL: Mutex<String> = Mutex::new(String::new());
-}
-
-pub fn establish_connection() -> SqliteConnection {
-    let connection_url = CONNECTION_URL.lock().unwrap();
-    SqliteConnection::establish(&connection_url).unwrap_or_else(|_| {
-        log::error!("Error connecting to {}", *connection_url);
-        panic!("Error connecting to {}", *connection_url)
-    })
-}
-
-pub fn setup_db(connection_url: &str) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
-    let mut locked_url = CONNECTION_URL.lock().unwrap();
-    *locked_url = connection_url.to_owned();
-    drop(locked_url); // Release the lock before establishing the connection
-    let mut conn = establish_connection();
-    conn.run_pending_migrations(MIGRATIONS)?;
-    Ok(())
-}
-
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct SyncEventPayload {
     calendar_id: i32,
@@ -73,9 +51,44 @@ pub fn run() {
             commands::components::parse_event,
             commands::components::create_component,
             commands::components::set_vcmp_status,
+            commands::components::set_occurrence_status,
             commands::components::delete_vcmp,
             commands::components::update_vcmp,
+            commands::components::update_vcmp_fields,
+            commands::components::postpone_vcmp,
+            commands::components::list_most_postponed,
             commands::components::list_unscheduled_todos,
+            commands::components::list_overdue_todos,
+            commands::components::list_subtasks,
+            commands::history::undo,
+            commands::history::redo,
+            commands::trash::list_trash,
+            commands::trash::restore_from_trash,
+            commands::trash::empty_trash,
+            commands::settings::get_settings,
+            commands::settings::update_settings,
+            commands::statistics::get_statistics,
+            commands::scheduler::preview_schedule,
+            commands::scheduler::apply_schedule,
+            commands::free_busy::export_free_busy,
+            commands::slots::find_slots,
+            commands::conflicts::list_conflicts,
+            commands::shift_day::preview_shift_day,
+            commands::shift_day::apply_shift_day,
+            commands::routines::list_routines,
+            commands::routines::save_routine,
+            commands::routines::delete_routine,
+            commands::routines::instantiate_routine,
+            commands::templates::list_templates,
+            commands::templates::save_template,
+            commands::templates::delete_template,
+            commands::bulk::bulk_set_status,
+            commands::bulk::bulk_move_to_calendar,
+            commands::bulk::bulk_add_tag,
+            commands::bulk::bulk_remove_tag,
+            commands::bulk::bulk_reschedule,
+            commands::bulk::bulk_delete,
+            commands::habits::list_habits,
         ]);
 
     #[cfg(debug_assertions)] // <- Only export on non-release builds
@@ -101,15 +114,20 @@ pub fn run() {
             }
 
             println!("Connection URL: {conn_url}");
-            if let Err(e) = setup_db(&conn_url) {
+            let state = AppState::new(conn_url).map_err(|e| {
                 println!("Database setup failed: {e}");
-            }
-
-            let Ok(state) = AppState::new(conn_url) else {
-                return Err(anyhow!("Could not connect").into_boxed_dyn_error());
-            };
+                e.into_boxed_dyn_error()
+            })?;
 
+            let db = state.db.clone();
             app.manage(state);
+            async_runtime::spawn(run_daily_rollover(db.clone()));
+            async_runtime::spawn(async move {
+                match purge_expired_trash(db).await {
+                    Ok(purged) => info!("Purged {purged} entries from the trash"),
+                    Err(e) => warn!("Could not purge the trash: {e}"),
+                }
+            });
             let handle = app.handle().clone();
             app.listen("sync", move |event| {
                 let handle = handle.clone();
@@ -117,7 +135,9 @@ pub fn run() {
                     if let Ok(payload) = serde_json::from_str::<SyncEventPayload>(event.payload()) {
                         let state = handle.state::<AppState>();
                         let lock = state.syncing.write().await;
-                        let res = internal_super_sync_calendar(payload.calendar_id).await;
+                        let res =
+                            internal_super_sync_calendar(state.db.clone(), payload.calendar_id)
+                                .await;
                         drop(lock);
                         info!("Sync resulted in {res:?}");
                     }
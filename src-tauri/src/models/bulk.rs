@@ -0,0 +1,344 @@
+//! One change applied to many components at once.
+//!
+//! Everything runs in a single transaction and each component in its own savepoint, so
+//! a component that can't be changed is reported without undoing the other ones. A
+//! component moved to another calendar is created again there and the delete of its old
+//! resource is left to the next sync.
+
+use anyhow::anyhow;
+use chrono::{DateTime, FixedOffset, TimeDelta, Utc};
+use diesel::prelude::*;
+
+use crate::{
+    calendar_items::{
+        component_props::{merge_tags, normalize_tag},
+        event_status::EventStatus,
+    },
+    db_conn::DbConn,
+    models::{
+        VCmp, VCmpId, journal::JournalEntry, todo_occurrence::TodoOccurrence, trash::TrashEntry,
+        vevent::VEvent, vtodo::VTodo,
+    },
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BulkAction {
+    /// Recurring components only change the status of their occurrence on the day of `day`
+    SetStatus {
+        status: EventStatus,
+        day: DateTime<FixedOffset>,
+    },
+    AddTag(String),
+    RemoveTag(String),
+    /// Moves the start and the end, recurring and unscheduled components are left as is
+    Reschedule(TimeDelta),
+    Delete,
+    /// `calendar_url` is the url of the calendar in `calendar_id`
+    MoveToCalendar {
+        calendar_id: i32,
+        calendar_url: String,
+    },
+}
+
+/// Outcome of a bulk action for one component, `error` is set when it was left unchanged
+#[derive(Clone, Debug, PartialEq, serde::Serialize, specta::Type)]
+pub struct BulkItemResult {
+    pub vcmp_id: VCmpId,
+    pub error: Option<String>,
+}
+
+impl BulkAction {
+    /// Applies the action to every component, each change is journaled and left out of
+    /// sync so the next sync pushes it
+    pub async fn apply(
+        self,
+        conn: DbConn,
+        vcmp_ids: Vec<VCmpId>,
+        date_of_change: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<BulkItemResult>> {
+        let results = conn
+            .run(move |conn| {
+                conn.transaction::<_, anyhow::Error, _>(|conn| {
+                    let results = vcmp_ids
+                        .into_iter()
+                        .map(|vcmp_id| {
+                            let res = conn.transaction::<_, anyhow::Error, _>(|conn| {
+                                self.apply_to(conn, vcmp_id, date_of_change)
+                            });
+                            BulkItemResult {
+                                vcmp_id,
+                                error: res.err().map(|e| e.to_string()),
+                            }
+                        })
+                        .collect::<Vec<BulkItemResult>>();
+                    Ok(results)
+                })
+            })
+            .await?;
+        Ok(results)
+    }
+
+    fn apply_to(
+        &self,
+        conn: &mut SqliteConnection,
+        vcmp_id: VCmpId,
+        date_of_change: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        let before = VCmp::by_id_in(conn, vcmp_id)?.ok_or(anyhow!("No cmp with id {vcmp_id}"))?;
+        if let BulkAction::Delete = self {
+            TrashEntry::put_in(conn, &before)?;
+            before.remove_in(conn)?;
+            return JournalEntry::record_in(conn, Some(&before), None);
+        }
+        if let BulkAction::MoveToCalendar {
+            calendar_id,
+            calendar_url,
+        } = self
+        {
+            if before.get_calendar_id() != *calendar_id {
+                before
+                    .moved_to_calendar(*calendar_id, calendar_url)
+                    .into_new_resource(calendar_url)?
+                    .create_in_place_of_in(conn, &before)?;
+            }
+            return Ok(());
+        }
+        if let BulkAction::SetStatus { status, day } = self
+            && before.has_rrule()
+        {
+            return TodoOccurrence::set_status_in(conn, &before, *status, day, date_of_change);
+        }
+
+        let after = self.changed(&before, date_of_change)?;
+        after.replace_in(conn)?;
+        JournalEntry::record_in(conn, Some(&before), Some(&after))
+    }
+
+    /// Component with the action applied, not saved yet
+    fn changed(&self, vcmp: &VCmp, date_of_change: DateTime<Utc>) -> anyhow::Result<VCmp> {
+        let mut changed = vcmp.clone();
+        match self {
+            BulkAction::SetStatus { status, .. } => changed.set_status(*status, date_of_change),
+            BulkAction::AddTag(tag) => {
+                let tags = tags_mut(&mut changed);
+                *tags = merge_tags(tags.take(), vec![tag.clone()]);
+            }
+            BulkAction::RemoveTag(tag) => {
+                let tag = normalize_tag(tag);
+                let tags = tags_mut(&mut changed);
+                let kept = tags
+                    .take()
+                    .unwrap_or_default()
+                    .split(',')
+                    .filter(|t| Some(t.to_string()) != tag)
+                    .collect::<Vec<&str>>()
+                    .join(",");
+                *tags = merge_tags(Some(kept), vec![]);
+            }
+            BulkAction::Reschedule(by) => match &mut changed {
+                VCmp::Event(VEvent {
+                    has_rrule: true, ..
+                })
+                | VCmp::Todo(VTodo {
+                    has_rrule: true, ..
+                }) => {
+                    return Err(anyhow!("Recurring items can't be rescheduled"));
+                }
+                VCmp::Event(vevent) => {
+                    vevent.starts_at += *by;
+                    vevent.ends_at += *by;
+                }
+                VCmp::Todo(vtodo) => {
+                    let (Some(starts_at), Some(ends_at)) = (vtodo.starts_at, vtodo.ends_at) else {
+                        return Err(anyhow!("{} has no date to reschedule", vtodo.summary));
+                    };
+                    vtodo.starts_at = Some(starts_at + *by);
+                    vtodo.ends_at = Some(ends_at + *by);
+                }
+            },
+            BulkAction::Delete | BulkAction::MoveToCalendar { .. } => {}
+        }
+
+        match &mut changed {
+            VCmp::Event(vevent) => vevent.out_of_sync = true,
+            VCmp::Todo(vtodo) => vtodo.out_of_sync = true,
+        }
+        Ok(changed)
+    }
+}
+
+fn tags_mut(vcmp: &mut VCmp) -> &mut Option<String> {
+    match vcmp {
+        VCmp::Event(vevent) => &mut vevent.tag,
+        VCmp::Todo(vtodo) => &mut vtodo.tag,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+    use crate::{
+        schema::{vevents, vtodos},
+        test_utils::{create_event, create_todo, new_event, new_synced_todo, new_todo},
+    };
+
+    fn create_items(conn: DbConn) -> (VCmp, VCmp) {
+        let event = create_event(conn.clone(), new_event("basic.ics"));
+        let todo = create_todo(conn, new_todo("todo_date.ics"));
+        (VCmp::Event(event), VCmp::Todo(todo))
+    }
+
+    #[test]
+    fn test_failing_item_keeps_the_others() {
+        let conn = DbConn::in_memory().unwrap();
+        let (event, todo) = create_items(conn.clone());
+        let ids = vec![event.get_id(), todo.get_id()];
+
+        let results = block_on(BulkAction::Reschedule(TimeDelta::hours(1)).apply(
+            conn.clone(),
+            ids.clone(),
+            Utc::now(),
+        ))
+        .unwrap();
+        // The event repeats, only the todo moves
+        assert!(results[0].error.is_some());
+        assert_eq!(results[1].error, None);
+        let VCmp::Todo(VTodo {
+            starts_at,
+            out_of_sync,
+            ..
+        }) = block_on(VCmp::by_id(conn.clone(), todo.get_id()))
+            .unwrap()
+            .unwrap()
+        else {
+            panic!("Expected a todo");
+        };
+        let VCmp::Todo(before) = todo else {
+            panic!("Expected a todo");
+        };
+        assert_eq!(starts_at, before.starts_at.map(|s| s + TimeDelta::hours(1)));
+        assert!(out_of_sync);
+
+        block_on(BulkAction::AddTag("#Work".to_string()).apply(conn.clone(), ids, Utc::now()))
+            .unwrap();
+        let event = block_on(VCmp::by_id(conn, event.get_id()))
+            .unwrap()
+            .unwrap();
+        let VCmp::Event(vevent) = event else {
+            panic!("Expected an event");
+        };
+        assert_eq!(vevent.tag, Some("health,work".to_string()));
+    }
+
+    #[test]
+    fn test_delete_many() {
+        let conn = DbConn::in_memory().unwrap();
+        let (event, todo) = create_items(conn.clone());
+        let missing = VCmpId::Todo(todo.get_id().into_parts().1 + 1);
+
+        let results = block_on(BulkAction::Delete.apply(
+            conn.clone(),
+            vec![event.get_id(), todo.get_id(), missing],
+            Utc::now(),
+        ))
+        .unwrap();
+        let errors = results
+            .iter()
+            .map(|r| r.error.is_some())
+            .collect::<Vec<bool>>();
+        assert_eq!(errors, vec![false, false, true]);
+        assert!(
+            block_on(VCmp::by_id(conn.clone(), event.get_id()))
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            block_on(VCmp::by_id(conn, todo.get_id()))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_move_many_to_calendar() {
+        let conn = DbConn::in_memory().unwrap();
+        let event = VCmp::Event(create_event(conn.clone(), new_event("basic.ics")));
+        let todo = VCmp::Todo(create_todo(conn.clone(), new_synced_todo("todo.ics")));
+
+        let results = block_on(
+            BulkAction::MoveToCalendar {
+                calendar_id: 2,
+                calendar_url: "/other/".to_string(),
+            }
+            .apply(
+                conn.clone(),
+                vec![event.get_id(), todo.get_id()],
+                Utc::now(),
+            ),
+        )
+        .unwrap();
+        assert!(results.iter().all(|r| r.error.is_none()));
+        // The synced todo waits for the delete of its old resource
+        let old = block_on(VCmp::by_id_with_deleted(conn.clone(), todo.get_id()))
+            .unwrap()
+            .unwrap();
+        assert!(old.is_deleted());
+        let moved = block_on(conn.run(|conn| {
+            vtodos::table
+                .filter(vtodos::deleted_at.is_null())
+                .select(VTodo::as_select())
+                .first(conn)
+        }))
+        .unwrap();
+        assert_eq!(moved.uid, old.get_uid());
+        assert_eq!(moved.calendar_id, 2);
+        assert_eq!(moved.href, Some(format!("/other/{}.ics", moved.uid)));
+        assert_eq!(moved.synced_at, None);
+        // The event was never synced, nothing is left behind
+        let calendars = block_on(conn.run(|conn| {
+            vevents::table
+                .select(vevents::calendar_id)
+                .load::<i32>(conn)
+        }))
+        .unwrap();
+        assert_eq!(calendars, vec![2]);
+    }
+
+    #[test]
+    fn test_set_status_of_recurring_todo_occurrence() {
+        let conn = DbConn::in_memory().unwrap();
+        // Daily reminder at 08:00 from 20/05/24
+        let todo = create_todo(conn.clone(), new_todo("todo_recurring.ics"));
+        let day = Utc.with_ymd_and_hms(2024, 5, 21, 10, 0, 0).unwrap();
+
+        let results = block_on(
+            BulkAction::SetStatus {
+                status: EventStatus::Done,
+                day: day.fixed_offset(),
+            }
+            .apply(conn.clone(), vec![VCmpId::Todo(todo.id)], day),
+        )
+        .unwrap();
+        assert_eq!(results[0].error, None);
+        let occurrences = block_on(TodoOccurrence::for_uids_between(
+            conn.clone(),
+            vec![todo.uid.clone()],
+            day - TimeDelta::hours(10),
+            day + TimeDelta::hours(14),
+        ))
+        .unwrap();
+        let occurrence_start = Utc.with_ymd_and_hms(2024, 5, 21, 8, 0, 0).unwrap();
+        assert_eq!(
+            occurrences[&(todo.uid.clone(), occurrence_start)].status,
+            EventStatus::Done
+        );
+        let Some(VCmp::Todo(series)) = block_on(VCmp::by_id(conn, VCmpId::Todo(todo.id))).unwrap()
+        else {
+            panic!("Expected a todo");
+        };
+        assert_eq!(series.status, todo.status);
+    }
+}
@@ -0,0 +1,19 @@
+use chrono::{DateTime, Utc};
+
+use crate::models::VCmpId;
+
+/// Occurrence of an event that takes the same time as another item
+#[derive(Clone, Debug, PartialEq, serde::Serialize, specta::Type)]
+pub struct ConflictingItem {
+    pub vcmp_id: VCmpId,
+    pub summary: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+/// Two occurrences overlapping each other, `first` starts earlier
+#[derive(Clone, Debug, PartialEq, serde::Serialize, specta::Type)]
+pub struct Conflict {
+    pub first: ConflictingItem,
+    pub second: ConflictingItem,
+}
@@ -0,0 +1,88 @@
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone};
+
+use crate::models::{
+    free_busy::{Interval, at_hour, free_between},
+    settings::Settings,
+};
+
+/// Load of a day compared with its budget
+#[derive(Clone, Debug, PartialEq, serde::Serialize, specta::Type)]
+pub struct DaySummary {
+    pub day: NaiveDate,
+    /// Sum of the load of the events and todos of the day, done ones included
+    pub scheduled_load: i32,
+    pub load_budget: i32,
+    /// Minutes of the working day not taken by events
+    pub free_minutes: i32,
+    pub over_budget: bool,
+}
+
+impl DaySummary {
+    /// `busy` are the times taken by the events of the day
+    pub fn new<Tz: TimeZone>(
+        day: &DateTime<Tz>,
+        settings: &Settings,
+        scheduled_load: i32,
+        busy: &[Interval],
+    ) -> Self {
+        let load_budget = settings.load_budget_for(day.weekday());
+        Self {
+            day: day.date_naive(),
+            scheduled_load,
+            load_budget,
+            free_minutes: free_minutes(day, settings, busy),
+            over_budget: scheduled_load > load_budget,
+        }
+    }
+
+    /// Same day with an item of `load` added to it
+    pub fn with_added_load(self, load: i32) -> Self {
+        let scheduled_load = self.scheduled_load + load;
+        Self {
+            scheduled_load,
+            over_budget: scheduled_load > self.load_budget,
+            ..self
+        }
+    }
+}
+
+/// Minutes between the start and the end of the working day not covered by any busy time
+fn free_minutes<Tz: TimeZone>(day: &DateTime<Tz>, settings: &Settings, busy: &[Interval]) -> i32 {
+    let (Some(start), Some(end)) = (
+        at_hour(day, settings.day_start_hour),
+        at_hour(day, settings.day_end_hour),
+    ) else {
+        return 0;
+    };
+
+    let free = free_between((start, end), busy.to_vec())
+        .iter()
+        .map(|(s, e)| (*e - *s).num_minutes())
+        .sum::<i64>();
+    i32::try_from(free).unwrap_or(i32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    #[test]
+    fn test_overlapping_busy_times_count_once() {
+        let at = |hour: u32| Utc.with_ymd_and_hms(2024, 6, 3, hour, 0, 0).unwrap();
+        let settings = Settings {
+            daily_load_budget: 4,
+            ..Default::default()
+        };
+        let summary = DaySummary::new(
+            &at(9),
+            &settings,
+            5,
+            &[(at(7), at(10)), (at(9), at(11)), (at(19), at(22))],
+        );
+        // From 8:00 to 20:00 minus 8:00 to 11:00 and 19:00 to 20:00
+        assert_eq!(summary.free_minutes, 8 * 60);
+        assert!(summary.over_budget);
+    }
+}
@@ -0,0 +1,273 @@
+//! Busy time of a set of calendars, exported as a `VFREEBUSY` (RFC 5545 3.6.4).
+//!
+//! Events marked `TRANSP:TRANSPARENT` don't take time and are left out, recurring
+//! events are expanded inside the range and overlapping times are merged.
+
+use anyhow::anyhow;
+use chrono::{DateTime, NaiveTime, TimeDelta, TimeZone, Utc};
+use icalendar::Component;
+use now::DateTimeNow;
+
+use crate::{
+    calendar_items::{component_props::ComponentProps, event_type::EventType},
+    db_conn::DbConn,
+    models::{IcalParseableTrait, vevent::VEvent},
+};
+
+/// Most occurrences of a single recurring event taken in the range
+const MAX_OCCURRENCES: u16 = 1000;
+const TRANSPARENT: &str = "TRANSPARENT";
+const ICAL_DATE_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+pub type Interval = (DateTime<Utc>, DateTime<Utc>);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FreeBusy {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Sorted and without overlaps
+    pub busy: Vec<Interval>,
+}
+
+impl FreeBusy {
+    /// Busy time of the calendars between `start` and `end`, the events of the
+    /// `excluded_types` don't count as busy
+    pub async fn for_range(
+        conn: DbConn,
+        calendar_ids: Vec<i32>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        excluded_types: &[EventType],
+    ) -> anyhow::Result<Self> {
+        if end <= start {
+            return Err(anyhow!("The end of the range is before its start"));
+        }
+        let events =
+            VEvent::list_in_calendars_between_or_recurring(conn, calendar_ids, start, end).await?;
+        Ok(Self::from_events(start, end, &events, excluded_types))
+    }
+
+    fn from_events(
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        events: &[VEvent],
+        excluded_types: &[EventType],
+    ) -> Self {
+        let busy = events
+            .iter()
+            .filter(|e| !excluded_types.contains(&e.event_type) && !is_transparent(e))
+            .flat_map(|e| occurrences(e, start, end))
+            .map(|(s, e)| (s.max(start), e.min(end)))
+            .collect();
+        Self {
+            start,
+            end,
+            busy: merge(busy),
+        }
+    }
+
+    /// Free periods inside `window`, keeping `buffer` free before and after the busy ones
+    pub fn free_within(&self, window: Interval, buffer: TimeDelta) -> Vec<Interval> {
+        let busy = self
+            .busy
+            .iter()
+            .map(|(s, e)| (*s - buffer, *e + buffer))
+            .collect();
+        free_between(window, busy)
+    }
+
+    /// `VCALENDAR` with a single `VFREEBUSY`, one `FREEBUSY` line per busy period
+    pub fn to_ics(&self, uid: &str, dtstamp: DateTime<Utc>) -> String {
+        let format = |date: &DateTime<Utc>| date.format(ICAL_DATE_FORMAT).to_string();
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            "PRODID:-//mimido//free busy//EN".to_string(),
+            "METHOD:PUBLISH".to_string(),
+            "BEGIN:VFREEBUSY".to_string(),
+            format!("UID:{uid}"),
+            format!("DTSTAMP:{}", format(&dtstamp)),
+            format!("DTSTART:{}", format(&self.start)),
+            format!("DTEND:{}", format(&self.end)),
+        ];
+        lines.extend(
+            self.busy
+                .iter()
+                .map(|(s, e)| format!("FREEBUSY;FBTYPE=BUSY:{}/{}", format(s), format(e))),
+        );
+        lines.push("END:VFREEBUSY".to_string());
+        lines.push("END:VCALENDAR".to_string());
+        // Every line ends with CRLF, none is long enough to be folded
+        lines.iter().map(|l| format!("{l}\r\n")).collect()
+    }
+}
+
+/// Periods inside `window` not covered by any of `busy`, which can overlap
+pub fn free_between(window: Interval, busy: Vec<Interval>) -> Vec<Interval> {
+    let mut free = Vec::new();
+    let mut cursor = window.0;
+    for (s, e) in merge(busy) {
+        if s >= window.1 {
+            break;
+        }
+        if s > cursor {
+            free.push((cursor, s));
+        }
+        cursor = cursor.max(e);
+    }
+    if window.1 > cursor {
+        free.push((cursor, window.1));
+    }
+    free
+}
+
+/// Time of `hour` on the day of `day`, past the last hour the day ends at midnight
+pub fn at_hour<Tz: TimeZone>(day: &DateTime<Tz>, hour: u32) -> Option<DateTime<Utc>> {
+    match NaiveTime::from_hms_opt(hour, 0, 0) {
+        Some(time) => day.with_time(time).earliest().map(|d| d.to_utc()),
+        None => Some(day.end_of_day().to_utc()),
+    }
+}
+
+/// Marked as not taking time with `TRANSP:TRANSPARENT`
+pub(crate) fn is_transparent(event: &VEvent) -> bool {
+    event.parse_ical_data().is_ok_and(|e| {
+        e.property_value(ComponentProps::Transp.as_ref())
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case(TRANSPARENT))
+    })
+}
+
+/// Times the event takes that overlap `start` to `end`
+pub(crate) fn occurrences(
+    event: &VEvent,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<Interval> {
+    let duration = event.ends_at - event.starts_at;
+    let starts = match event.get_rrule().filter(|_| event.has_rrule) {
+        Some(rule_set) => rule_set
+            .after((start - duration).with_timezone(&rrule::Tz::UTC))
+            .before(end.with_timezone(&rrule::Tz::UTC))
+            .all(MAX_OCCURRENCES)
+            .dates
+            .iter()
+            .map(|d| d.to_utc())
+            .collect(),
+        None => vec![event.starts_at],
+    };
+    starts
+        .into_iter()
+        .map(|s| (s, s + duration))
+        .filter(|(s, e)| *s < end && *e > start)
+        .collect()
+}
+
+/// Sorted intervals where the overlapping and touching ones are joined
+fn merge(mut intervals: Vec<Interval>) -> Vec<Interval> {
+    intervals.sort();
+    let mut merged: Vec<Interval> = Vec::new();
+    for (s, e) in intervals {
+        match merged.last_mut() {
+            Some(last) if s <= last.1 => last.1 = last.1.max(e),
+            _ => merged.push((s, e)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+    use crate::test_utils::{create_event, new_event, new_opaque_block};
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_merge() {
+        let merged = merge(vec![
+            (at(3, 12), at(3, 14)),
+            (at(3, 9), at(3, 10)),
+            (at(3, 10), at(3, 11)),
+            (at(3, 13), at(3, 13)),
+        ]);
+        assert_eq!(merged, vec![(at(3, 9), at(3, 11)), (at(3, 12), at(3, 14))]);
+    }
+
+    #[test]
+    fn test_free_within_keeps_buffer() {
+        let free_busy = FreeBusy {
+            start: at(3, 0),
+            end: at(4, 0),
+            busy: vec![(at(3, 7), at(3, 9)), (at(3, 13), at(3, 16))],
+        };
+        let free = free_busy.free_within((at(3, 8), at(3, 20)), TimeDelta::hours(1));
+        assert_eq!(free, vec![(at(3, 10), at(3, 12)), (at(3, 17), at(3, 20))]);
+    }
+
+    #[test]
+    fn test_recurring_busy_time() {
+        let conn = DbConn::in_memory().unwrap();
+        create_event(conn.clone(), new_opaque_block());
+
+        // From Monday at 14:00 to Wednesday at 9:00
+        let free_busy = block_on(FreeBusy::for_range(
+            conn.clone(),
+            vec![1],
+            at(3, 14),
+            at(5, 9),
+            &[],
+        ))
+        .unwrap();
+        assert_eq!(
+            free_busy.busy,
+            vec![(at(3, 14), at(3, 16)), (at(4, 13), at(4, 16))]
+        );
+
+        let excluded = block_on(FreeBusy::for_range(
+            conn.clone(),
+            vec![1],
+            at(3, 0),
+            at(5, 0),
+            &[EventType::Block],
+        ))
+        .unwrap();
+        assert!(excluded.busy.is_empty());
+        let other_calendar =
+            block_on(FreeBusy::for_range(conn, vec![2], at(3, 0), at(5, 0), &[])).unwrap();
+        assert!(other_calendar.busy.is_empty());
+    }
+
+    #[test]
+    fn test_transparent_events_are_free() {
+        let conn = DbConn::in_memory().unwrap();
+        // basic.ics is marked TRANSP:TRANSPARENT
+        create_event(conn.clone(), new_event("basic.ics"));
+
+        let free_busy =
+            block_on(FreeBusy::for_range(conn, vec![1], at(3, 0), at(5, 0), &[])).unwrap();
+        assert!(free_busy.busy.is_empty());
+    }
+
+    #[test]
+    fn test_to_ics() {
+        let free_busy = FreeBusy {
+            start: at(3, 0),
+            end: at(4, 0),
+            busy: vec![(at(3, 13), at(3, 16))],
+        };
+        let ics = free_busy.to_ics("uid", at(2, 8));
+        assert_eq!(
+            ics,
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//mimido//free busy//EN\r\n\
+            METHOD:PUBLISH\r\nBEGIN:VFREEBUSY\r\nUID:uid\r\nDTSTAMP:20240602T080000Z\r\n\
+            DTSTART:20240603T000000Z\r\nDTEND:20240604T000000Z\r\n\
+            FREEBUSY;FBTYPE=BUSY:20240603T130000Z/20240603T160000Z\r\n\
+            END:VFREEBUSY\r\nEND:VCALENDAR\r\n"
+        );
+    }
+}
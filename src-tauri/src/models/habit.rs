@@ -0,0 +1,248 @@
+//! Recurring reminders and tasks followed as habits.
+//!
+//! Each occurrence given by the recurrence of the component is done when its
+//! [`TodoOccurrence`] is done and missed otherwise. The occurrences of the current day
+//! that are still open are left out, there is still time to do them. The dated
+//! reminders are stored as events, so the recurring reminder events are habits too.
+
+use chrono::{DateTime, TimeDelta, TimeZone, Utc};
+use now::DateTimeNow;
+use rrule::RRuleSet;
+
+use crate::{
+    calendar_items::{event_status::EventStatus, event_type::EventType},
+    db_conn::DbConn,
+    models::{
+        IcalParseableTrait, VCmp, VCmpId, todo_occurrence::TodoOccurrence, vevent::VEvent,
+        vtodo::VTodo,
+    },
+};
+
+/// Days of history taken into account
+const HISTORY_DAYS: i64 = 365;
+/// Most occurrences of a single habit taken in the history
+const MAX_OCCURRENCES: u16 = 1000;
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, specta::Type)]
+pub struct HabitDay {
+    pub occurrence_start: DateTime<Utc>,
+    pub done: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, specta::Type)]
+pub struct Habit {
+    pub vcmp_id: VCmpId,
+    pub summary: String,
+    pub event_type: EventType,
+    /// Occurrences done in a row up to the last one
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    /// Share of the last occurrences that were done, from 0 to 1
+    pub completion_rate: f64,
+    /// Occurrences of the last year, the oldest first
+    pub history: Vec<HabitDay>,
+}
+
+impl Habit {
+    /// Habits of the recurring reminders and tasks on the day of `now`, sorted by summary.
+    /// The completion rate is taken over the `last_occurrences`
+    pub async fn list<Tz: TimeZone>(
+        conn: DbConn,
+        now: DateTime<Tz>,
+        last_occurrences: usize,
+    ) -> anyhow::Result<Vec<Habit>> {
+        let todos = VTodo::list_recurring_of_types(
+            conn.clone(),
+            vec![EventType::Reminder, EventType::Task],
+        )
+        .await?;
+        let events =
+            VEvent::list_recurring_of_types(conn.clone(), vec![EventType::Reminder]).await?;
+        let mut vcmps = todos
+            .into_iter()
+            .map(VCmp::Todo)
+            .chain(events.into_iter().map(VCmp::Event))
+            .collect::<Vec<VCmp>>();
+        vcmps.sort_by_key(VCmp::get_summary);
+        let since = (now.clone() - TimeDelta::days(HISTORY_DAYS))
+            .beginning_of_day()
+            .to_utc();
+        let today = now.beginning_of_day().to_utc();
+        let end = now.end_of_day().to_utc();
+
+        let uids = vcmps.iter().map(VCmp::get_uid).collect();
+        let occurrences = TodoOccurrence::for_uids_between(conn, uids, since, end).await?;
+        let habits = vcmps
+            .iter()
+            .map(|vcmp| {
+                let uid = vcmp.get_uid();
+                let history = occurrence_starts(vcmp, since, end)
+                    .into_iter()
+                    .filter_map(|start| {
+                        let done = occurrences
+                            .get(&(uid.clone(), start))
+                            .is_some_and(|o| o.status == EventStatus::Done);
+                        (done || start < today).then_some(HabitDay {
+                            occurrence_start: start,
+                            done,
+                        })
+                    })
+                    .collect::<Vec<HabitDay>>();
+                Self::from_history(vcmp, history, last_occurrences)
+            })
+            .collect();
+        Ok(habits)
+    }
+
+    fn from_history(vcmp: &VCmp, history: Vec<HabitDay>, last_occurrences: usize) -> Self {
+        let (current_streak, longest_streak) = streaks(&history);
+        Self {
+            vcmp_id: vcmp.get_id(),
+            summary: vcmp.get_summary(),
+            event_type: vcmp.get_event_type(),
+            current_streak,
+            longest_streak,
+            completion_rate: completion_rate(&history, last_occurrences),
+            history,
+        }
+    }
+}
+
+fn occurrence_starts(vcmp: &VCmp, since: DateTime<Utc>, end: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+    let rule_set: Option<RRuleSet> = match vcmp {
+        VCmp::Todo(vtodo) => vtodo.get_rrule(),
+        VCmp::Event(vevent) => vevent.get_rrule(),
+    };
+    let Some(rule_set) = rule_set else {
+        return vec![];
+    };
+    rule_set
+        .after(since.with_timezone(&rrule::Tz::UTC))
+        .before(end.with_timezone(&rrule::Tz::UTC))
+        .all(MAX_OCCURRENCES)
+        .dates
+        .iter()
+        .map(|d| d.to_utc())
+        .filter(|d| *d < end)
+        .collect()
+}
+
+/// Current and longest runs of done occurrences
+fn streaks(history: &[HabitDay]) -> (u32, u32) {
+    let mut current = 0;
+    let mut longest = 0;
+    for day in history {
+        if day.done {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    (current, longest)
+}
+
+fn completion_rate(history: &[HabitDay], last_occurrences: usize) -> f64 {
+    let recent = &history[history.len().saturating_sub(last_occurrences)..];
+    if recent.is_empty() {
+        return 0.0;
+    }
+    let done = recent.iter().filter(|d| d.done).count();
+    done as f64 / recent.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+    use crate::{
+        models::vevent::NewVEvent,
+        test_utils::{create_event, create_todo, new_event, new_todo},
+    };
+
+    fn at(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, 8, 0, 0).unwrap()
+    }
+
+    fn save(conn: DbConn, todo: &VTodo, day: u32, status: EventStatus) {
+        block_on(TodoOccurrence::new(todo.uid.clone(), at(day), status, at(day)).save(conn))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_streaks_and_completion_rate() {
+        let history = [true, false, true, true, true, false, true, true]
+            .into_iter()
+            .enumerate()
+            .map(|(i, done)| HabitDay {
+                occurrence_start: at(20 + i as u32),
+                done,
+            })
+            .collect::<Vec<HabitDay>>();
+        assert_eq!(streaks(&history), (2, 3));
+        assert_eq!(completion_rate(&history, 4), 0.75);
+        assert_eq!(completion_rate(&history, 100), 0.75);
+        assert_eq!(completion_rate(&[], 4), 0.0);
+    }
+
+    #[test]
+    fn test_missed_occurrences_break_the_streak() {
+        let conn = DbConn::in_memory().unwrap();
+        // Daily reminder at 08:00 from 20/05/24
+        let todo = create_todo(conn.clone(), new_todo("todo_recurring.ics"));
+        save(conn.clone(), &todo, 20, EventStatus::Done);
+        save(conn.clone(), &todo, 21, EventStatus::Done);
+        // Reopened, so missed like the ones without a status
+        save(conn.clone(), &todo, 22, EventStatus::Todo);
+        save(conn.clone(), &todo, 24, EventStatus::Done);
+
+        // Today's occurrence is still open
+        let now = at(25) + TimeDelta::hours(2);
+        let habits = block_on(Habit::list(conn.clone(), now, 4)).unwrap();
+        assert_eq!(habits.len(), 1);
+        let habit = &habits[0];
+        assert_eq!(habit.vcmp_id, VCmpId::Todo(todo.id));
+        let done = habit.history.iter().map(|d| d.done).collect::<Vec<bool>>();
+        assert_eq!(done, vec![true, true, false, false, true]);
+        assert_eq!((habit.current_streak, habit.longest_streak), (1, 2));
+        assert_eq!(habit.completion_rate, 0.5);
+
+        save(conn.clone(), &todo, 25, EventStatus::Done);
+        let habits = block_on(Habit::list(conn, now, 2)).unwrap();
+        assert_eq!(habits[0].history.len(), 6);
+        assert_eq!(habits[0].current_streak, 2);
+        assert_eq!(habits[0].completion_rate, 1.0);
+    }
+
+    #[test]
+    fn test_recurring_reminder_events_are_habits() {
+        let conn = DbConn::in_memory().unwrap();
+        // Reminder every weekday at 13:00 from Monday 20/05/24
+        let event = create_event(
+            conn.clone(),
+            NewVEvent {
+                event_type: EventType::Reminder,
+                ..new_event("basic.ics")
+            },
+        );
+        let done_at = Utc.with_ymd_and_hms(2024, 5, 20, 13, 0, 0).unwrap();
+        block_on(
+            TodoOccurrence::new(event.uid.clone(), done_at, EventStatus::Done, done_at)
+                .save(conn.clone()),
+        )
+        .unwrap();
+
+        let now = Utc.with_ymd_and_hms(2024, 5, 22, 9, 0, 0).unwrap();
+        let habits = block_on(Habit::list(conn, now, 10)).unwrap();
+        assert_eq!(habits.len(), 1);
+        assert_eq!(habits[0].vcmp_id, VCmpId::Event(event.id));
+        let done = habits[0]
+            .history
+            .iter()
+            .map(|d| d.done)
+            .collect::<Vec<bool>>();
+        assert_eq!(done, vec![true, false]);
+        assert_eq!((habits[0].current_streak, habits[0].longest_streak), (0, 1));
+    }
+}
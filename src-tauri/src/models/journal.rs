@@ -0,0 +1,239 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    db_conn::DbConn,
+    models::{VCmp, VCmpId, todo_occurrence::TodoOccurrence},
+    schema::journal,
+};
+
+/// Entries that can be undone, the older ones are dropped when recording new ones
+const MAX_DONE_ENTRIES: i64 = 500;
+
+/// A mutation made through the command layer, used to undo and redo it.
+///
+/// `before` and `after` hold the serialized [`VCmp`], [`None`] when the component
+/// didn't exist (on creation) or doesn't exist anymore (on deletion). When
+/// `occurrence_start` is set, the status of that occurrence of a recurring todo changed
+/// or event and they hold the serialized [`TodoOccurrence`] instead
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = journal)]
+pub struct JournalEntry {
+    pub id: i32,
+    pub vcmp_kind: String,
+    pub vcmp_id: i32,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub undone: bool,
+    pub created_at: DateTime<Utc>,
+    pub occurrence_start: Option<DateTime<Utc>>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = journal)]
+struct NewJournalEntry {
+    vcmp_kind: String,
+    vcmp_id: i32,
+    before: Option<String>,
+    after: Option<String>,
+    created_at: DateTime<Utc>,
+    occurrence_start: Option<DateTime<Utc>>,
+}
+
+impl NewJournalEntry {
+    fn new(before: Option<&VCmp>, after: Option<&VCmp>) -> anyhow::Result<Self> {
+        let (vcmp_kind, vcmp_id) = after
+            .or(before)
+            .map(|vcmp| vcmp.get_id().into_parts())
+            .ok_or(anyhow!("A journal entry needs a component"))?;
+        Ok(Self {
+            vcmp_kind: vcmp_kind.to_string(),
+            vcmp_id,
+            before: to_json(before)?,
+            after: to_json(after)?,
+            created_at: Utc::now(),
+            occurrence_start: None,
+        })
+    }
+
+    fn for_occurrence(
+        vcmp_id: VCmpId,
+        before: Option<&TodoOccurrence>,
+        after: &TodoOccurrence,
+    ) -> anyhow::Result<Self> {
+        let (vcmp_kind, vcmp_id) = vcmp_id.into_parts();
+        Ok(Self {
+            vcmp_kind: vcmp_kind.to_string(),
+            vcmp_id,
+            before: to_json(before)?,
+            after: to_json(Some(after))?,
+            created_at: Utc::now(),
+            occurrence_start: Some(after.occurrence_start),
+        })
+    }
+
+    fn insert(self, conn: &mut SqliteConnection) -> QueryResult<()> {
+        use crate::schema::journal::dsl as journal_dsl;
+
+        conn.transaction(|conn| {
+            diesel::delete(journal_dsl::journal.filter(journal_dsl::undone.eq(true)))
+                .execute(conn)?;
+            diesel::insert_into(journal_dsl::journal)
+                .values(self)
+                .execute(conn)?;
+            let oldest_kept = journal_dsl::journal
+                .filter(journal_dsl::undone.eq(false))
+                .order(journal_dsl::id.desc())
+                .select(journal_dsl::id)
+                .offset(MAX_DONE_ENTRIES - 1)
+                .first::<i32>(conn)
+                .optional()?;
+            if let Some(oldest_kept) = oldest_kept {
+                diesel::delete(journal_dsl::journal.filter(journal_dsl::id.lt(oldest_kept)))
+                    .execute(conn)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+fn to_json<T: Serialize>(value: Option<&T>) -> anyhow::Result<Option<String>> {
+    value
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(anyhow::Error::new)
+}
+
+fn from_json<T: DeserializeOwned>(json: &Option<String>) -> anyhow::Result<Option<T>> {
+    json.as_deref()
+        .map(serde_json::from_str)
+        .transpose()
+        .map_err(anyhow::Error::new)
+}
+
+impl JournalEntry {
+    /// Records a mutation, the entries that were undone can't be redone anymore
+    pub async fn record(
+        conn: DbConn,
+        before: Option<&VCmp>,
+        after: Option<&VCmp>,
+    ) -> anyhow::Result<()> {
+        let entry = NewJournalEntry::new(before, after)?;
+        conn.run(move |conn| entry.insert(conn)).await?;
+        Ok(())
+    }
+
+    /// Same as [`JournalEntry::record`] on a connection that may be in a transaction
+    pub fn record_in(
+        conn: &mut SqliteConnection,
+        before: Option<&VCmp>,
+        after: Option<&VCmp>,
+    ) -> anyhow::Result<()> {
+        NewJournalEntry::new(before, after)?.insert(conn)?;
+        Ok(())
+    }
+
+    /// Records a change of the status of an occurrence of the component with `vcmp_id`,
+    /// `before` is [`None`] when the occurrence had no status of its own
+    pub fn record_occurrence_in(
+        conn: &mut SqliteConnection,
+        vcmp_id: VCmpId,
+        before: Option<&TodoOccurrence>,
+        after: &TodoOccurrence,
+    ) -> anyhow::Result<()> {
+        NewJournalEntry::for_occurrence(vcmp_id, before, after)?.insert(conn)?;
+        Ok(())
+    }
+
+    /// Latest entry that can be undone
+    pub async fn last_done(conn: DbConn) -> anyhow::Result<Option<JournalEntry>> {
+        use crate::schema::journal::dsl as journal_dsl;
+
+        let entry = conn
+            .run(move |conn| {
+                journal_dsl::journal
+                    .filter(journal_dsl::undone.eq(false))
+                    .order(journal_dsl::id.desc())
+                    .select(JournalEntry::as_select())
+                    .first(conn)
+                    .optional()
+            })
+            .await?;
+        Ok(entry)
+    }
+
+    /// The entry that was undone last, which is the next one to redo
+    pub async fn first_undone(conn: DbConn) -> anyhow::Result<Option<JournalEntry>> {
+        use crate::schema::journal::dsl as journal_dsl;
+
+        let entry = conn
+            .run(move |conn| {
+                journal_dsl::journal
+                    .filter(journal_dsl::undone.eq(true))
+                    .order(journal_dsl::id.asc())
+                    .select(JournalEntry::as_select())
+                    .first(conn)
+                    .optional()
+            })
+            .await?;
+        Ok(entry)
+    }
+
+    pub async fn set_undone(&self, conn: DbConn, undone: bool) -> anyhow::Result<()> {
+        use crate::schema::journal::dsl as journal_dsl;
+
+        let id = self.id;
+        conn.run(move |conn| {
+            diesel::update(journal_dsl::journal.filter(journal_dsl::id.eq(id)))
+                .set(journal_dsl::undone.eq(undone))
+                .execute(conn)
+        })
+        .await?;
+        Ok(())
+    }
+
+    pub fn get_vcmp_id(&self) -> anyhow::Result<VCmpId> {
+        VCmpId::from_parts(&self.vcmp_kind, self.vcmp_id)
+    }
+
+    /// State before the change, a [`VCmp`] or a [`TodoOccurrence`] depending on
+    /// `occurrence_start`
+    pub fn get_before<T: DeserializeOwned>(&self) -> anyhow::Result<Option<T>> {
+        from_json(&self.before)
+    }
+
+    pub fn get_after<T: DeserializeOwned>(&self) -> anyhow::Result<Option<T>> {
+        from_json(&self.after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+    use crate::test_utils::{create_todo, new_local_todo};
+
+    #[test]
+    fn test_record_drops_the_oldest_entries() {
+        use crate::schema::journal::dsl as journal_dsl;
+
+        let conn = DbConn::in_memory().unwrap();
+        let created = VCmp::Todo(create_todo(conn.clone(), new_local_todo("todo.ics")));
+        for _ in 0..=MAX_DONE_ENTRIES {
+            block_on(JournalEntry::record(conn.clone(), None, Some(&created))).unwrap();
+        }
+
+        let ids = block_on(conn.run(|conn| {
+            journal_dsl::journal
+                .select(journal_dsl::id)
+                .order(journal_dsl::id.asc())
+                .load::<i32>(conn)
+        }))
+        .unwrap();
+        assert_eq!(ids.len() as i64, MAX_DONE_ENTRIES);
+        assert_eq!(ids.first(), Some(&2));
+    }
+}
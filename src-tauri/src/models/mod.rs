@@ -1,20 +1,25 @@
 use crate::{
     calendar_items::{
         component_props::{ComponentProps, get_string_property},
+        event_date::EventDateInfo,
         event_status::EventStatus,
         event_type::EventType,
         event_upsert::EventUpsertInfo,
+        input_traits::ToUserInput,
+        priority,
+        timezone::CalendarTimezones,
     },
     db_conn::DbConn,
-    establish_connection,
     models::{
+        journal::JournalEntry,
         model_traits::{ById, DeleteById, SetSyncedAt},
         server::Server,
+        todo_occurrence::TodoOccurrence,
         vevent::{NewVEvent, VEvent, VEventTrait},
         vtodo::{NewVTodo, VTodo, VTodoTrait},
     },
     schema::*,
-    util::Etag,
+    util::{Etag, Href},
 };
 use anyhow::anyhow;
 use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
@@ -23,43 +28,164 @@ use icalendar::DatePerhapsTime;
 use libdav::FetchedResource;
 use rrule::{RRuleError, RRuleSet};
 
+pub mod bulk;
+pub mod conflict;
+pub mod day_summary;
+pub mod free_busy;
+pub mod habit;
+pub mod journal;
 pub mod model_traits;
+pub mod named_item;
+pub mod patch;
+pub mod reschedule;
+pub mod routine;
 pub mod server;
+pub mod settings;
+pub mod statistics;
+pub mod template;
+pub mod todo_occurrence;
+pub mod trash;
 pub mod vcmp_builder;
 pub(crate) mod vevent;
 pub(crate) mod vtodo;
 
-use tauri::async_runtime::spawn_blocking;
 pub use vcmp_builder::VCmpBuilder;
 
 /// Enum to unify the [`VEvent`] and [`VTodo`] struct
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum VCmp {
     Todo(VTodo),
     Event(VEvent),
 }
 
-impl ById for VCmp {
-    async fn by_id(conn: DbConn, id: i32) -> anyhow::Result<Option<VCmp>> {
-        let vevent = VEvent::by_id(conn.clone(), id).await?;
-        if let Some(vevent) = vevent {
-            return Ok(Some(VCmp::Event(vevent)));
+/// Identifies a component across the `vevents` and `vtodos` tables,
+/// since both tables have their own id sequence
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, specta::Type,
+)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "id")]
+pub enum VCmpId {
+    Event(i32),
+    Todo(i32),
+}
+
+const EVENT_KIND: &str = "event";
+const TODO_KIND: &str = "todo";
+
+impl VCmpId {
+    /// Splits the id into the kind and the row id, to store it in a table
+    pub fn into_parts(self) -> (&'static str, i32) {
+        match self {
+            VCmpId::Event(id) => (EVENT_KIND, id),
+            VCmpId::Todo(id) => (TODO_KIND, id),
         }
-        let todo = VTodo::by_id(conn, id).await?;
-        if let Some(todo) = todo {
-            return Ok(Some(VCmp::Todo(todo)));
+    }
+
+    pub fn from_parts(kind: &str, id: i32) -> anyhow::Result<Self> {
+        match kind {
+            EVENT_KIND => Ok(VCmpId::Event(id)),
+            TODO_KIND => Ok(VCmpId::Todo(id)),
+            kind => Err(anyhow!("Unknown component kind {kind}")),
+        }
+    }
+}
+
+impl std::fmt::Display for VCmpId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VCmpId::Event(id) => write!(f, "event {id}"),
+            VCmpId::Todo(id) => write!(f, "todo {id}"),
         }
-        Ok(None)
     }
 }
 
+/// Copies to `$local` the fields that differ between `$base` and `$server`
+macro_rules! take_server_changes {
+    ($local: expr, $base: expr, $server: expr, [$($field: ident),* $(,)?]) => {{
+        $(
+            if $server.$field != $base.$field {
+                $local.$field = $server.$field.clone();
+            }
+        )*
+    }};
+}
+
 impl VCmp {
+    /// Finds the component, the ones waiting for their delete to be synced are ignored
+    pub async fn by_id(conn: DbConn, id: VCmpId) -> anyhow::Result<Option<VCmp>> {
+        let vcmp = Self::by_id_with_deleted(conn, id).await?;
+        Ok(vcmp.filter(|vcmp| !vcmp.is_deleted()))
+    }
+
+    /// Same as [`VCmp::by_id`] on a connection that may be in a transaction
+    pub fn by_id_in(conn: &mut SqliteConnection, id: VCmpId) -> QueryResult<Option<VCmp>> {
+        let vcmp = Self::by_id_with_deleted_in(conn, id)?;
+        Ok(vcmp.filter(|vcmp| !vcmp.is_deleted()))
+    }
+
+    /// Same as [`VCmp::by_id_with_deleted`] on a connection that may be in a transaction
+    pub fn by_id_with_deleted_in(
+        conn: &mut SqliteConnection,
+        id: VCmpId,
+    ) -> QueryResult<Option<VCmp>> {
+        let vcmp = match id {
+            VCmpId::Event(id) => vevents::table
+                .filter(vevents::id.eq(id))
+                .select(VEvent::as_select())
+                .first(conn)
+                .optional()?
+                .map(VCmp::Event),
+            VCmpId::Todo(id) => vtodos::table
+                .filter(vtodos::id.eq(id))
+                .select(VTodo::as_select())
+                .first(conn)
+                .optional()?
+                .map(VCmp::Todo),
+        };
+        Ok(vcmp)
+    }
+
+    pub async fn by_id_with_deleted(conn: DbConn, id: VCmpId) -> anyhow::Result<Option<VCmp>> {
+        match id {
+            VCmpId::Event(id) => Ok(VEvent::by_id(conn, id).await?.map(VCmp::Event)),
+            VCmpId::Todo(id) => Ok(VTodo::by_id(conn, id).await?.map(VCmp::Todo)),
+        }
+    }
+
+    pub fn get_id(&self) -> VCmpId {
+        match self {
+            VCmp::Todo(vtodo) => VCmpId::Todo(vtodo.id),
+            VCmp::Event(vevent) => VCmpId::Event(vevent.id),
+        }
+    }
+
     pub fn get_uid(&self) -> String {
         match self {
             VCmp::Todo(vtodo) => vtodo.uid.clone(),
             VCmp::Event(vevent) => vevent.uid.clone(),
         }
     }
+    pub fn get_summary(&self) -> String {
+        match self {
+            VCmp::Todo(vtodo) => vtodo.summary.clone(),
+            VCmp::Event(vevent) => vevent.summary.clone(),
+        }
+    }
+
+    pub fn has_rrule(&self) -> bool {
+        match self {
+            VCmp::Todo(vtodo) => vtodo.has_rrule,
+            VCmp::Event(vevent) => vevent.has_rrule,
+        }
+    }
+
+    pub fn get_event_type(&self) -> EventType {
+        match self {
+            VCmp::Todo(vtodo) => vtodo.event_type,
+            VCmp::Event(vevent) => vevent.event_type,
+        }
+    }
+
     pub fn get_calendar_id(&self) -> i32 {
         match self {
             VCmp::Todo(vtodo) => vtodo.calendar_id,
@@ -81,6 +207,257 @@ impl VCmp {
         }
     }
 
+    pub fn get_synced_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            VCmp::Todo(vtodo) => vtodo.synced_at,
+            VCmp::Event(vevent) => vevent.synced_at,
+        }
+    }
+
+    /// Component that doesn't exist on the server anymore
+    pub fn into_unsynced(self) -> VCmp {
+        match self {
+            VCmp::Event(vevent) => VCmp::Event(VEvent {
+                etag: None,
+                synced_at: None,
+                ..vevent
+            }),
+            VCmp::Todo(vtodo) => VCmp::Todo(VTodo {
+                etag: None,
+                synced_at: None,
+                ..vtodo
+            }),
+        }
+    }
+
+    /// Same component without the pending delete
+    pub fn into_undeleted(self) -> VCmp {
+        match self {
+            VCmp::Event(vevent) => VCmp::Event(VEvent {
+                deleted_at: None,
+                ..vevent
+            }),
+            VCmp::Todo(vtodo) => VCmp::Todo(VTodo {
+                deleted_at: None,
+                ..vtodo
+            }),
+        }
+    }
+
+    /// Last calendar fetched from the server
+    pub fn get_ical_data(&self) -> Option<String> {
+        match self {
+            VCmp::Todo(vtodo) => vtodo.ical_data.clone(),
+            VCmp::Event(vevent) => vevent.ical_data.clone(),
+        }
+    }
+
+    /// Same component with the urgency and importance of a `PRIORITY`
+    pub fn with_priority(self, priority: u8) -> VCmp {
+        let Some((urgency, importance)) = priority::from_priority(priority) else {
+            return self;
+        };
+        match self {
+            VCmp::Event(vevent) => VCmp::Event(VEvent {
+                urgency,
+                importance,
+                ..vevent
+            }),
+            VCmp::Todo(vtodo) => VCmp::Todo(VTodo {
+                urgency,
+                importance,
+                ..vtodo
+            }),
+        }
+    }
+
+    /// Same component with the fields changed on the server since it was last fetched taken
+    /// from `server_data`, the calendar the server has now, the other ones keep their local
+    /// value. The urgency and importance only follow a change of `PRIORITY`, and without
+    /// the last fetched calendar every other field keeps its local value
+    pub fn merged_with_server(&self, server_data: &str) -> anyhow::Result<VCmp> {
+        let href = self.get_href().unwrap_or_default();
+        let parse = |data: &str| NewVCmp::from_ical_data(self.get_calendar_id(), &href, data, "");
+        let base = self.get_ical_data();
+        let merged = match base.as_deref().map(parse).transpose()? {
+            None => self.clone(),
+            Some(base) => match (self.clone(), base, parse(server_data)?) {
+                (VCmp::Todo(mut local), NewVCmp::Todo(base), NewVCmp::Todo(server)) => {
+                    take_server_changes!(
+                        local,
+                        base,
+                        server,
+                        [
+                            summary,
+                            description,
+                            starts_at,
+                            ends_at,
+                            has_rrule,
+                            rrule_str,
+                            tag,
+                            status,
+                            event_type,
+                            original_text,
+                            load,
+                            postponed,
+                            completed,
+                            parent_uid,
+                        ]
+                    );
+                    VCmp::Todo(local)
+                }
+                (VCmp::Event(mut local), NewVCmp::Event(base), NewVCmp::Event(server)) => {
+                    take_server_changes!(
+                        local,
+                        base,
+                        server,
+                        [
+                            summary,
+                            description,
+                            starts_at,
+                            ends_at,
+                            has_rrule,
+                            rrule_str,
+                            tag,
+                            status,
+                            event_type,
+                            original_text,
+                            load,
+                            postponed,
+                        ]
+                    );
+                    VCmp::Event(local)
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "{} is not the same kind of component on the server",
+                        self.get_summary()
+                    ));
+                }
+            },
+        };
+
+        let last_fetched = base.and_then(|data| priority::priority_from_ical_data(&data));
+        let merged = match priority::priority_from_ical_data(server_data) {
+            Some(server) if Some(server) != last_fetched => merged.with_priority(server),
+            _ => merged,
+        };
+        // The server copy is the one the next conflict is resolved against
+        let server_data = Some(server_data.to_string());
+        Ok(match merged {
+            VCmp::Todo(vtodo) => VCmp::Todo(VTodo {
+                ical_data: server_data,
+                ..vtodo
+            }),
+            VCmp::Event(vevent) => VCmp::Event(VEvent {
+                ical_data: server_data,
+                ..vevent
+            }),
+        })
+    }
+
+    /// Same component moved to another date, keeping its duration when the new date has no
+    /// end. It only counts as postponed when it starts later than before, `date_of_change`
+    /// is used to write the `original_text` back
+    pub fn postpone_to<Tz: TimeZone>(
+        &self,
+        date_info: &EventDateInfo<Tz>,
+        date_of_change: &DateTime<Tz>,
+    ) -> anyhow::Result<VCmp> {
+        let starts_at = date_info.start.to_utc();
+        let ends_at = date_info.end.as_ref().map(|end| end.to_utc());
+        let slipped =
+            |current: Option<DateTime<Utc>>| i32::from(current.is_some_and(|c| starts_at > c));
+        let postponed = match self {
+            VCmp::Event(vevent) if vevent.has_rrule => {
+                return Err(anyhow!("Recurring events can't be postponed"));
+            }
+            VCmp::Todo(vtodo) if vtodo.has_rrule => {
+                return Err(anyhow!("Recurring todos can't be postponed"));
+            }
+            VCmp::Event(vevent) => {
+                let mut postponed = VEvent {
+                    starts_at,
+                    ends_at: ends_at.unwrap_or(starts_at + (vevent.ends_at - vevent.starts_at)),
+                    postponed: vevent.postponed + slipped(Some(vevent.starts_at)),
+                    out_of_sync: true,
+                    ..vevent.clone()
+                };
+                postponed.original_text = Some(postponed.to_input(date_of_change));
+                VCmp::Event(postponed)
+            }
+            VCmp::Todo(vtodo) => {
+                let duration = vtodo.starts_at.zip(vtodo.ends_at).map(|(s, e)| e - s);
+                let ends_at = ends_at
+                    .or(duration.map(|d| starts_at + d))
+                    .unwrap_or(date_info.get_end_or_default(vtodo.event_type).to_utc());
+                let mut postponed = VTodo {
+                    starts_at: Some(starts_at),
+                    ends_at: Some(ends_at),
+                    postponed: vtodo.postponed + slipped(vtodo.starts_at),
+                    out_of_sync: true,
+                    ..vtodo.clone()
+                };
+                postponed.original_text = Some(postponed.to_input(date_of_change));
+                VCmp::Todo(postponed)
+            }
+        };
+        Ok(postponed)
+    }
+
+    /// Same component in another calendar, unsynced so it's created there as a new
+    /// resource. Both have the same uid, so the one on the server has to be deleted
+    /// before saving it
+    pub fn moved_to_calendar(&self, calendar_id: i32, calendar_url: &str) -> VCmp {
+        let href = Some(format!("{calendar_url}{}.ics", self.get_uid()));
+        match self {
+            VCmp::Event(vevent) => VCmp::Event(VEvent {
+                calendar_id,
+                href,
+                etag: None,
+                synced_at: None,
+                ..vevent.clone()
+            }),
+            VCmp::Todo(vtodo) => VCmp::Todo(VTodo {
+                calendar_id,
+                href,
+                etag: None,
+                synced_at: None,
+                ..vtodo.clone()
+            }),
+        }
+    }
+
+    /// Whether the component belongs in the other table, following the same rules as
+    /// [`VCmpBuilder::build_new`], like a todo that has become an event
+    pub fn belongs_in_other_table(&self) -> bool {
+        match self {
+            VCmp::Event(vevent) => vevent.event_type == EventType::Task,
+            VCmp::Todo(vtodo) => {
+                vtodo.event_type != EventType::Task
+                    && vtodo.starts_at.is_some()
+                    && vtodo.parent_uid.is_none()
+            }
+        }
+    }
+
+    /// Same component as a new resource in the calendar of `calendar_url`, keeping the uid
+    /// and the fields but nothing of what the server knows about it. It's for the other
+    /// table when its type belongs there
+    pub fn into_new_resource(&self, calendar_url: &str) -> anyhow::Result<NewVCmp> {
+        VCmpBuilder::from(self)
+            .unsynced()
+            .calendar_href(Href(calendar_url.to_string()))
+            .build_new()
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        match self {
+            VCmp::Todo(vtodo) => vtodo.deleted_at.is_some(),
+            VCmp::Event(vevent) => vevent.deleted_at.is_some(),
+        }
+    }
+
     pub async fn delete(&self, conn: DbConn) -> anyhow::Result<bool> {
         match self {
             VCmp::Todo(vtodo) => VTodo::delete_by_id(conn.clone(), vtodo.id).await,
@@ -95,6 +472,22 @@ impl VCmp {
         }
     }
 
+    /// Same as [`VCmp::update`] on a connection that may be in a transaction
+    pub fn update_in(&self, conn: &mut SqliteConnection) -> QueryResult<VCmp> {
+        match self {
+            VCmp::Todo(vtodo) => diesel::update(vtodos::table.find(vtodo.id))
+                .set(vtodo)
+                .returning(VTodo::as_returning())
+                .get_result(conn)
+                .map(VCmp::Todo),
+            VCmp::Event(vevent) => diesel::update(vevents::table.find(vevent.id))
+                .set(vevent)
+                .returning(VEvent::as_returning())
+                .get_result(conn)
+                .map(VCmp::Event),
+        }
+    }
+
     pub fn apply_upsert<Tz: TimeZone>(
         &self,
         input: &str,
@@ -113,6 +506,75 @@ impl VCmp {
             }
         }
     }
+    /// Writes the whole row, inserting it with the same id if it doesn't exist
+    pub async fn replace(&self, conn: DbConn) -> anyhow::Result<()> {
+        let vcmp = self.clone();
+        conn.run(move |conn| vcmp.replace_in(conn)).await?;
+        Ok(())
+    }
+
+    /// Same as [`VCmp::replace`] on a connection that may be in a transaction
+    pub fn replace_in(&self, conn: &mut SqliteConnection) -> QueryResult<()> {
+        match self {
+            VCmp::Todo(vtodo) => diesel::replace_into(vtodos::table)
+                .values(vtodo)
+                .execute(conn)?,
+            VCmp::Event(vevent) => diesel::replace_into(vevents::table)
+                .values(vevent)
+                .execute(conn)?,
+        };
+        Ok(())
+    }
+
+    /// Whether the component was pushed, so removing it has to reach the server
+    pub fn is_on_server(&self) -> bool {
+        self.get_href().is_some() && self.get_synced_at().is_some()
+    }
+
+    /// Deletes the row, or only marks it as deleted when the delete has to reach the
+    /// server first, along with the statuses of its occurrences
+    pub fn remove_in(&self, conn: &mut SqliteConnection) -> QueryResult<()> {
+        self.remove_row_in(conn)?;
+        TodoOccurrence::delete_for_uid_in(conn, &self.get_uid())?;
+        Ok(())
+    }
+
+    /// Same as [`VCmp::remove_in`] keeping the occurrences, for a component that is
+    /// replaced by one with the same uid
+    fn remove_row_in(&self, conn: &mut SqliteConnection) -> QueryResult<()> {
+        let on_server = self.is_on_server();
+        match self {
+            VCmp::Todo(vtodo) if on_server => diesel::update(vtodos::table)
+                .filter(vtodos::id.eq(vtodo.id))
+                .set(vtodos::deleted_at.eq(Some(Utc::now())))
+                .execute(conn)?,
+            VCmp::Event(vevent) if on_server => diesel::update(vevents::table)
+                .filter(vevents::id.eq(vevent.id))
+                .set(vevents::deleted_at.eq(Some(Utc::now())))
+                .execute(conn)?,
+            VCmp::Todo(vtodo) => diesel::delete(vtodos::table)
+                .filter(vtodos::id.eq(vtodo.id))
+                .execute(conn)?,
+            VCmp::Event(vevent) => diesel::delete(vevents::table)
+                .filter(vevents::id.eq(vevent.id))
+                .execute(conn)?,
+        };
+        Ok(())
+    }
+
+    /// Calendar to send to the server, the `ical_data` is reused when there are no local
+    /// changes so properties unknown to the app are not lost
+    pub fn to_push_calendar(&self) -> icalendar::Calendar {
+        let ical_data = match self {
+            VCmp::Todo(vtodo) if !vtodo.out_of_sync => vtodo.ical_data.as_ref(),
+            VCmp::Event(vevent) if !vevent.out_of_sync => vevent.ical_data.as_ref(),
+            _ => None,
+        };
+        ical_data
+            .and_then(|data| data.parse::<icalendar::Calendar>().ok())
+            .unwrap_or_else(|| icalendar::Calendar::new().push(self.clone()).done())
+    }
+
     pub fn set_status<Tz: TimeZone>(&mut self, status: EventStatus, date_of_update: DateTime<Tz>) {
         match self {
             VCmp::Todo(vtodo) => {
@@ -161,6 +623,22 @@ pub enum NewVCmp {
 }
 
 impl NewVCmp {
+    /// Same as [`NewVCmp::from_resource`] with the calendar data of a resource
+    pub fn from_ical_data(
+        calendar_id: i32,
+        href: &str,
+        ical_data: &str,
+        etag: &str,
+    ) -> anyhow::Result<NewVCmp> {
+        if let Some(todo) = NewVTodo::from_ical_data(calendar_id, href, ical_data, etag)? {
+            return Ok(NewVCmp::Todo(todo));
+        }
+        if let Some(event) = NewVEvent::from_ical_data(calendar_id, href, ical_data, etag)? {
+            return Ok(NewVCmp::Event(event));
+        }
+        Err(anyhow!("No Supported Component found"))
+    }
+
     pub fn from_resource(
         calendar_id: i32,
         fetched_resource: &FetchedResource,
@@ -192,6 +670,71 @@ impl NewVCmp {
             NewVCmp::Event(new_vevent) => new_vevent.create(conn).await.map(VCmp::Event),
         }
     }
+
+    /// Creates the component in place of `replaced`, which is removed as if it was deleted,
+    /// in a single transaction. It's journaled as a single change from one to the other
+    pub async fn create_in_place_of(self, conn: DbConn, replaced: &VCmp) -> anyhow::Result<VCmp> {
+        let replaced = replaced.clone();
+        let created = conn
+            .run(move |conn| {
+                conn.transaction::<_, anyhow::Error, _>(|conn| {
+                    self.create_in_place_of_in(conn, &replaced)
+                })
+            })
+            .await?;
+        Ok(created)
+    }
+
+    /// Same as [`NewVCmp::create_in_place_of`] on a connection that may be in a transaction
+    pub(crate) fn create_in_place_of_in(
+        &self,
+        conn: &mut SqliteConnection,
+        replaced: &VCmp,
+    ) -> anyhow::Result<VCmp> {
+        // Removed first since both have the same uid, a pending delete doesn't count
+        replaced.remove_row_in(conn)?;
+        let created = self.create_in(conn)?;
+        JournalEntry::record_in(conn, Some(replaced), Some(&created))?;
+        Ok(created)
+    }
+
+    /// Same as [`NewVCmp::create`] on a connection that may be in a transaction, fails
+    /// while a delete of a component with the same uid in the same calendar didn't reach
+    /// the server
+    pub(crate) fn create_in(&self, conn: &mut SqliteConnection) -> anyhow::Result<VCmp> {
+        // Both would have the same href, the delete would remove the new resource
+        let pending_deletes = match self {
+            NewVCmp::Todo(new_vtodo) => vtodos::table
+                .filter(vtodos::uid.eq(&new_vtodo.uid))
+                .filter(vtodos::calendar_id.eq(new_vtodo.calendar_id))
+                .count()
+                .get_result::<i64>(conn)?,
+            NewVCmp::Event(new_vevent) => vevents::table
+                .filter(vevents::uid.eq(&new_vevent.uid))
+                .filter(vevents::calendar_id.eq(new_vevent.calendar_id))
+                .count()
+                .get_result::<i64>(conn)?,
+        };
+        if pending_deletes > 0 {
+            return Err(anyhow!(
+                "The previous change of type or calendar has to be synced before changing it back"
+            ));
+        }
+
+        let created = match self {
+            NewVCmp::Todo(new_vtodo) => diesel::insert_into(vtodos::table)
+                .values(new_vtodo)
+                .returning(VTodo::as_returning())
+                .get_result(conn)
+                .map(VCmp::Todo)?,
+            NewVCmp::Event(new_vevent) => diesel::insert_into(vevents::table)
+                .values(new_vevent)
+                .returning(VEvent::as_returning())
+                .get_result(conn)
+                .map(VCmp::Event)?,
+        };
+        Ok(created)
+    }
 }
 
 #[derive(
@@ -227,9 +770,7 @@ impl Calendar {
         use crate::schema::calendars::dsl as calendars_dsl;
         use crate::schema::servers::dsl as server_dsl;
 
-        spawn_blocking(move || {
-            let conn = &mut *conn.0.lock().unwrap();
-
+        conn.run(move |conn| {
             server_dsl::servers
                 .inner_join(calendars_dsl::calendars)
                 .filter(calendars_dsl::id.eq(calendar_id))
@@ -237,14 +778,13 @@ impl Calendar {
                 .first::<(Server, Calendar)>(conn)
                 .map_err(anyhow::Error::new)
         })
-        .await?
+        .await
     }
 
-    pub async fn by_name(name: &str) -> anyhow::Result<Option<Calendar>> {
+    pub async fn by_name(conn: DbConn, name: &str) -> anyhow::Result<Option<Calendar>> {
         use crate::schema::calendars::dsl as calendars_dsl;
         let name = name.to_string();
-        spawn_blocking(|| {
-            let conn = &mut establish_connection();
+        conn.run(move |conn| {
             calendars_dsl::calendars
                 .filter(calendars_dsl::name.eq(name))
                 .select(Calendar::as_select())
@@ -252,28 +792,27 @@ impl Calendar {
                 .optional()
                 .map_err(anyhow::Error::new)
         })
-        .await?
+        .await
     }
 
-    pub async fn list_all() -> anyhow::Result<Vec<Calendar>> {
+    pub async fn list_all(conn: DbConn) -> anyhow::Result<Vec<Calendar>> {
         use crate::schema::calendars::dsl as calendars_dsl;
         use crate::schema::servers::dsl as server_dsl;
-        let servers = spawn_blocking(|| {
-            let conn = &mut establish_connection();
-            server_dsl::servers
-                .inner_join(calendars_dsl::calendars)
-                .select(Calendar::as_select())
-                .load(conn)
-        })
-        .await??;
+        let servers = conn
+            .run(move |conn| {
+                server_dsl::servers
+                    .inner_join(calendars_dsl::calendars)
+                    .select(Calendar::as_select())
+                    .load(conn)
+            })
+            .await?;
 
         Ok(servers)
     }
 
-    pub async fn set_default_calendar(calendar_id: i32) -> anyhow::Result<()> {
+    pub async fn set_default_calendar(conn: DbConn, calendar_id: i32) -> anyhow::Result<()> {
         use crate::schema::calendars::dsl as calendars_dsl;
-        spawn_blocking(move || {
-            let conn = &mut establish_connection();
+        conn.run(move |conn| {
             conn.transaction::<(), diesel::result::Error, _>(|conn| {
                 diesel::update(calendars_dsl::calendars)
                     .set(calendars_dsl::is_default.eq(false))
@@ -287,15 +826,18 @@ impl Calendar {
             })
             .map_err(anyhow::Error::new)
         })
-        .await?
+        .await
     }
 
-    pub async fn update_sync_token(&self, new_token: &str) -> anyhow::Result<Calendar> {
+    pub async fn update_sync_token(
+        &self,
+        conn: DbConn,
+        new_token: &str,
+    ) -> anyhow::Result<Calendar> {
         use crate::schema::calendars::dsl as calendars_dsl;
         let new_token = new_token.to_string();
         let id = self.id;
-        spawn_blocking(move || {
-            let conn = &mut establish_connection();
+        conn.run(move |conn| {
             update(calendars_dsl::calendars.filter(calendars_dsl::id.eq(id)))
                 .set((
                     calendars_dsl::sync_token.eq(Some(new_token)),
@@ -305,15 +847,14 @@ impl Calendar {
                 .get_result(conn)
                 .map_err(anyhow::Error::new)
         })
-        .await?
+        .await
     }
 
-    pub async fn create_or_update(new_cal: NewCalendar) -> anyhow::Result<Calendar> {
+    pub async fn create_or_update(conn: DbConn, new_cal: NewCalendar) -> anyhow::Result<Calendar> {
         use crate::schema::calendars::dsl as calendars_dsl;
 
-        let calendar_record = Calendar::by_name(&new_cal.name).await?;
-        spawn_blocking(move || {
-            let conn = &mut establish_connection();
+        let calendar_record = Calendar::by_name(conn.clone(), &new_cal.name).await?;
+        conn.run(move |conn| {
             if let Some(calendar) = calendar_record {
                 diesel::update(calendars_dsl::calendars)
                     .filter(calendars_dsl::id.eq(calendar.id))
@@ -332,7 +873,7 @@ impl Calendar {
                     .map_err(anyhow::Error::new)
             }
         })
-        .await?
+        .await
     }
 }
 
@@ -379,7 +920,8 @@ pub trait IcalParseableTrait<Cmp: icalendar::Component> {
     fn get_rrule_from_ical(&self) -> Option<RRuleSet> {
         let event = self.parse_ical_data().ok()?;
         let rrule = get_string_property(&event, ComponentProps::RRule)?;
-        let start_str = get_start_string(&event)?;
+        let timezones = CalendarTimezones::from_ical_data(&self.get_ical_data()?);
+        let start_str = get_start_string(&event, &timezones)?;
 
         let r_date = get_string_property(&event, ComponentProps::RDate);
         let ex_date = get_string_property(&event, ComponentProps::Exdate);
@@ -494,12 +1036,17 @@ fn format_date_ical(date: NaiveDateTime) -> String {
     date.format("%Y%m%dT%H%M%S").to_string()
 }
 
-fn get_start_string(cmp: &impl icalendar::Component) -> Option<String> {
+fn get_start_string(
+    cmp: &impl icalendar::Component,
+    timezones: &CalendarTimezones,
+) -> Option<String> {
     let dt_start = cmp.get_start()?;
 
     match dt_start {
         DatePerhapsTime::DateTime(calendar_date_time) => match calendar_date_time {
-            icalendar::CalendarDateTime::Floating(_) => None,
+            icalendar::CalendarDateTime::Floating(date_time) => {
+                get_local_start_string(timezones, None, date_time)
+            }
             icalendar::CalendarDateTime::Utc(date_time) =>
             // Add `Z` to the end of the date string since RRule assumes local otherwise
             {
@@ -509,8 +1056,7 @@ fn get_start_string(cmp: &impl icalendar::Component) -> Option<String> {
                 ))
             }
             icalendar::CalendarDateTime::WithTimezone { date_time, tzid } => {
-                let date = format_date_ical(date_time);
-                Some(format!("DTSTART;TZID={tzid}:{date}\n"))
+                get_local_start_string(timezones, Some(&tzid), date_time)
             }
         },
         DatePerhapsTime::Date(naive_date) => Some(format!(
@@ -519,3 +1065,218 @@ fn get_start_string(cmp: &impl icalendar::Component) -> Option<String> {
         )),
     }
 }
+
+/// RRule only understands IANA timezones, custom VTIMEZONE definitions take the IANA timezone
+/// with the same offsets so the recurrences keep their local time across DST changes. The
+/// ones without an equivalent are converted to UTC
+fn get_local_start_string(
+    timezones: &CalendarTimezones,
+    tzid: Option<&str>,
+    date_time: NaiveDateTime,
+) -> Option<String> {
+    let tz = timezones
+        .iana_timezone(tzid)
+        .or_else(|| timezones.iana_equivalent(tzid, &date_time));
+    match tz {
+        Some(tz) => Some(format!(
+            "DTSTART;TZID={}:{}\n",
+            tz.name(),
+            format_date_ical(date_time)
+        )),
+        None => timezones
+            .to_utc(tzid, &date_time)
+            .map(|d| format!("DTSTART:{}Z\n", format_date_ical(d.naive_utc()))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+    use crate::test_utils::{create_event, create_todo, new_event, new_synced_todo, new_todo};
+
+    /// Creates an event and a todo that share the same row id
+    fn setup_colliding_ids(conn: DbConn) -> (VEvent, VTodo) {
+        let event = create_event(conn.clone(), new_event("basic.ics"));
+        let todo = create_todo(conn, new_todo("todo.ics"));
+        assert_eq!(event.id, todo.id);
+        (event, todo)
+    }
+
+    #[test]
+    fn test_by_id_does_not_collide() {
+        let conn = DbConn::in_memory().unwrap();
+        let (event, todo) = setup_colliding_ids(conn.clone());
+
+        let found = block_on(VCmp::by_id(conn.clone(), VCmpId::Todo(todo.id)))
+            .unwrap()
+            .unwrap();
+        assert!(matches!(&found, VCmp::Todo(t) if t.uid == todo.uid));
+        assert_eq!(found.get_id(), VCmpId::Todo(todo.id));
+
+        let found = block_on(VCmp::by_id(conn, VCmpId::Event(event.id)))
+            .unwrap()
+            .unwrap();
+        assert!(matches!(&found, VCmp::Event(e) if e.uid == event.uid));
+        assert_eq!(found.get_id(), VCmpId::Event(event.id));
+    }
+
+    #[test]
+    fn test_delete_does_not_collide() {
+        let conn = DbConn::in_memory().unwrap();
+        let (event, todo) = setup_colliding_ids(conn.clone());
+
+        let cmp = block_on(VCmp::by_id(conn.clone(), VCmpId::Todo(todo.id)))
+            .unwrap()
+            .unwrap();
+        assert!(block_on(cmp.delete(conn.clone())).unwrap());
+
+        assert!(
+            block_on(VCmp::by_id(conn.clone(), VCmpId::Todo(todo.id)))
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            block_on(VCmp::by_id(conn, VCmpId::Event(event.id)))
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_todo_turned_into_event() {
+        let conn = DbConn::in_memory().unwrap();
+        // Synced todo from 13:00 to 16:00
+        let todo = create_todo(conn.clone(), new_todo("todo_date.ics"));
+        let edited = VCmp::Todo(VTodo {
+            event_type: EventType::Block,
+            ..todo.clone()
+        });
+        assert!(edited.belongs_in_other_table());
+
+        let converted = edited.into_new_resource("/cal/").unwrap();
+        let converted =
+            block_on(converted.create_in_place_of(conn.clone(), &VCmp::Todo(todo.clone())))
+                .unwrap();
+        let VCmp::Event(event) = &converted else {
+            panic!("Expected an event");
+        };
+        assert_eq!(event.uid, todo.uid);
+        assert_eq!(event.starts_at, todo.starts_at.unwrap());
+        assert_eq!(event.href, Some(format!("/cal/{}.ics", todo.uid)));
+        assert_eq!(event.synced_at, None);
+        // The todo waits for its delete to reach the server
+        let todo_id = VCmpId::Todo(todo.id);
+        assert!(
+            block_on(VCmp::by_id(conn.clone(), todo_id))
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            block_on(VCmp::by_id_with_deleted(conn.clone(), todo_id))
+                .unwrap()
+                .is_some()
+        );
+
+        let back = VCmp::Event(VEvent {
+            event_type: EventType::Task,
+            ..event.clone()
+        });
+        let back = back.into_new_resource("/cal/").unwrap();
+        assert!(block_on(back.create_in_place_of(conn, &converted)).is_err());
+    }
+
+    #[test]
+    fn test_moved_to_calendar_is_created_again() {
+        let conn = DbConn::in_memory().unwrap();
+        let todo = VCmp::Todo(create_todo(conn.clone(), new_synced_todo("todo.ics")));
+        assert!(todo.is_on_server());
+
+        let moved = todo.moved_to_calendar(2, "/other/");
+        assert!(!moved.is_on_server());
+        let moved = moved.into_new_resource("/other/").unwrap();
+        let moved = block_on(moved.create_in_place_of(conn.clone(), &todo)).unwrap();
+        let VCmp::Todo(saved) = block_on(VCmp::by_id(conn.clone(), moved.get_id()))
+            .unwrap()
+            .unwrap()
+        else {
+            panic!("Expected a todo");
+        };
+        assert_eq!(saved.calendar_id, 2);
+        assert_eq!(saved.href, Some(format!("/other/{}.ics", saved.uid)));
+        assert_eq!((saved.etag, saved.synced_at), (None, None));
+        // The old resource waits for its delete to reach the server with the same uid
+        let old = block_on(VCmp::by_id_with_deleted(conn, todo.get_id()))
+            .unwrap()
+            .unwrap();
+        assert!(old.is_deleted());
+        assert_eq!(old.get_uid(), saved.uid);
+        assert_eq!(old.get_href(), todo.get_href());
+    }
+
+    #[test]
+    fn test_merged_with_server_takes_the_server_summary() {
+        let conn = DbConn::in_memory().unwrap();
+        let todo = create_todo(conn, new_synced_todo("todo.ics"));
+        let local = VCmp::Todo(VTodo {
+            description: Some("Two packs".to_string()),
+            ..todo.clone()
+        });
+        let server_data = todo
+            .ical_data
+            .clone()
+            .unwrap()
+            .replace("SUMMARY:Yerba", "SUMMARY:Yerba mate");
+
+        let VCmp::Todo(merged) = local.merged_with_server(&server_data).unwrap() else {
+            panic!("Expected a todo");
+        };
+        assert_eq!(merged.summary, "Yerba mate");
+        // Changed locally only
+        assert_eq!(merged.description, Some("Two packs".to_string()));
+        assert_eq!(merged.status, todo.status);
+        assert_eq!(merged.ical_data, Some(server_data));
+    }
+
+    #[test]
+    fn test_vcmp_id_serialization() {
+        assert_eq!(
+            serde_json::to_string(&VCmpId::Event(5)).unwrap(),
+            r#"{"kind":"event","id":5}"#
+        );
+        let id: VCmpId = serde_json::from_str(r#"{"kind":"todo","id":5}"#).unwrap();
+        assert_eq!(id, VCmpId::Todo(5));
+    }
+
+    #[test]
+    fn test_postponed_only_when_later() {
+        let conn = DbConn::in_memory().unwrap();
+        // Todo from 13:00 to 16:00 on 20/05/24
+        let todo = VCmp::Todo(create_todo(conn, new_todo("todo_date.ics")));
+        let at = |day: u32| Utc.with_ymd_and_hms(2024, 5, day, 13, 0, 0).unwrap();
+        let to = |day: u32| EventDateInfo {
+            start: at(day),
+            end: None,
+            recurrence: crate::calendar_items::event_date::EventRecurrence::none(),
+        };
+
+        let VCmp::Todo(later) = todo.postpone_to(&to(22), &at(20)).unwrap() else {
+            panic!("Expected a todo");
+        };
+        let VCmp::Todo(before) = &todo else {
+            panic!("Expected a todo");
+        };
+        assert_eq!(later.postponed, before.postponed + 1);
+        assert_eq!(later.ends_at, Some(at(22) + TimeDelta::hours(3)));
+        assert_eq!(later.original_text, Some(later.to_input(&at(20))));
+        assert_ne!(later.original_text, before.original_text);
+
+        let VCmp::Todo(earlier) = todo.postpone_to(&to(19), &at(18)).unwrap() else {
+            panic!("Expected a todo");
+        };
+        assert_eq!(earlier.postponed, before.postponed);
+        assert_eq!(earlier.starts_at, Some(at(19)));
+    }
+}
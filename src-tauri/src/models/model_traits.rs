@@ -18,6 +18,11 @@ pub(crate) trait CalendarAndSyncStatus: Sized {
         conn: DbConn,
         calendar_id: i32,
     ) -> anyhow::Result<Vec<Self>>;
+    /// Components deleted locally whose delete wasn't sent to the server yet
+    async fn by_calendar_id_and_deleted(
+        conn: DbConn,
+        calendar_id: i32,
+    ) -> anyhow::Result<Vec<Self>>;
 }
 
 pub(crate) trait ByHref: Sized {
@@ -0,0 +1,89 @@
+//! Items saved only locally under a unique name, like routines and templates.
+//!
+//! They share the `named_items` table, each row keyed by the kind and the name of the
+//! item with the item itself as json.
+
+use diesel::prelude::*;
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{db_conn::DbConn, schema::named_items};
+
+#[derive(Queryable, Selectable, Insertable, Debug)]
+#[diesel(table_name = named_items)]
+struct NamedItemRow {
+    kind: String,
+    name: String,
+    value: String,
+}
+
+pub(crate) trait NamedItem: Serialize + DeserializeOwned + Send + 'static {
+    /// Kind stored next to the name, unique to each implementation
+    const KIND: &'static str;
+
+    fn name(&self) -> &str;
+
+    async fn by_name(conn: DbConn, name: &str) -> anyhow::Result<Option<Self>> {
+        use crate::schema::named_items::dsl as named_items_dsl;
+
+        let name = name.to_string();
+        let row = conn
+            .run(move |conn| {
+                named_items_dsl::named_items
+                    .find((Self::KIND, name))
+                    .select(NamedItemRow::as_select())
+                    .first::<NamedItemRow>(conn)
+                    .optional()
+            })
+            .await?;
+        Ok(row.map(|r| serde_json::from_str(&r.value)).transpose()?)
+    }
+
+    /// Sorted by name
+    async fn list_by_name(conn: DbConn) -> anyhow::Result<Vec<Self>> {
+        use crate::schema::named_items::dsl as named_items_dsl;
+
+        let rows = conn
+            .run(move |conn| {
+                named_items_dsl::named_items
+                    .filter(named_items_dsl::kind.eq(Self::KIND))
+                    .order(named_items_dsl::name.asc())
+                    .select(NamedItemRow::as_select())
+                    .load(conn)
+            })
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|r| serde_json::from_str(&r.value))
+            .collect::<Result<Vec<Self>, _>>()?)
+    }
+
+    /// Creates the item or replaces the one of the same kind with the same name
+    async fn save(&self, conn: DbConn) -> anyhow::Result<()> {
+        use crate::schema::named_items::dsl as named_items_dsl;
+
+        let row = NamedItemRow {
+            kind: Self::KIND.to_string(),
+            name: self.name().to_string(),
+            value: serde_json::to_string(self)?,
+        };
+        conn.run(move |conn| {
+            diesel::replace_into(named_items_dsl::named_items)
+                .values(row)
+                .execute(conn)
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_by_name(conn: DbConn, name: &str) -> anyhow::Result<bool> {
+        use crate::schema::named_items::dsl as named_items_dsl;
+
+        let name = name.to_string();
+        let deleted = conn
+            .run(move |conn| {
+                diesel::delete(named_items_dsl::named_items.find((Self::KIND, name))).execute(conn)
+            })
+            .await?;
+        Ok(deleted > 0)
+    }
+}
@@ -0,0 +1,511 @@
+//! Typed edit of some fields of a component, for the changes that can't be written as a
+//! quick-add input, like the description, or that would reset the fields the input
+//! doesn't have.
+
+use anyhow::anyhow;
+use chrono::{DateTime, TimeZone, Utc};
+use diesel::prelude::*;
+use rrule::{RRule, RRuleSet, Unvalidated};
+
+use crate::{
+    calendar_items::{
+        component_props::merge_tags,
+        event_date::{EventDateInfo, EventRecurrence},
+        event_status::EventStatus,
+        event_type::EventType,
+        input_traits::ToUserInput,
+    },
+    db_conn::DbConn,
+    models::{IcalParseableTrait, VCmp},
+    schema::{vevents, vtodos},
+};
+
+/// Fields to change, the ones left as [`None`] keep their value
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, specta::Type)]
+pub struct VCmpPatch {
+    pub summary: Option<String>,
+    /// An empty description removes it
+    pub description: Option<String>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    /// Value of an `RRULE`, like `FREQ=WEEKLY;BYDAY=MO`, empty to stop repeating
+    pub rrule: Option<String>,
+    pub status: Option<EventStatus>,
+    pub event_type: Option<EventType>,
+    /// Replaces all the tags, an empty list removes them
+    pub tags: Option<Vec<String>>,
+    pub urgency: Option<i32>,
+    pub importance: Option<i32>,
+    pub load: Option<i32>,
+    pub calendar_id: Option<i32>,
+}
+
+/// Columns of `vevents` written by a patch, the ones left as [`None`] aren't written
+#[derive(AsChangeset)]
+#[diesel(table_name = vevents)]
+struct VEventChanges {
+    summary: Option<String>,
+    description: Option<Option<String>>,
+    starts_at: Option<DateTime<Utc>>,
+    ends_at: Option<DateTime<Utc>>,
+    has_rrule: Option<bool>,
+    rrule_str: Option<Option<String>>,
+    tag: Option<Option<String>>,
+    status: Option<EventStatus>,
+    event_type: Option<EventType>,
+    original_text: Option<Option<String>>,
+    load: Option<i32>,
+    urgency: Option<i32>,
+    importance: Option<i32>,
+    calendar_id: Option<i32>,
+    href: Option<Option<String>>,
+    etag: Option<Option<String>>,
+    synced_at: Option<Option<DateTime<Utc>>>,
+    out_of_sync: Option<bool>,
+}
+
+/// Columns of `vtodos` written by a patch, the ones left as [`None`] aren't written
+#[derive(AsChangeset)]
+#[diesel(table_name = vtodos)]
+struct VTodoChanges {
+    summary: Option<String>,
+    description: Option<Option<String>>,
+    starts_at: Option<Option<DateTime<Utc>>>,
+    ends_at: Option<Option<DateTime<Utc>>>,
+    has_rrule: Option<bool>,
+    rrule_str: Option<Option<String>>,
+    tag: Option<Option<String>>,
+    status: Option<EventStatus>,
+    completed: Option<Option<DateTime<Utc>>>,
+    event_type: Option<EventType>,
+    original_text: Option<Option<String>>,
+    load: Option<i32>,
+    urgency: Option<i32>,
+    importance: Option<i32>,
+    calendar_id: Option<i32>,
+    href: Option<Option<String>>,
+    etag: Option<Option<String>>,
+    synced_at: Option<Option<DateTime<Utc>>>,
+    out_of_sync: Option<bool>,
+}
+
+/// Takes the value of each field set in the patch out of the patched component, along
+/// with the columns changed with them. The columns only one of the tables has are given
+/// after the component
+macro_rules! changes_of {
+    ($patch:expr, $cmp:expr, $changes:ident { $($field:ident: $value:expr),* }) => {{
+        let dates =
+            $patch.starts_at.is_some() || $patch.ends_at.is_some() || $patch.rrule.is_some();
+        let moved = $patch.calendar_id.is_some();
+        $changes {
+            summary: $patch.summary.as_ref().map(|_| $cmp.summary.clone()),
+            description: $patch.description.as_ref().map(|_| $cmp.description.clone()),
+            starts_at: dates.then_some($cmp.starts_at),
+            ends_at: dates.then_some($cmp.ends_at),
+            has_rrule: dates.then_some($cmp.has_rrule),
+            rrule_str: dates.then(|| $cmp.rrule_str.clone()),
+            tag: $patch.tags.as_ref().map(|_| $cmp.tag.clone()),
+            status: $patch.status.map(|_| $cmp.status),
+            event_type: $patch.event_type.map(|_| $cmp.event_type),
+            original_text: Some($cmp.original_text.clone()),
+            load: $patch.load.map(|_| $cmp.load),
+            urgency: $patch.urgency.map(|_| $cmp.urgency),
+            importance: $patch.importance.map(|_| $cmp.importance),
+            calendar_id: moved.then_some($cmp.calendar_id),
+            href: moved.then(|| $cmp.href.clone()),
+            etag: moved.then(|| $cmp.etag.clone()),
+            synced_at: moved.then_some($cmp.synced_at),
+            out_of_sync: Some($cmp.out_of_sync),
+            $($field: $value),*
+        }
+    }};
+}
+
+/// Sets the fields shared by [`VEvent`](super::vevent::VEvent) and
+/// [`VTodo`](super::vtodo::VTodo)
+macro_rules! patch_common_fields {
+    ($patch:expr, $cmp:expr) => {{
+        if let Some(summary) = &$patch.summary {
+            $cmp.summary = summary.trim().to_string();
+        }
+        if let Some(description) = &$patch.description {
+            $cmp.description = non_empty(description);
+        }
+        if let Some(event_type) = $patch.event_type {
+            $cmp.event_type = event_type;
+        }
+        if let Some(tags) = &$patch.tags {
+            $cmp.tag = merge_tags(None, tags.clone());
+        }
+        if let Some(urgency) = $patch.urgency {
+            $cmp.urgency = urgency;
+        }
+        if let Some(importance) = $patch.importance {
+            $cmp.importance = importance;
+        }
+        if let Some(load) = $patch.load {
+            $cmp.load = load;
+        }
+        $cmp.out_of_sync = true;
+    }};
+}
+
+impl VCmpPatch {
+    /// Component with the patch applied, not saved yet. `calendar_url` is the url of the
+    /// calendar in `calendar_id` and `date_of_change` is used to complete todos and to
+    /// write the `original_text` back
+    pub fn apply_to<Tz: TimeZone>(
+        &self,
+        vcmp: &VCmp,
+        calendar_url: Option<&str>,
+        date_of_change: DateTime<Tz>,
+    ) -> anyhow::Result<VCmp> {
+        self.validate()?;
+
+        let mut patched = match self.calendar_id {
+            Some(calendar_id) if calendar_id != vcmp.get_calendar_id() => {
+                let calendar_url =
+                    calendar_url.ok_or(anyhow!("No calendar with id {calendar_id}"))?;
+                vcmp.moved_to_calendar(calendar_id, calendar_url)
+            }
+            _ => vcmp.clone(),
+        };
+        if let Some(status) = self.status {
+            patched.set_status(status, date_of_change.clone());
+        }
+
+        match &mut patched {
+            VCmp::Event(vevent) => {
+                patch_common_fields!(self, vevent);
+                let starts_at = self.starts_at.unwrap_or(vevent.starts_at);
+                // Moving only the start keeps the duration
+                let ends_at = match (self.starts_at, self.ends_at) {
+                    (_, Some(ends_at)) => ends_at,
+                    (Some(_), None) => starts_at + (vevent.ends_at - vevent.starts_at),
+                    (None, None) => vevent.ends_at,
+                };
+                if ends_at <= starts_at {
+                    return Err(anyhow!("The end must be after the start"));
+                }
+                let current = vevent.get_rrule();
+                let rrule = self.rrule_for(current.as_ref(), vevent.starts_at != starts_at);
+                vevent.starts_at = starts_at;
+                vevent.ends_at = ends_at;
+                if let Some(rrule) = rrule {
+                    vevent.rrule_str = rrule
+                        .map(|r| rrule_set_str(starts_at, &r, current.as_ref()))
+                        .transpose()?;
+                    vevent.has_rrule = vevent.rrule_str.is_some();
+                }
+                vevent.original_text = Some(vevent.to_input(&date_of_change));
+            }
+            VCmp::Todo(vtodo) => {
+                patch_common_fields!(self, vtodo);
+                let starts_at = self.starts_at.or(vtodo.starts_at);
+                let ends_at = match (starts_at, self.starts_at, self.ends_at) {
+                    (None, _, Some(_)) => {
+                        return Err(anyhow!("{} needs a start to have an end", vtodo.summary));
+                    }
+                    (_, _, Some(ends_at)) => Some(ends_at),
+                    (Some(start), Some(_), None) => match vtodo.starts_at.zip(vtodo.ends_at) {
+                        Some((s, e)) => Some(start + (e - s)),
+                        None => Some(default_end(start, vtodo.event_type)),
+                    },
+                    (_, None, None) => vtodo.ends_at,
+                };
+                if let (Some(starts_at), Some(ends_at)) = (starts_at, ends_at)
+                    && ends_at <= starts_at
+                {
+                    return Err(anyhow!("The end must be after the start"));
+                }
+                let current = vtodo.get_rrule();
+                let rrule = self.rrule_for(current.as_ref(), vtodo.starts_at != starts_at);
+                vtodo.starts_at = starts_at;
+                vtodo.ends_at = ends_at;
+                if let Some(rrule) = rrule {
+                    vtodo.rrule_str = match (rrule, starts_at) {
+                        (Some(_), None) => {
+                            return Err(anyhow!("{} needs a start to repeat", vtodo.summary));
+                        }
+                        (Some(rrule), Some(start)) => {
+                            Some(rrule_set_str(start, &rrule, current.as_ref())?)
+                        }
+                        (None, _) => None,
+                    };
+                    vtodo.has_rrule = vtodo.rrule_str.is_some();
+                }
+                vtodo.original_text = Some(vtodo.to_input(&date_of_change));
+            }
+        }
+        Ok(patched)
+    }
+
+    /// Writes the columns changed by the patch out of `patched`, the component returned by
+    /// [`Self::apply_to`], so the other columns keep the value they have in the table
+    pub async fn save(&self, conn: DbConn, patched: &VCmp) -> anyhow::Result<()> {
+        let patch = self.clone();
+        let patched = patched.clone();
+        conn.run(move |conn| patch.save_in(conn, &patched)).await
+    }
+
+    /// Same as [`VCmpPatch::save`] on a connection that may be in a transaction
+    pub fn save_in(&self, conn: &mut SqliteConnection, patched: &VCmp) -> anyhow::Result<()> {
+        match patched {
+            VCmp::Event(vevent) => {
+                let changes = changes_of!(self, vevent, VEventChanges {});
+                diesel::update(vevents::table.find(vevent.id))
+                    .set(&changes)
+                    .execute(conn)?
+            }
+            VCmp::Todo(vtodo) => {
+                let changes = changes_of!(
+                    self,
+                    vtodo,
+                    VTodoChanges {
+                        completed: self.status.map(|_| vtodo.completed)
+                    }
+                );
+                diesel::update(vtodos::table.find(vtodo.id))
+                    .set(&changes)
+                    .execute(conn)?
+            }
+        };
+        Ok(())
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.summary.as_ref().is_some_and(|s| s.trim().is_empty()) {
+            return Err(anyhow!("The summary can't be empty"));
+        }
+        if self.urgency.is_some_and(|u| !(0..=3).contains(&u)) {
+            return Err(anyhow!("The urgency goes from 0 to 3"));
+        }
+        if self.importance.is_some_and(|i| !(-3..=3).contains(&i)) {
+            return Err(anyhow!("The importance goes from -3 to 3"));
+        }
+        if self.load.is_some_and(|l| l < 0) {
+            return Err(anyhow!("The load can't be negative"));
+        }
+        Ok(())
+    }
+
+    /// New `RRULE` value, [`None`] when the recurrence stays as it is. The current one is
+    /// written again when the start moves, since the recurrence starts with it
+    fn rrule_for(&self, current: Option<&RRuleSet>, start_moved: bool) -> Option<Option<String>> {
+        match &self.rrule {
+            Some(rrule) => Some(non_empty(rrule)),
+            None if start_moved => current
+                .and_then(|set| set.get_rrule().first().map(|r| r.to_string()))
+                .map(Some),
+            None => None,
+        }
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+fn default_end(start: DateTime<Utc>, event_type: EventType) -> DateTime<Utc> {
+    EventDateInfo {
+        start,
+        end: None,
+        recurrence: EventRecurrence::none(),
+    }
+    .get_end_or_default(event_type)
+}
+
+/// Recurrence as stored in `rrule_str`, starting at `start`. The timezone of the
+/// `current` recurrence is kept, so it follows the daylight saving time changes, and its
+/// added and excluded dates move with the start
+fn rrule_set_str(
+    start: DateTime<Utc>,
+    rrule: &str,
+    current: Option<&RRuleSet>,
+) -> anyhow::Result<String> {
+    let rrule = rrule.trim_start_matches("RRULE:");
+    let tz = current.map_or(rrule::Tz::UTC, |set| set.get_dt_start().timezone());
+    let dt_start = start.with_timezone(&tz);
+    let rule = rrule
+        .parse::<RRule<Unvalidated>>()
+        .map_err(|e| anyhow!("Invalid recurrence {rrule}: {e}"))?
+        .validate(dt_start)
+        .map_err(|e| anyhow!("Invalid recurrence {rrule}: {e}"))?;
+    let mut set = RRuleSet::new(dt_start).rrule(rule);
+    if let Some(current) = current {
+        let moved = start - current.get_dt_start().to_utc();
+        for rdate in current.get_rdate() {
+            set = set.rdate(*rdate + moved);
+        }
+        for exdate in current.get_exdate() {
+            set = set.exdate(*exdate + moved);
+        }
+    }
+    Ok(set.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+    use crate::{
+        db_conn::DbConn,
+        models::vevent::VEvent,
+        test_utils::{create_event, create_todo, new_event, new_todo},
+    };
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 20, hour, 0, 0).unwrap()
+    }
+
+    fn patched_event(patch: VCmpPatch, event: &VEvent) -> VEvent {
+        let VCmp::Event(patched) = patch
+            .apply_to(&VCmp::Event(event.clone()), None, at(9))
+            .unwrap()
+        else {
+            panic!("Expected an event");
+        };
+        patched
+    }
+
+    #[test]
+    fn test_patch_keeps_other_fields() {
+        let event = create_event(DbConn::in_memory().unwrap(), new_event("basic.ics"));
+        let patched = patched_event(
+            VCmpPatch {
+                description: Some("Bring water".to_string()),
+                urgency: Some(2),
+                ends_at: Some(at(17)),
+                ..Default::default()
+            },
+            &event,
+        );
+        assert_eq!(patched.description, Some("Bring water".to_string()));
+        assert_eq!(patched.urgency, 2);
+        assert_eq!(patched.ends_at, at(17));
+        assert_eq!(patched.starts_at, event.starts_at);
+        assert_eq!(patched.summary, event.summary);
+        assert_eq!(patched.tag, event.tag);
+        assert_eq!(patched.rrule_str, event.rrule_str);
+        assert!(patched.out_of_sync);
+        assert_eq!(patched.original_text, Some(patched.to_input(&at(9))));
+    }
+
+    #[test]
+    fn test_moving_the_start_keeps_duration_and_recurrence() {
+        let event = create_event(DbConn::in_memory().unwrap(), new_event("basic.ics"));
+        let patched = patched_event(
+            VCmpPatch {
+                starts_at: Some(at(14)),
+                ..Default::default()
+            },
+            &event,
+        );
+        assert_eq!(patched.ends_at, at(17));
+        assert!(patched.has_rrule);
+        let starts = patched.get_rrule().unwrap().all(1).dates;
+        assert_eq!(starts[0].to_utc(), at(14));
+
+        let patched = patched_event(
+            VCmpPatch {
+                rrule: Some(String::new()),
+                ..Default::default()
+            },
+            &event,
+        );
+        assert!(!patched.has_rrule);
+        assert_eq!(patched.rrule_str, None);
+    }
+
+    #[test]
+    fn test_moving_the_start_keeps_timezone_and_exceptions() {
+        let event = VEvent {
+            rrule_str: Some(
+                "DTSTART;TZID=America/Detroit:20240520T090000\n\
+                RRULE:FREQ=DAILY\n\
+                EXDATE;TZID=America/Detroit:20240522T090000"
+                    .to_string(),
+            ),
+            ..create_event(DbConn::in_memory().unwrap(), new_event("basic.ics"))
+        };
+        let patched = patched_event(
+            VCmpPatch {
+                starts_at: Some(at(14)),
+                ..Default::default()
+            },
+            &event,
+        );
+        assert!(patched.rrule_str.unwrap().contains("TZID=America/Detroit"));
+        let starts = patched
+            .get_rrule()
+            .unwrap()
+            .all(3)
+            .dates
+            .iter()
+            .map(|d| d.to_utc())
+            .collect::<Vec<DateTime<Utc>>>();
+        let day = |day: u32| Utc.with_ymd_and_hms(2024, 5, day, 14, 0, 0).unwrap();
+        assert_eq!(starts, vec![day(20), day(21), day(23)]);
+    }
+
+    #[test]
+    fn test_save_writes_only_the_patched_columns() {
+        let conn = DbConn::in_memory().unwrap();
+        let event = create_event(conn.clone(), new_event("basic.ics"));
+        let patch = VCmpPatch {
+            urgency: Some(3),
+            ..Default::default()
+        };
+        let patched = patch
+            .apply_to(&VCmp::Event(event.clone()), None, at(9))
+            .unwrap();
+        // Changed since the patch was applied, like by a sync
+        block_on(
+            VCmp::Event(VEvent {
+                summary: "Changed".to_string(),
+                ..event.clone()
+            })
+            .update(conn.clone()),
+        )
+        .unwrap();
+
+        block_on(patch.save(conn.clone(), &patched)).unwrap();
+        let Some(VCmp::Event(saved)) = block_on(VCmp::by_id(conn, patched.get_id())).unwrap()
+        else {
+            panic!("Expected an event");
+        };
+        assert_eq!(saved.urgency, 3);
+        assert_eq!(saved.summary, "Changed");
+        assert!(saved.out_of_sync);
+    }
+
+    #[test]
+    fn test_invalid_patch() {
+        let conn = DbConn::in_memory().unwrap();
+        let event = VCmp::Event(create_event(conn.clone(), new_event("basic.ics")));
+        let before_start = VCmpPatch {
+            ends_at: Some(at(12)),
+            ..Default::default()
+        };
+        assert!(before_start.apply_to(&event, None, at(9)).is_err());
+        let urgency = VCmpPatch {
+            urgency: Some(5),
+            ..Default::default()
+        };
+        assert!(urgency.apply_to(&event, None, at(9)).is_err());
+
+        // Unscheduled todo
+        let todo = VCmp::Todo(create_todo(conn, new_todo("todo.ics")));
+        let end_only = VCmpPatch {
+            ends_at: Some(at(12)),
+            ..Default::default()
+        };
+        assert!(end_only.apply_to(&todo, None, at(9)).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+//! Components moved to the times proposed in a preview, like the slots of the scheduler
+//! or the items of a shifted day.
+//!
+//! The preview may be stale by the time it's applied, so each component is checked to
+//! still start where it did when the times were proposed. All the changes are applied
+//! in a single transaction, or none of them when one of the components changed.
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+
+use crate::{
+    db_conn::DbConn,
+    models::{VCmp, VCmpId, journal::JournalEntry, vevent::VEvent, vtodo::VTodo},
+};
+
+/// New times of a component, `starts_at` is its start when they were proposed, [`None`]
+/// for an unscheduled todo
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeChange {
+    pub vcmp_id: VCmpId,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub new_starts_at: DateTime<Utc>,
+    pub new_ends_at: DateTime<Utc>,
+}
+
+/// Moves every component to its new times, each change is journaled and left out of
+/// sync so the next sync pushes it
+pub async fn apply(conn: DbConn, changes: Vec<TimeChange>) -> anyhow::Result<()> {
+    conn.run(move |conn| {
+        conn.transaction::<_, anyhow::Error, _>(|conn| {
+            changes
+                .iter()
+                .try_for_each(|change| apply_change(conn, change))
+        })
+    })
+    .await
+}
+
+fn apply_change(conn: &mut SqliteConnection, change: &TimeChange) -> anyhow::Result<()> {
+    let vcmp_id = change.vcmp_id;
+    let before = VCmp::by_id_in(conn, vcmp_id)?.ok_or(anyhow!("No cmp with id {vcmp_id}"))?;
+    let after = match &before {
+        VCmp::Event(vevent) if change.starts_at == Some(vevent.starts_at) => VCmp::Event(VEvent {
+            starts_at: change.new_starts_at,
+            ends_at: change.new_ends_at,
+            out_of_sync: true,
+            ..vevent.clone()
+        }),
+        VCmp::Todo(vtodo) if change.starts_at == vtodo.starts_at => VCmp::Todo(VTodo {
+            starts_at: Some(change.new_starts_at),
+            ends_at: Some(change.new_ends_at),
+            out_of_sync: true,
+            ..vtodo.clone()
+        }),
+        _ => {
+            return Err(anyhow!(
+                "{} changed since the times were proposed",
+                before.get_summary()
+            ));
+        }
+    };
+    after.replace_in(conn)?;
+    JournalEntry::record_in(conn, Some(&before), Some(&after))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, TimeZone};
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+    use crate::test_utils::{create_todo, get_todo, new_todo};
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 20, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_stale_change_applies_nothing() {
+        let conn = DbConn::in_memory().unwrap();
+        // Unscheduled todo and todo from 13:00 to 16:00
+        let unscheduled = create_todo(conn.clone(), new_todo("todo.ics"));
+        let dated = create_todo(conn.clone(), new_todo("todo_date.ics"));
+
+        let schedule = TimeChange {
+            vcmp_id: VCmpId::Todo(unscheduled.id),
+            starts_at: None,
+            new_starts_at: at(9),
+            new_ends_at: at(10),
+        };
+        // Proposed when it started an hour earlier
+        let stale = TimeChange {
+            vcmp_id: VCmpId::Todo(dated.id),
+            starts_at: Some(at(12)),
+            new_starts_at: at(17),
+            new_ends_at: at(20),
+        };
+        assert!(block_on(apply(conn.clone(), vec![schedule.clone(), stale])).is_err());
+        let todo = get_todo(conn.clone(), schedule.vcmp_id).unwrap();
+        assert_eq!(todo.starts_at, None);
+
+        let shift = TimeChange {
+            vcmp_id: VCmpId::Todo(dated.id),
+            starts_at: dated.starts_at,
+            new_starts_at: at(13) + TimeDelta::minutes(30),
+            new_ends_at: at(16) + TimeDelta::minutes(30),
+        };
+        block_on(apply(conn.clone(), vec![schedule.clone(), shift.clone()])).unwrap();
+        let todo = get_todo(conn.clone(), schedule.vcmp_id).unwrap();
+        assert_eq!(todo.starts_at, Some(at(9)));
+        assert!(todo.out_of_sync);
+        let todo = get_todo(conn.clone(), shift.vcmp_id).unwrap();
+        assert_eq!(todo.starts_at, Some(shift.new_starts_at));
+
+        // Already scheduled
+        assert!(block_on(apply(conn, vec![schedule])).is_err());
+    }
+}
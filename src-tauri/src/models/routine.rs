@@ -0,0 +1,68 @@
+use crate::{
+    db_conn::DbConn,
+    models::{model_traits::ListAll, named_item::NamedItem},
+};
+
+/// Step of a routine
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, specta::Type)]
+pub struct RoutineItem {
+    /// Quick-add input, like `.b Stretch #health`
+    pub input: String,
+    /// Minutes from the start of the routine, used when the input has no date
+    pub offset_minutes: i32,
+}
+
+/// Named sequence of items created together, stored only locally with its items as
+/// json
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, specta::Type)]
+pub struct Routine {
+    pub name: String,
+    pub items: Vec<RoutineItem>,
+}
+
+impl NamedItem for Routine {
+    const KIND: &'static str = "routine";
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl ListAll for Routine {
+    /// Sorted by name
+    async fn list_all(conn: DbConn) -> anyhow::Result<Vec<Self>> {
+        Self::list_by_name(conn).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+
+    #[test]
+    fn test_save_and_replace() {
+        let conn = DbConn::in_memory().unwrap();
+        let routine = Routine {
+            name: "morning".to_string(),
+            items: vec![RoutineItem {
+                input: ".b Stretch".to_string(),
+                offset_minutes: 0,
+            }],
+        };
+        block_on(routine.save(conn.clone())).unwrap();
+        let replaced = Routine {
+            items: vec![],
+            ..routine
+        };
+        block_on(replaced.save(conn.clone())).unwrap();
+
+        assert_eq!(
+            block_on(Routine::list_all(conn.clone())).unwrap(),
+            vec![replaced]
+        );
+        assert!(block_on(Routine::delete_by_name(conn.clone(), "morning")).unwrap());
+        assert_eq!(block_on(Routine::by_name(conn, "morning")).unwrap(), None);
+    }
+}
@@ -0,0 +1,199 @@
+use anyhow::anyhow;
+use chrono::Weekday;
+use diesel::prelude::*;
+use serde_json::{Map, Value};
+
+use crate::{db_conn::DbConn, schema::settings};
+
+/// Longest time a deleted component can stay in the trash, about ten years
+const MAX_TRASH_RETENTION_DAYS: i32 = 3650;
+
+#[derive(Queryable, Selectable, Insertable, Debug)]
+#[diesel(table_name = settings)]
+struct SettingRow {
+    key: String,
+    value: String,
+}
+
+/// User settings, each field is stored as a row of the `settings` table with
+/// its value as json, the missing ones take the default value
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, specta::Type)]
+#[serde(default)]
+pub struct Settings {
+    /// Days a deleted component stays in the trash before it's purged
+    pub trash_retention_days: i32,
+    /// Calendars whose overdue todos are moved to the current day
+    pub rollover_calendar_ids: Vec<i32>,
+    /// Tags whose overdue todos are moved to the current day, without the `#`
+    pub rollover_tags: Vec<String>,
+    /// Load that fits in a day
+    pub daily_load_budget: i32,
+    /// Load that fits in each weekday starting on monday, a missing value uses `daily_load_budget`
+    pub weekday_load_budgets: Vec<Option<i32>>,
+    /// Hour the working day starts, the free time of a day is counted from it
+    pub day_start_hour: u32,
+    /// Hour the working day ends
+    pub day_end_hour: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            trash_retention_days: 30,
+            rollover_calendar_ids: vec![],
+            rollover_tags: vec![],
+            daily_load_budget: 10,
+            weekday_load_budgets: vec![],
+            day_start_hour: 8,
+            day_end_hour: 20,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load_budget_for(&self, weekday: Weekday) -> i32 {
+        self.weekday_load_budgets
+            .get(weekday.num_days_from_monday() as usize)
+            .copied()
+            .flatten()
+            .unwrap_or(self.daily_load_budget)
+    }
+
+    /// Whether the overdue todos of the calendar, or with any of the tags, roll over
+    pub fn rolls_over(&self, calendar_id: i32, tag: Option<&str>) -> bool {
+        if self.rollover_calendar_ids.contains(&calendar_id) {
+            return true;
+        }
+        tag.into_iter().flat_map(|tag| tag.split(',')).any(|tag| {
+            self.rollover_tags
+                .iter()
+                .any(|t| t.trim_start_matches('#').eq_ignore_ascii_case(tag.trim()))
+        })
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !(1..=MAX_TRASH_RETENTION_DAYS).contains(&self.trash_retention_days) {
+            return Err(anyhow!(
+                "Deleted items should stay in the trash from 1 to {MAX_TRASH_RETENTION_DAYS} days, got {}",
+                self.trash_retention_days
+            ));
+        }
+        Ok(())
+    }
+
+    pub async fn load(conn: DbConn) -> anyhow::Result<Settings> {
+        use crate::schema::settings::dsl as settings_dsl;
+
+        let rows = conn
+            .run(move |conn| {
+                settings_dsl::settings
+                    .select(SettingRow::as_select())
+                    .load(conn)
+            })
+            .await?;
+
+        let mut values = Map::new();
+        for row in rows {
+            match serde_json::from_str::<Value>(&row.value) {
+                Ok(value) => {
+                    values.insert(row.key, value);
+                }
+                Err(e) => log::warn!("Ignoring setting {}: {e}", row.key),
+            }
+        }
+        Ok(serde_json::from_value(Value::Object(values))?)
+    }
+
+    pub async fn save(&self, conn: DbConn) -> anyhow::Result<()> {
+        use crate::schema::settings::dsl as settings_dsl;
+
+        let Value::Object(values) = serde_json::to_value(self)? else {
+            return Err(anyhow!("Settings should serialize to an object"));
+        };
+        let rows = values
+            .into_iter()
+            .map(|(key, value)| SettingRow {
+                key,
+                value: value.to_string(),
+            })
+            .collect::<Vec<SettingRow>>();
+
+        conn.run(move |conn| {
+            diesel::replace_into(settings_dsl::settings)
+                .values(rows)
+                .execute(conn)
+        })
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+
+    #[test]
+    fn test_defaults_when_empty() {
+        let conn = DbConn::in_memory().unwrap();
+        let settings = block_on(Settings::load(conn)).unwrap();
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let conn = DbConn::in_memory().unwrap();
+        let settings = Settings {
+            trash_retention_days: 7,
+            rollover_calendar_ids: vec![2],
+            rollover_tags: vec!["work".to_string()],
+            weekday_load_budgets: vec![None, Some(4)],
+            ..Default::default()
+        };
+        block_on(settings.save(conn.clone())).unwrap();
+        assert_eq!(block_on(Settings::load(conn)).unwrap(), settings);
+    }
+
+    #[test]
+    fn test_validate_trash_retention() {
+        assert!(Settings::default().validate().is_ok());
+        let ten_years = Settings {
+            trash_retention_days: MAX_TRASH_RETENTION_DAYS,
+            ..Default::default()
+        };
+        assert!(ten_years.validate().is_ok());
+        for days in [0, -1, MAX_TRASH_RETENTION_DAYS + 1, 100_000_000] {
+            let settings = Settings {
+                trash_retention_days: days,
+                ..Default::default()
+            };
+            assert!(settings.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn test_rolls_over() {
+        let settings = Settings {
+            rollover_calendar_ids: vec![2],
+            rollover_tags: vec!["#Work".to_string()],
+            ..Default::default()
+        };
+        assert!(settings.rolls_over(2, None));
+        assert!(settings.rolls_over(1, Some("health,work")));
+        assert!(!settings.rolls_over(1, Some("health")));
+        assert!(!Settings::default().rolls_over(2, Some("work")));
+    }
+
+    #[test]
+    fn test_load_budget_for_weekday() {
+        let settings = Settings {
+            daily_load_budget: 8,
+            weekday_load_budgets: vec![None, Some(4)],
+            ..Default::default()
+        };
+        assert_eq!(settings.load_budget_for(Weekday::Mon), 8);
+        assert_eq!(settings.load_budget_for(Weekday::Tue), 4);
+        assert_eq!(settings.load_budget_for(Weekday::Sun), 8);
+    }
+}
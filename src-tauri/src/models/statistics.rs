@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Days, NaiveDate, TimeDelta, TimeZone};
+use now::DateTimeNow;
+
+use crate::{
+    db_conn::DbConn,
+    models::{
+        vevent::{VEvent, VEventTrait},
+        vtodo::VTodo,
+    },
+};
+
+/// Longest range the statistics are calculated for
+const MAX_DAYS: usize = 366;
+
+/// Values of a single day of the range
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, specta::Type)]
+pub struct DayBucket {
+    pub day: NaiveDate,
+    /// Todos created that day
+    pub created: i32,
+    /// Todos completed that day
+    pub completed: i32,
+    /// Sum of the load of the todos completed that day
+    pub load_done: i32,
+}
+
+/// Minutes spent in `Block` and `Event` items of a tag, one value per day of the range
+#[derive(Clone, Debug, PartialEq, serde::Serialize, specta::Type)]
+pub struct TagSeries {
+    /// [`None`] for the items without tags
+    pub tag: Option<String>,
+    pub minutes: Vec<i32>,
+    pub total_minutes: i32,
+}
+
+/// Metrics over the work of a range of days, shaped for charts
+#[derive(Clone, Debug, PartialEq, serde::Serialize, specta::Type)]
+pub struct Statistics {
+    /// One bucket per day of the range, including the empty ones
+    pub days: Vec<DayBucket>,
+    pub created: i32,
+    pub completed: i32,
+    /// Average times the todos completed in the range were postponed
+    pub average_postponed: f64,
+    /// Sorted by tag, the untagged items first
+    pub time_per_tag: Vec<TagSeries>,
+}
+
+impl Statistics {
+    /// Statistics from the day of `from` to the day of `to`, both included, the days
+    /// are taken in the timezone of `from`
+    pub async fn for_range<Tz: TimeZone>(
+        conn: DbConn,
+        from: DateTime<Tz>,
+        to: DateTime<Tz>,
+    ) -> anyhow::Result<Statistics> {
+        let days = days_between(&from, &to)?;
+        let start = from.beginning_of_day().to_utc();
+        let end = to.end_of_day().to_utc();
+
+        let todos = VTodo::list_created_or_completed_between(conn.clone(), start, end).await?;
+        let events = VEvent::list_timed_between_or_recurring(conn, start, end).await?;
+        Ok(Self::aggregate(&days, &todos, &events))
+    }
+
+    fn aggregate<Tz: TimeZone>(days: &[DateTime<Tz>], todos: &[VTodo], events: &[VEvent]) -> Self {
+        let index_of = |date: DateTime<chrono::Utc>| {
+            days.iter()
+                .position(|d| d.date_naive() == date.with_timezone(&d.timezone()).date_naive())
+        };
+
+        let mut buckets = days
+            .iter()
+            .map(|d| DayBucket {
+                day: d.date_naive(),
+                ..Default::default()
+            })
+            .collect::<Vec<DayBucket>>();
+        let mut postponed = Vec::new();
+        for todo in todos {
+            if let Some(i) = todo.created_at.and_then(index_of) {
+                buckets[i].created += 1;
+            }
+            if let Some(i) = todo.completed.and_then(index_of) {
+                buckets[i].completed += 1;
+                buckets[i].load_done += todo.load;
+                postponed.push(todo.postponed);
+            }
+        }
+
+        let mut series: BTreeMap<Option<String>, Vec<i32>> = BTreeMap::new();
+        for (i, day) in days.iter().enumerate() {
+            for event in events {
+                let Some(duration) = duration_on_day(event, day) else {
+                    continue;
+                };
+                let minutes = i32::try_from(duration.num_minutes()).unwrap_or(i32::MAX);
+                for tag in split_tags(event.tag.as_deref()) {
+                    series.entry(tag).or_insert_with(|| vec![0; days.len()])[i] += minutes;
+                }
+            }
+        }
+
+        let average_postponed = if postponed.is_empty() {
+            0.0
+        } else {
+            f64::from(postponed.iter().sum::<i32>()) / postponed.len() as f64
+        };
+        Self {
+            created: buckets.iter().map(|b| b.created).sum(),
+            completed: buckets.iter().map(|b| b.completed).sum(),
+            days: buckets,
+            average_postponed,
+            time_per_tag: series
+                .into_iter()
+                .map(|(tag, minutes)| TagSeries {
+                    tag,
+                    total_minutes: minutes.iter().sum(),
+                    minutes,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Beginning of each day from `from` to `to`
+fn days_between<Tz: TimeZone>(
+    from: &DateTime<Tz>,
+    to: &DateTime<Tz>,
+) -> anyhow::Result<Vec<DateTime<Tz>>> {
+    if to < from {
+        return Err(anyhow!("The end of the range is before its start"));
+    }
+    let mut days = Vec::new();
+    let mut day = from.beginning_of_day();
+    while day <= *to {
+        if days.len() == MAX_DAYS {
+            return Err(anyhow!("Statistics are limited to {MAX_DAYS} days"));
+        }
+        let next = day
+            .clone()
+            .checked_add_days(Days::new(1))
+            .ok_or(anyhow!("Date out of range"))?;
+        days.push(day);
+        day = next;
+    }
+    Ok(days)
+}
+
+/// Duration of the occurrence of the event starting on `day`, if there is one
+fn duration_on_day<Tz: TimeZone>(event: &VEvent, day: &DateTime<Tz>) -> Option<TimeDelta> {
+    if event.has_rrule {
+        let (starts_at, ends_at) = event.get_start_end_for_date(day);
+        (starts_at >= *day && starts_at < day.end_of_day()).then(|| ends_at - starts_at)
+    } else {
+        let starts_on = event.starts_at.with_timezone(&day.timezone()).date_naive();
+        (starts_on == day.date_naive()).then(|| event.ends_at - event.starts_at)
+    }
+}
+
+fn split_tags(tag: Option<&str>) -> Vec<Option<String>> {
+    match tag {
+        Some(tag) => tag.split(',').map(|t| Some(t.to_string())).collect(),
+        None => vec![None],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+    use crate::{
+        calendar_items::event_status::EventStatus,
+        models::vtodo::NewVTodo,
+        test_utils::{create_event, create_todo, new_event, new_todo},
+    };
+
+    #[test]
+    fn test_statistics_for_range() {
+        let conn = DbConn::in_memory().unwrap();
+        let todo = new_todo("todo_date.ics");
+        let done = NewVTodo {
+            status: EventStatus::Done,
+            created_at: Some(Utc.with_ymd_and_hms(2024, 6, 3, 9, 0, 0).unwrap()),
+            completed: Some(Utc.with_ymd_and_hms(2024, 6, 4, 10, 0, 0).unwrap()),
+            load: 3,
+            postponed: 2,
+            ..todo.clone()
+        };
+        let pending = NewVTodo {
+            uid: "pending".to_string(),
+            href: Some("/pending".to_string()),
+            status: EventStatus::Todo,
+            created_at: Some(Utc.with_ymd_and_hms(2024, 6, 4, 9, 0, 0).unwrap()),
+            completed: None,
+            postponed: 5,
+            ..todo
+        };
+        for todo in [done, pending] {
+            create_todo(conn.clone(), todo);
+        }
+        // Recurring block from 13:00 to 16:00 every weekday, tagged health
+        create_event(conn.clone(), new_event("basic.ics"));
+
+        let from = chrono_tz::Tz::UTC
+            .with_ymd_and_hms(2024, 6, 3, 0, 0, 0)
+            .unwrap();
+        let to = chrono_tz::Tz::UTC
+            .with_ymd_and_hms(2024, 6, 5, 12, 0, 0)
+            .unwrap();
+        let statistics = block_on(Statistics::for_range(conn, from, to)).unwrap();
+
+        let days = statistics
+            .days
+            .iter()
+            .map(|d| (d.created, d.completed, d.load_done))
+            .collect::<Vec<(i32, i32, i32)>>();
+        assert_eq!(days, vec![(1, 0, 0), (1, 1, 3), (0, 0, 0)]);
+        assert_eq!(statistics.created, 2);
+        assert_eq!(statistics.completed, 1);
+        assert_eq!(statistics.average_postponed, 2.0);
+        assert_eq!(
+            statistics.time_per_tag,
+            vec![TagSeries {
+                tag: Some("health".to_string()),
+                minutes: vec![180, 180, 180],
+                total_minutes: 540,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_range_limits() {
+        let from = Utc.with_ymd_and_hms(2024, 6, 3, 0, 0, 0).unwrap();
+        assert!(days_between(&from, &(from - TimeDelta::days(1))).is_err());
+        assert!(days_between(&from, &(from + TimeDelta::days(400))).is_err());
+        assert_eq!(days_between(&from, &from).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,168 @@
+//! Saved quick-add inputs, used by writing `:name` at the start of the input.
+//!
+//! A template can have placeholders written as `{name}`. After the trigger, values
+//! given as `name=value` fill their placeholder and the rest of the text fills
+//! `{date}`, or is added at the end when the template has no `{date}`. For example
+//! `.e Standup {date} at 9:30-9:45 #team` saved as `standup` turns `:standup tomorrow`
+//! into `.e Standup tomorrow at 9:30-9:45 #team`.
+
+use anyhow::anyhow;
+use regex::Regex;
+
+use crate::{
+    db_conn::DbConn,
+    models::{model_traits::ListAll, named_item::NamedItem},
+};
+
+const NAME_RE: &str = r"^[\w-]+$";
+const TRIGGER_RE: &str = r"^\s*:(?P<name>[\w-]+)(?P<args>.*)$";
+const PLACEHOLDER_RE: &str = r"\{(?P<name>\w+)\}";
+/// Placeholder filled with the text after the trigger
+const DATE_PLACEHOLDER: &str = "date";
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, specta::Type)]
+pub struct Template {
+    pub name: String,
+    pub input: String,
+}
+
+impl NamedItem for Template {
+    const KIND: &'static str = "template";
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl ListAll for Template {
+    /// Sorted by name
+    async fn list_all(conn: DbConn) -> anyhow::Result<Vec<Self>> {
+        Self::list_by_name(conn).await
+    }
+}
+
+impl Template {
+    /// Names are made of letters, digits, `_` and `-`, so they can be written after `:`
+    pub fn validate_name(name: &str) -> anyhow::Result<()> {
+        let re = Regex::new(NAME_RE).expect("To Compile Regex");
+        if !re.is_match(name) {
+            return Err(anyhow!("\"{name}\" can't be used as a template name"));
+        }
+        Ok(())
+    }
+
+    /// Template name and arguments of an input starting with `:name`
+    pub fn trigger(input: &str) -> Option<(String, String)> {
+        let re = Regex::new(TRIGGER_RE).expect("To Compile Regex");
+        let caps = re.captures(input)?;
+        Some((
+            caps.name("name")?.as_str().to_string(),
+            caps.name("args")?.as_str().trim().to_string(),
+        ))
+    }
+
+    /// Input with the placeholders filled from `args`
+    pub fn expand(&self, args: &str) -> anyhow::Result<String> {
+        let mut values = Vec::new();
+        let mut rest = Vec::new();
+        for word in args.split_whitespace() {
+            match word.split_once('=') {
+                Some((name, value)) if !name.is_empty() => values.push((name, value)),
+                _ => rest.push(word),
+            }
+        }
+        let rest = rest.join(" ");
+
+        let re = Regex::new(PLACEHOLDER_RE).expect("To Compile Regex");
+        let mut missing = None;
+        let mut has_date = false;
+        let expanded = re.replace_all(&self.input, |caps: &regex::Captures| {
+            let name = &caps["name"];
+            if name == DATE_PLACEHOLDER {
+                has_date = true;
+                return rest.clone();
+            }
+            match values.iter().find(|(n, _)| *n == name) {
+                Some((_, value)) => value.to_string(),
+                None => {
+                    missing.get_or_insert_with(|| name.to_string());
+                    String::new()
+                }
+            }
+        });
+        if let Some(name) = missing {
+            return Err(anyhow!(
+                "Template {} needs a value for {{{name}}}, given as {name}=value",
+                self.name
+            ));
+        }
+
+        let expanded = if has_date || rest.is_empty() {
+            expanded.to_string()
+        } else {
+            format!("{expanded} {rest}")
+        };
+        Ok(expanded.split_whitespace().collect::<Vec<&str>>().join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+
+    fn standup() -> Template {
+        Template {
+            name: "standup".to_string(),
+            input: ".e Standup {date} at 9:30-9:45 #{team}".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_trigger() {
+        assert_eq!(
+            Template::trigger(":standup tomorrow"),
+            Some(("standup".to_string(), "tomorrow".to_string()))
+        );
+        assert_eq!(Template::trigger("Standup: tomorrow"), None);
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(Template::validate_name("daily-standup_2").is_ok());
+        assert!(Template::validate_name("standup tomorrow").is_err());
+        assert!(Template::validate_name(":standup").is_err());
+        assert!(Template::validate_name("").is_err());
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(
+            standup().expand("tomorrow team=core").unwrap(),
+            ".e Standup tomorrow at 9:30-9:45 #core"
+        );
+        assert!(standup().expand("tomorrow").is_err());
+
+        let call = Template {
+            name: "call".to_string(),
+            input: ".r Call mum".to_string(),
+        };
+        assert_eq!(
+            call.expand("today at 18").unwrap(),
+            ".r Call mum today at 18"
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let conn = DbConn::in_memory().unwrap();
+        block_on(standup().save(conn.clone())).unwrap();
+        assert_eq!(
+            block_on(Template::by_name(conn.clone(), "standup")).unwrap(),
+            Some(standup())
+        );
+        assert!(block_on(Template::delete_by_name(conn.clone(), "standup")).unwrap());
+        assert!(block_on(Template::list_all(conn)).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,308 @@
+//! Status of each occurrence of a recurring todo.
+//!
+//! A recurring [`VTodo`](super::vtodo::VTodo) has a single `status` for the whole series,
+//! so the status of a day is stored here by its uid and the start of its occurrence. The
+//! dated reminders are stored as [`VEvent`](super::vevent::VEvent), so the occurrences of
+//! a recurring event are kept the same way by the uid of the event. It's only kept
+//! locally, the series on the server is left as is.
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use chrono::{DateTime, TimeZone, Utc};
+use diesel::prelude::*;
+use now::DateTimeNow;
+
+use crate::{
+    calendar_items::event_status::EventStatus,
+    db_conn::DbConn,
+    models::{VCmp, journal::JournalEntry, vevent::VEventTrait, vtodo::VTodoTrait},
+    schema::todo_occurrences,
+};
+
+#[derive(
+    Queryable,
+    Selectable,
+    Insertable,
+    Clone,
+    Debug,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    specta::Type,
+)]
+#[diesel(table_name = todo_occurrences)]
+pub struct TodoOccurrence {
+    pub vcmp_uid: String,
+    pub occurrence_start: DateTime<Utc>,
+    pub status: EventStatus,
+    pub completed: Option<DateTime<Utc>>,
+}
+
+impl TodoOccurrence {
+    pub fn new<Tz: TimeZone>(
+        vcmp_uid: String,
+        occurrence_start: DateTime<Utc>,
+        status: EventStatus,
+        date_of_update: DateTime<Tz>,
+    ) -> Self {
+        let completed = matches!(status, EventStatus::Done).then(|| date_of_update.to_utc());
+        Self {
+            vcmp_uid,
+            occurrence_start,
+            status,
+            completed,
+        }
+    }
+
+    /// Sets the status of the occurrence of the recurring `vcmp` on the day of `day`, the
+    /// change is journaled
+    pub async fn set_status<Tz: TimeZone>(
+        conn: DbConn,
+        vcmp: &VCmp,
+        status: EventStatus,
+        day: &DateTime<Tz>,
+        date_of_update: DateTime<Tz>,
+    ) -> anyhow::Result<()> {
+        let vcmp = vcmp.clone();
+        let day = day.fixed_offset();
+        let date_of_update = date_of_update.to_utc();
+        conn.run(move |conn| {
+            conn.transaction::<_, anyhow::Error, _>(|conn| {
+                Self::set_status_in(conn, &vcmp, status, &day, date_of_update)
+            })
+        })
+        .await
+    }
+
+    /// Same as [`TodoOccurrence::set_status`] on a connection that may be in a transaction
+    pub fn set_status_in<Tz: TimeZone>(
+        conn: &mut SqliteConnection,
+        vcmp: &VCmp,
+        status: EventStatus,
+        day: &DateTime<Tz>,
+        date_of_update: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        if !vcmp.has_rrule() {
+            return Err(anyhow!("{} doesn't repeat", vcmp.get_summary()));
+        }
+        let occurrence_start = occurrence_start_on_day(vcmp, day).ok_or(anyhow!(
+            "{} doesn't happen on {}",
+            vcmp.get_summary(),
+            day.date_naive()
+        ))?;
+        let uid = vcmp.get_uid();
+        let before = Self::by_start_in(conn, &uid, occurrence_start)?;
+        let after = Self::new(uid, occurrence_start, status, date_of_update);
+        after.save_in(conn)?;
+        JournalEntry::record_occurrence_in(conn, vcmp.get_id(), before.as_ref(), &after)
+    }
+
+    /// Creates the status of the occurrence or replaces the previous one
+    pub async fn save(&self, conn: DbConn) -> anyhow::Result<()> {
+        let occurrence = self.clone();
+        conn.run(move |conn| occurrence.save_in(conn)).await
+    }
+
+    fn save_in(&self, conn: &mut SqliteConnection) -> QueryResult<()> {
+        use crate::schema::todo_occurrences::dsl as occurrences_dsl;
+
+        diesel::replace_into(occurrences_dsl::todo_occurrences)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Removes the status of the occurrence, it's open again like the ones that never had
+    /// one
+    pub async fn delete(&self, conn: DbConn) -> anyhow::Result<()> {
+        use crate::schema::todo_occurrences::dsl as occurrences_dsl;
+
+        let uid = self.vcmp_uid.clone();
+        let start = self.occurrence_start;
+        conn.run(move |conn| {
+            diesel::delete(
+                occurrences_dsl::todo_occurrences
+                    .filter(occurrences_dsl::vcmp_uid.eq(uid))
+                    .filter(occurrences_dsl::occurrence_start.eq(start)),
+            )
+            .execute(conn)
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Removes the statuses of all the occurrences of the component, on a connection that
+    /// may be in a transaction
+    pub fn delete_for_uid_in(conn: &mut SqliteConnection, vcmp_uid: &str) -> QueryResult<usize> {
+        use crate::schema::todo_occurrences::dsl as occurrences_dsl;
+
+        diesel::delete(
+            occurrences_dsl::todo_occurrences.filter(occurrences_dsl::vcmp_uid.eq(vcmp_uid)),
+        )
+        .execute(conn)
+    }
+
+    /// Removes the statuses left by components that no longer exist, on a connection that
+    /// may be in a transaction
+    pub fn delete_orphans_in(conn: &mut SqliteConnection) -> QueryResult<usize> {
+        use crate::schema::{todo_occurrences::dsl as occurrences_dsl, vevents, vtodos};
+
+        let live_events = vevents::table
+            .filter(vevents::deleted_at.is_null())
+            .select(vevents::uid);
+        let live_todos = vtodos::table
+            .filter(vtodos::deleted_at.is_null())
+            .select(vtodos::uid);
+        diesel::delete(
+            occurrences_dsl::todo_occurrences
+                .filter(occurrences_dsl::vcmp_uid.ne_all(live_events))
+                .filter(occurrences_dsl::vcmp_uid.ne_all(live_todos)),
+        )
+        .execute(conn)
+    }
+
+    fn by_start_in(
+        conn: &mut SqliteConnection,
+        vcmp_uid: &str,
+        occurrence_start: DateTime<Utc>,
+    ) -> QueryResult<Option<Self>> {
+        use crate::schema::todo_occurrences::dsl as occurrences_dsl;
+
+        occurrences_dsl::todo_occurrences
+            .filter(occurrences_dsl::vcmp_uid.eq(vcmp_uid))
+            .filter(occurrences_dsl::occurrence_start.eq(occurrence_start))
+            .select(Self::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// Occurrences of the components starting between `start` and `end`, by uid and start
+    pub async fn for_uids_between(
+        conn: DbConn,
+        vcmp_uids: Vec<String>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> anyhow::Result<HashMap<(String, DateTime<Utc>), TodoOccurrence>> {
+        use crate::schema::todo_occurrences::dsl as occurrences_dsl;
+
+        let occurrences = conn
+            .run(move |conn| {
+                occurrences_dsl::todo_occurrences
+                    .filter(occurrences_dsl::vcmp_uid.eq_any(vcmp_uids))
+                    .filter(occurrences_dsl::occurrence_start.ge(start))
+                    .filter(occurrences_dsl::occurrence_start.lt(end))
+                    .select(Self::as_select())
+                    .load(conn)
+            })
+            .await?;
+        Ok(occurrences
+            .into_iter()
+            .map(|o| ((o.vcmp_uid.clone(), o.occurrence_start), o))
+            .collect())
+    }
+}
+
+/// Start of the occurrence of the recurring `vcmp` on the day of `day`
+fn occurrence_start_on_day<Tz: TimeZone>(vcmp: &VCmp, day: &DateTime<Tz>) -> Option<DateTime<Utc>> {
+    let base = day.beginning_of_day();
+    let (starts_at, _) = match vcmp {
+        VCmp::Event(vevent) => Some(vevent.get_start_end_for_date(&base)),
+        VCmp::Todo(vtodo) => vtodo.get_start_end_for_date(&base),
+    }?;
+    (starts_at > base && starts_at < day.end_of_day()).then(|| starts_at.to_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use tauri::async_runtime::block_on;
+
+    use super::*;
+    use crate::test_utils::{create_event, new_event};
+
+    fn at(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, 8, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_status_by_occurrence() {
+        let conn = DbConn::in_memory().unwrap();
+        let uid = "vitamins".to_string();
+        block_on(
+            TodoOccurrence::new(uid.clone(), at(20), EventStatus::Done, at(20)).save(conn.clone()),
+        )
+        .unwrap();
+        // Reopened the same day
+        block_on(
+            TodoOccurrence::new(uid.clone(), at(20), EventStatus::Todo, at(20)).save(conn.clone()),
+        )
+        .unwrap();
+        block_on(
+            TodoOccurrence::new(uid.clone(), at(21), EventStatus::Done, at(21)).save(conn.clone()),
+        )
+        .unwrap();
+
+        let day = block_on(TodoOccurrence::for_uids_between(
+            conn.clone(),
+            vec![uid.clone()],
+            at(21) - chrono::TimeDelta::hours(8),
+            at(22) - chrono::TimeDelta::hours(8),
+        ))
+        .unwrap();
+        assert_eq!(day.len(), 1);
+        assert_eq!(day[&(uid.clone(), at(21))].completed, Some(at(21)));
+
+        let day = block_on(TodoOccurrence::for_uids_between(
+            conn,
+            vec![uid.clone()],
+            at(20) - chrono::TimeDelta::hours(8),
+            at(21) - chrono::TimeDelta::hours(8),
+        ))
+        .unwrap();
+        assert_eq!(day[&(uid, at(20))].status, EventStatus::Todo);
+    }
+
+    fn uids_with_occurrences(conn: DbConn, uids: Vec<String>) -> Vec<String> {
+        let occurrences =
+            block_on(TodoOccurrence::for_uids_between(conn, uids, at(1), at(31))).unwrap();
+        let mut uids = occurrences
+            .into_keys()
+            .map(|(uid, _)| uid)
+            .collect::<Vec<_>>();
+        uids.sort();
+        uids
+    }
+
+    #[test]
+    fn test_removed_component_loses_its_occurrences() {
+        let conn = DbConn::in_memory().unwrap();
+        let event = create_event(conn.clone(), new_event("basic.ics"));
+        let uid = event.uid.clone();
+        block_on(
+            TodoOccurrence::new(uid.clone(), at(20), EventStatus::Done, at(20)).save(conn.clone()),
+        )
+        .unwrap();
+
+        let vcmp = VCmp::Event(event);
+        block_on(conn.run(move |conn| vcmp.remove_in(conn))).unwrap();
+        assert!(uids_with_occurrences(conn, vec![uid]).is_empty());
+    }
+
+    #[test]
+    fn test_delete_orphans_keeps_live_components() {
+        let conn = DbConn::in_memory().unwrap();
+        let event = create_event(conn.clone(), new_event("basic.ics"));
+        let uids = vec![event.uid.clone(), "gone".to_string()];
+        for uid in &uids {
+            block_on(
+                TodoOccurrence::new(uid.clone(), at(20), EventStatus::Done, at(20))
+                    .save(conn.clone()),
+            )
+            .unwrap();
+        }
+
+        let deleted = block_on(conn.run(TodoOccurrence::delete_orphans_in)).unwrap();
+        assert_eq!(deleted, 1);
+        assert_eq!(uids_with_occurrences(conn, uids), vec![event.uid]);
+    }
+}
@@ -0,0 +1,175 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+
+use crate::{
+    db_conn::DbConn,
+    models::{
+        VCmp, VCmpId,
+        model_traits::{ById, DeleteById, ListAll},
+        todo_occurrence::TodoOccurrence,
+    },
+    schema::trash,
+};
+
+/// A deleted component, kept so it can be restored until the trash is purged
+///
+/// `snapshot` holds the serialized [`VCmp`] and `ical_data` the last calendar
+/// that was, or would have been, sent to the server
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = trash)]
+pub struct TrashEntry {
+    pub id: i32,
+    pub vcmp_kind: String,
+    pub vcmp_id: i32,
+    pub calendar_id: i32,
+    pub summary: String,
+    pub snapshot: String,
+    pub ical_data: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = trash)]
+struct NewTrashEntry {
+    vcmp_kind: String,
+    vcmp_id: i32,
+    calendar_id: i32,
+    summary: String,
+    snapshot: String,
+    ical_data: String,
+    deleted_at: DateTime<Utc>,
+}
+
+impl NewTrashEntry {
+    fn new(vcmp: &VCmp) -> anyhow::Result<Self> {
+        let (vcmp_kind, vcmp_id) = vcmp.get_id().into_parts();
+        Ok(Self {
+            vcmp_kind: vcmp_kind.to_string(),
+            vcmp_id,
+            calendar_id: vcmp.get_calendar_id(),
+            summary: vcmp.get_summary(),
+            snapshot: serde_json::to_string(vcmp)?,
+            ical_data: vcmp.to_push_calendar().to_string(),
+            deleted_at: Utc::now(),
+        })
+    }
+
+    fn insert(self, conn: &mut SqliteConnection) -> QueryResult<usize> {
+        use crate::schema::trash::dsl as trash_dsl;
+
+        diesel::insert_into(trash_dsl::trash)
+            .values(self)
+            .execute(conn)
+    }
+}
+
+impl ById for TrashEntry {
+    async fn by_id(conn: DbConn, id: i32) -> anyhow::Result<Option<Self>> {
+        use crate::schema::trash::dsl as trash_dsl;
+
+        conn.run(move |conn| {
+            trash_dsl::trash
+                .filter(trash_dsl::id.eq(id))
+                .select(Self::as_select())
+                .first::<Self>(conn)
+                .optional()
+                .map_err(anyhow::Error::new)
+        })
+        .await
+    }
+}
+
+impl ListAll for TrashEntry {
+    /// Most recently deleted first
+    async fn list_all(conn: DbConn) -> anyhow::Result<Vec<Self>> {
+        use crate::schema::trash::dsl as trash_dsl;
+
+        let entries = conn
+            .run(move |conn| {
+                trash_dsl::trash
+                    .order(trash_dsl::deleted_at.desc())
+                    .select(Self::as_select())
+                    .load(conn)
+            })
+            .await?;
+        Ok(entries)
+    }
+}
+
+impl DeleteById for TrashEntry {
+    async fn delete_by_id(conn: DbConn, id: i32) -> anyhow::Result<bool> {
+        conn.run(move |conn| Self::delete_by_id_in(conn, id)).await
+    }
+}
+
+impl TrashEntry {
+    /// Keeps a copy of the component, on a connection that may be in a transaction
+    pub fn put_in(conn: &mut SqliteConnection, vcmp: &VCmp) -> anyhow::Result<()> {
+        NewTrashEntry::new(vcmp)?.insert(conn)?;
+        Ok(())
+    }
+
+    /// Same as [`DeleteById::delete_by_id`] on a connection that may be in a transaction
+    pub fn delete_by_id_in(conn: &mut SqliteConnection, id: i32) -> QueryResult<bool> {
+        use crate::schema::trash::dsl as trash_dsl;
+
+        let res = diesel::delete(trash_dsl::trash.filter(trash_dsl::id.eq(id))).execute(conn)?;
+        Ok(res > 0)
+    }
+
+    /// Removes the entries of a component that was brought back some other way
+    pub async fn delete_by_vcmp_id(conn: DbConn, vcmp_id: VCmpId) -> anyhow::Result<()> {
+        use crate::schema::trash::dsl as trash_dsl;
+
+        let (vcmp_kind, vcmp_id) = vcmp_id.into_parts();
+        conn.run(move |conn| {
+            diesel::delete(
+                trash_dsl::trash.filter(
+                    trash_dsl::vcmp_kind
+                        .eq(vcmp_kind)
+                        .and(trash_dsl::vcmp_id.eq(vcmp_id)),
+                ),
+            )
+            .execute(conn)
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes the entries deleted before `deleted_before` and the occurrences left by
+    /// deleted components, returns how many entries were purged
+    pub async fn purge(conn: DbConn, deleted_before: DateTime<Utc>) -> anyhow::Result<usize> {
+        use crate::schema::trash::dsl as trash_dsl;
+
+        let res = conn
+            .run(move |conn| {
+                conn.transaction::<_, diesel::result::Error, _>(|conn| {
+                    let res = diesel::delete(
+                        trash_dsl::trash.filter(trash_dsl::deleted_at.lt(deleted_before)),
+                    )
+                    .execute(conn)?;
+                    TodoOccurrence::delete_orphans_in(conn)?;
+                    Ok(res)
+                })
+            })
+            .await?;
+        Ok(res)
+    }
+
+    pub async fn delete_all(conn: DbConn) -> anyhow::Result<usize> {
+        use crate::schema::trash::dsl as trash_dsl;
+
+        let res = conn
+            .run(move |conn| diesel::delete(trash_dsl::trash).execute(conn))
+            .await?;
+        Ok(res)
+    }
+
+    pub fn get_vcmp_id(&self) -> anyhow::Result<VCmpId> {
+        VCmpId::from_parts(&self.vcmp_kind, self.vcmp_id)
+    }
+
+    pub fn get_vcmp(&self) -> anyhow::Result<VCmp> {
+        Ok(serde_json::from_str(&self.snapshot)?)
+    }
+}
@@ -43,6 +43,8 @@ pub struct VCmpBuilder {
 
     // VTodo specific
     completed: Option<DateTime<Utc>>,
+    parent_uid: Option<String>,
+    created_at: Option<DateTime<Utc>>,
 }
 
 impl VCmpBuilder {
@@ -174,11 +176,27 @@ impl VCmpBuilder {
         self
     }
 
+    pub fn parent_uid(mut self, parent_uid: impl Into<String>) -> Self {
+        self.parent_uid = Some(parent_uid.into());
+        self
+    }
+
     pub fn out_of_sync(mut self, out_of_sync: bool) -> Self {
         self.out_of_sync = Some(out_of_sync);
         self
     }
 
+    /// Forgets the row and what the server knows about the component, so it can be
+    /// created again as a new resource
+    pub fn unsynced(mut self) -> Self {
+        self.id = None;
+        self.href = None;
+        self.ical_data = None;
+        self.etag = None;
+        self.synced_at = None;
+        self
+    }
+
     fn get_href(&self) -> Option<String> {
         if let Some(href) = self.href.clone() {
             Some(href)
@@ -198,7 +216,8 @@ impl VCmpBuilder {
     /// The component type (Event vs Todo) is determined by:
     /// 1. If `event_type` is `EventType::Task`, creates a Todo
     /// 2. If `starts_at` is None, creates a Todo (tasks can have optional dates)
-    /// 3. Otherwise creates an Event
+    /// 3. If it has a `parent_uid`, creates a Todo since only todos have subtasks
+    /// 4. Otherwise creates an Event
     ///
     /// # Errors
     ///
@@ -224,6 +243,7 @@ impl VCmpBuilder {
             == EventType::Task;
 
         let has_no_dates = self.starts_at.is_none();
+        let is_subtask = self.parent_uid.is_some();
         let event_type = self.event_type.unwrap_or(EventType::Event);
         let status = self.status.unwrap_or(EventStatus::Todo);
         let load = self.load.unwrap_or(0);
@@ -234,7 +254,7 @@ impl VCmpBuilder {
         let last_modified = self.last_modified.unwrap_or(now);
 
         log::info!("href {:?}", self.get_href());
-        if is_task || has_no_dates {
+        if is_task || has_no_dates || is_subtask {
             // Build NewVTodo
             Ok(NewVCmp::Todo(NewVTodo {
                 calendar_id,
@@ -260,6 +280,8 @@ impl VCmpBuilder {
                 synced_at: self.synced_at,
                 completed: self.completed,
                 out_of_sync: false,
+                parent_uid: self.parent_uid.clone(),
+                created_at: Some(self.created_at.unwrap_or(now)),
             }))
         } else {
             // Build NewVEvent - requires dates
@@ -322,6 +344,7 @@ impl VCmpBuilder {
             == EventType::Task;
 
         let has_no_dates = self.starts_at.is_none();
+        let is_subtask = self.parent_uid.is_some();
         let event_type = self.event_type.unwrap_or(EventType::Event);
         let status = self.status.unwrap_or(EventStatus::Todo);
         let load = self.load.unwrap_or(0);
@@ -331,7 +354,7 @@ impl VCmpBuilder {
         let now = Utc::now();
         let last_modified = self.last_modified.unwrap_or(now);
 
-        if is_task || has_no_dates {
+        if is_task || has_no_dates || is_subtask {
             // Build VTodo
             Ok(VCmp::Todo(VTodo {
                 id,
@@ -358,6 +381,9 @@ impl VCmpBuilder {
                 synced_at: self.synced_at,
                 completed: self.completed,
                 out_of_sync: false,
+                deleted_at: None,
+                parent_uid: self.parent_uid.clone(),
+                created_at: self.created_at,
             }))
         } else {
             // Build VEvent - requires dates
@@ -392,6 +418,7 @@ impl VCmpBuilder {
                 etag: self.etag.clone(),
                 synced_at: self.synced_at,
                 out_of_sync: false,
+                deleted_at: None,
             }))
         }
     }
@@ -427,6 +454,8 @@ impl From<&VEvent> for VCmpBuilder {
             synced_at: event.synced_at,
             completed: None,
             out_of_sync: Some(event.out_of_sync),
+            parent_uid: None,
+            created_at: None,
         }
     }
 }
@@ -459,6 +488,8 @@ impl From<&VTodo> for VCmpBuilder {
             synced_at: todo.synced_at,
             completed: todo.completed,
             out_of_sync: Some(todo.out_of_sync),
+            parent_uid: todo.parent_uid.clone(),
+            created_at: todo.created_at,
         }
     }
 }
@@ -614,6 +645,7 @@ mod tests {
             etag: Some("etag123".to_string()),
             synced_at: Some(Utc::now()),
             out_of_sync: false,
+            deleted_at: None,
         };
 
         let rebuilt = VCmpBuilder::from(&original_event).build().unwrap();
@@ -658,6 +690,9 @@ mod tests {
             synced_at: Some(now),
             completed: None,
             out_of_sync: false,
+            deleted_at: None,
+            parent_uid: None,
+            created_at: None,
         };
 
         let rebuilt = VCmpBuilder::from(&original_todo).build().unwrap();
@@ -706,7 +741,9 @@ mod tests {
 
     #[test]
     fn test_from_event_upsert_info() {
-        use crate::calendar_items::{event_date::EventDateInfo, event_tags::EventTags};
+        use crate::calendar_items::{
+            event_date::EventDateInfo, event_parent::EventParent, event_tags::EventTags,
+        };
 
         let date_of_input = chrono_tz::America::Buenos_Aires
             .with_ymd_and_hms(2025, 3, 6, 10, 30, 0)
@@ -725,6 +762,7 @@ mod tests {
             load: 3,
             importance: 8,
             tag: EventTags(Some("work".to_string())),
+            parent: EventParent(None),
         };
 
         let builder = VCmpBuilder::from(&upsert_info)
@@ -751,7 +789,7 @@ mod tests {
 
     #[test]
     fn test_from_event_upsert_info_without_dates() {
-        use crate::calendar_items::event_tags::EventTags;
+        use crate::calendar_items::{event_parent::EventParent, event_tags::EventTags};
 
         let upsert_info = EventUpsertInfo {
             summary: "Buy groceries".to_string(),
@@ -763,6 +801,7 @@ mod tests {
             load: 1,
             importance: 3,
             tag: EventTags(Some("personal".to_string())),
+            parent: EventParent(None),
         };
 
         let builder = VCmpBuilder::from(&upsert_info)
@@ -1,11 +1,12 @@
 use anyhow::anyhow;
 use now::DateTimeNow;
 use std::str::FromStr;
-use tauri::async_runtime::spawn_blocking;
 
 use crate::{
     calendar_items::{
-        component_props::{ComponentProps, GeneralComponentProps},
+        component_props::{
+            ComponentProps, GeneralComponentProps, add_priority_property, add_tag_properties,
+        },
         date_from_calendar_to_utc,
         event_date::EventDateInfo,
         event_status::EventStatus,
@@ -14,6 +15,7 @@ use crate::{
         event_upsert::EventUpsertInfo,
         input_traits::ToUserInput,
         parse_duration,
+        timezone::CalendarTimezones,
     },
     db_conn::DbConn,
     impl_ical_parseable,
@@ -36,7 +38,15 @@ use log::warn;
 use super::IcalParseableTrait;
 
 #[derive(
-    Queryable, Selectable, Insertable, AsChangeset, Debug, Clone, serde::Serialize, specta::Type,
+    Queryable,
+    Selectable,
+    Insertable,
+    AsChangeset,
+    Debug,
+    Clone,
+    serde::Serialize,
+    serde::Deserialize,
+    specta::Type,
 )]
 #[diesel(table_name = vevents)]
 pub struct VEvent {
@@ -63,15 +73,16 @@ pub struct VEvent {
     pub etag: Option<String>,
     pub synced_at: Option<chrono::DateTime<Utc>>,
     pub out_of_sync: bool,
+    /// Set when deleted locally, the row is kept until the delete reaches the server
+    #[serde(default)]
+    pub deleted_at: Option<chrono::DateTime<Utc>>,
 }
 
 impl ById for VEvent {
     async fn by_id(conn: DbConn, id: i32) -> anyhow::Result<Option<Self>> {
         use crate::schema::vevents::dsl as event_dsl;
 
-        spawn_blocking(move || {
-            let conn = &mut *conn.0.lock().unwrap();
-
+        conn.run(move |conn| {
             event_dsl::vevents
                 .filter(event_dsl::id.eq(id))
                 .select(Self::as_select())
@@ -79,7 +90,7 @@ impl ById for VEvent {
                 .optional()
                 .map_err(anyhow::Error::new)
         })
-        .await?
+        .await
     }
 }
 
@@ -88,8 +99,7 @@ impl ByHref for VEvent {
         use crate::schema::vevents::dsl as event_dsl;
         let href_str = href.to_string();
 
-        spawn_blocking(move || {
-            let c = &mut *conn.0.lock().unwrap();
+        conn.run(move |c| {
             event_dsl::vevents
                 .filter(event_dsl::href.eq(href_str))
                 .select(Self::as_select())
@@ -97,7 +107,7 @@ impl ByHref for VEvent {
                 .optional()
                 .map_err(anyhow::Error::new)
         })
-        .await?
+        .await
     }
 }
 
@@ -105,13 +115,13 @@ impl DeleteById for VEvent {
     async fn delete_by_id(conn: DbConn, vevent_id: i32) -> anyhow::Result<bool> {
         use crate::schema::vevents::dsl as event_dsl;
 
-        let res = spawn_blocking(move || {
-            let c = &mut *conn.0.lock().unwrap();
-            delete(event_dsl::vevents)
-                .filter(event_dsl::id.eq(vevent_id))
-                .execute(&mut *c)
-        })
-        .await??;
+        let res = conn
+            .run(move |c| {
+                delete(event_dsl::vevents)
+                    .filter(event_dsl::id.eq(vevent_id))
+                    .execute(&mut *c)
+            })
+            .await?;
         Ok(res > 0)
     }
 }
@@ -119,15 +129,14 @@ impl DeleteById for VEvent {
 impl DeleteAllByCalendar for VEvent {
     async fn delete_all_by_calendar(conn: DbConn, calendar_id: i32) -> anyhow::Result<()> {
         use crate::schema::vevents::dsl as event_dsl;
-        spawn_blocking(move || {
-            let conn = &mut *conn.0.lock().unwrap();
-
+        conn.run(move |conn| {
             // Clean the events from that calendar
             delete(event_dsl::vevents)
                 .filter(event_dsl::calendar_id.eq(calendar_id))
+                .filter(event_dsl::deleted_at.is_null())
                 .execute(conn)
         })
-        .await??;
+        .await?;
         Ok(())
     }
 }
@@ -140,19 +149,19 @@ impl ListForDayOrRecurring for VEvent {
         use crate::schema::vevents::dsl as event_dsl;
         let start = date.beginning_of_day();
         let end = date.end_of_day();
-        let events = spawn_blocking(move || {
-            let conn = &mut *conn.0.lock().unwrap();
-
-            event_dsl::vevents
-                .filter(
-                    event_dsl::has_rrule.eq(true).or(event_dsl::starts_at
-                        .ge(start)
-                        .and(event_dsl::ends_at.le(end))),
-                )
-                .select(VEvent::as_select())
-                .load(conn)
-        })
-        .await??;
+        let events = conn
+            .run(move |conn| {
+                event_dsl::vevents
+                    .filter(event_dsl::deleted_at.is_null())
+                    .filter(
+                        event_dsl::has_rrule.eq(true).or(event_dsl::starts_at
+                            .ge(start)
+                            .and(event_dsl::ends_at.le(end))),
+                    )
+                    .select(VEvent::as_select())
+                    .load(conn)
+            })
+            .await?;
         Ok(events)
     }
 }
@@ -164,19 +173,19 @@ impl CalendarAndSyncStatus for VEvent {
     ) -> anyhow::Result<Vec<Self>> {
         use crate::schema::vevents::dsl as event_dsl;
 
-        let todos = spawn_blocking(move || {
-            let conn = &mut *conn.0.lock().unwrap();
-
-            event_dsl::vevents
-                .filter(
-                    event_dsl::calendar_id
-                        .eq(calendar_id)
-                        .and(event_dsl::synced_at.is_null()),
-                )
-                .select(VEvent::as_select())
-                .load(conn)
-        })
-        .await??;
+        let todos = conn
+            .run(move |conn| {
+                event_dsl::vevents
+                    .filter(event_dsl::deleted_at.is_null())
+                    .filter(
+                        event_dsl::calendar_id
+                            .eq(calendar_id)
+                            .and(event_dsl::synced_at.is_null()),
+                    )
+                    .select(VEvent::as_select())
+                    .load(conn)
+            })
+            .await?;
         Ok(todos)
     }
 
@@ -186,19 +195,40 @@ impl CalendarAndSyncStatus for VEvent {
     ) -> anyhow::Result<Vec<Self>> {
         use crate::schema::vevents::dsl as event_dsl;
 
-        let events = spawn_blocking(move || {
-            let conn = &mut *conn.0.lock().unwrap();
+        let events = conn
+            .run(move |conn| {
+                event_dsl::vevents
+                    .filter(event_dsl::deleted_at.is_null())
+                    .filter(
+                        event_dsl::calendar_id
+                            .eq(calendar_id)
+                            .and(event_dsl::out_of_sync.eq(true)),
+                    )
+                    .select(VEvent::as_select())
+                    .load(conn)
+            })
+            .await?;
+        Ok(events)
+    }
+
+    async fn by_calendar_id_and_deleted(
+        conn: DbConn,
+        calendar_id: i32,
+    ) -> anyhow::Result<Vec<Self>> {
+        use crate::schema::vevents::dsl as event_dsl;
 
-            event_dsl::vevents
-                .filter(
-                    event_dsl::calendar_id
-                        .eq(calendar_id)
-                        .and(event_dsl::out_of_sync.eq(true)),
-                )
-                .select(VEvent::as_select())
-                .load(conn)
-        })
-        .await??;
+        let events = conn
+            .run(move |conn| {
+                event_dsl::vevents
+                    .filter(
+                        event_dsl::calendar_id
+                            .eq(calendar_id)
+                            .and(event_dsl::deleted_at.is_not_null()),
+                    )
+                    .select(VEvent::as_select())
+                    .load(conn)
+            })
+            .await?;
         Ok(events)
     }
 }
@@ -213,8 +243,7 @@ impl SetSyncedAt for VEvent {
         use crate::schema::vevents::dsl as vevent_dsl;
 
         let id = self.id;
-        spawn_blocking(move || {
-            let conn = &mut *conn.0.lock().unwrap();
+        conn.run(move |conn| {
             update(vevent_dsl::vevents)
                 .filter(vevent_dsl::id.eq(id))
                 .set((
@@ -224,7 +253,7 @@ impl SetSyncedAt for VEvent {
                 ))
                 .execute(conn)
         })
-        .await??;
+        .await?;
         Ok(())
     }
 }
@@ -239,26 +268,104 @@ impl VEvent {
         }
     }
 
+    /// `Block` and `Event` items starting between `start` and `end`, plus the recurring ones
+    pub async fn list_timed_between_or_recurring(
+        conn: DbConn,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<VEvent>> {
+        use crate::schema::vevents::dsl as event_dsl;
+
+        let events = conn
+            .run(move |conn| {
+                event_dsl::vevents
+                    .filter(event_dsl::deleted_at.is_null())
+                    .filter(event_dsl::event_type.eq_any([EventType::Block, EventType::Event]))
+                    .filter(
+                        event_dsl::has_rrule.eq(true).or(event_dsl::starts_at
+                            .ge(start)
+                            .and(event_dsl::starts_at.le(end))),
+                    )
+                    .select(VEvent::as_select())
+                    .load(conn)
+            })
+            .await?;
+        Ok(events)
+    }
+
+    /// Recurring events of the given types, sorted by summary
+    pub async fn list_recurring_of_types(
+        conn: DbConn,
+        event_types: Vec<EventType>,
+    ) -> anyhow::Result<Vec<VEvent>> {
+        use crate::schema::vevents::dsl as event_dsl;
+
+        let events = conn
+            .run(move |conn| {
+                event_dsl::vevents
+                    .filter(event_dsl::deleted_at.is_null())
+                    .filter(event_dsl::has_rrule.eq(true))
+                    .filter(event_dsl::event_type.eq_any(event_types))
+                    .order(event_dsl::summary.asc())
+                    .select(VEvent::as_select())
+                    .load(conn)
+            })
+            .await?;
+        Ok(events)
+    }
+
+    /// Events of the calendars overlapping `start` to `end`, plus their recurring ones
+    pub async fn list_in_calendars_between_or_recurring(
+        conn: DbConn,
+        calendar_ids: Vec<i32>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<VEvent>> {
+        use crate::schema::vevents::dsl as event_dsl;
+
+        let events = conn
+            .run(move |conn| {
+                event_dsl::vevents
+                    .filter(event_dsl::deleted_at.is_null())
+                    .filter(event_dsl::calendar_id.eq_any(calendar_ids))
+                    .filter(
+                        event_dsl::has_rrule.eq(true).or(event_dsl::starts_at
+                            .lt(end)
+                            .and(event_dsl::ends_at.gt(start))),
+                    )
+                    .select(VEvent::as_select())
+                    .load(conn)
+            })
+            .await?;
+        Ok(events)
+    }
+
     pub async fn update_status_by_id(
         conn: DbConn,
         vevent_id: i32,
         status: EventStatus,
     ) -> anyhow::Result<Option<VEvent>> {
+        conn.run(move |conn| Self::update_status_by_id_in(conn, vevent_id, status))
+            .await
+    }
+
+    /// Same as [`VEvent::update_status_by_id`] on a connection that may be in a transaction
+    pub fn update_status_by_id_in(
+        conn: &mut SqliteConnection,
+        vevent_id: i32,
+        status: EventStatus,
+    ) -> QueryResult<Option<VEvent>> {
         use crate::schema::vevents::dsl as vevents_dsl;
-        let conn_2 = conn.clone();
-        spawn_blocking(move || {
-            let conn = &mut *conn_2.0.lock().unwrap();
 
-            diesel::update(vevents_dsl::vevents)
-                .filter(vevents_dsl::id.eq(vevent_id))
-                .set((
-                    vevents_dsl::status.eq(status),
-                    vevents_dsl::out_of_sync.eq(true),
-                ))
-                .execute(conn)
-        })
-        .await??;
-        Self::by_id(conn, vevent_id).await
+        diesel::update(vevents_dsl::vevents)
+            .filter(vevents_dsl::id.eq(vevent_id))
+            .set((
+                vevents_dsl::status.eq(status),
+                vevents_dsl::out_of_sync.eq(true),
+            ))
+            .returning(VEvent::as_returning())
+            .get_result(conn)
+            .optional()
     }
 
     pub fn apply_upsert<Tz: TimeZone>(
@@ -273,7 +380,6 @@ impl VEvent {
         event.ends_at = date_info.get_end_or_default(extracted.event_type).to_utc();
         event.event_type = extracted.event_type;
         event.status = extracted.status;
-        event.postponed = extracted.postponed;
         event.urgency = extracted.urgency;
         event.load = extracted.load;
         event.importance = extracted.importance;
@@ -300,6 +406,7 @@ impl From<VEvent> for CalendarComponent {
             .add_property(ComponentProps::Load, value.load.to_string())
             .add_property(ComponentProps::Urgency, value.urgency.to_string())
             .add_property(ComponentProps::Importance, value.importance.to_string())
+            .add_property(ComponentProps::Postponed, value.postponed.to_string())
             .done();
 
         if let Some(rule) = value
@@ -309,9 +416,14 @@ impl From<VEvent> for CalendarComponent {
             event.add_property(ComponentProps::RRule, rule);
         }
 
+        add_priority_property(&mut event, value.urgency, value.importance);
+
+        if let Some(description) = &value.description {
+            event.description(description);
+        }
+
         if let Some(tag) = value.tag {
-            event.add_property(ComponentProps::Tag, tag.to_lowercase());
-            event.add_property(ComponentProps::Categories, tag.to_uppercase());
+            add_tag_properties(&mut event, &tag);
         }
 
         event.into()
@@ -390,28 +502,27 @@ macro_rules! impl_event_trait {
             async fn create(&self, conn: DbConn) -> anyhow::Result<VEvent> {
                 use crate::schema::vevents::dsl as events_dsl;
                 let event = self.clone();
-                let val = spawn_blocking(move || {
-                    let conn = &mut *conn.0.lock().unwrap();
-                    insert_into(events_dsl::vevents)
-                        .values(event)
-                        .returning(VEvent::as_returning())
-                        .get_result(conn)
-                })
-                .await??;
+                let val = conn
+                    .run(move |conn| {
+                        insert_into(events_dsl::vevents)
+                            .values(event)
+                            .returning(VEvent::as_returning())
+                            .get_result(conn)
+                    })
+                    .await?;
                 Ok(val)
             }
             async fn update(&self, conn: DbConn, id: i32) -> anyhow::Result<VEvent> {
                 use crate::schema::vevents::dsl as events_dsl;
                 let event = self.clone();
-                let val = spawn_blocking(move || {
-                    let conn = &mut *conn.0.lock().unwrap();
-
-                    update(events_dsl::vevents.filter(events_dsl::id.eq(id)))
-                        .set(event)
-                        .returning(VEvent::as_returning())
-                        .get_result(conn)
-                })
-                .await??;
+                let val = conn
+                    .run(move |conn| {
+                        update(events_dsl::vevents.filter(events_dsl::id.eq(id)))
+                            .set(event)
+                            .returning(VEvent::as_returning())
+                            .get_result(conn)
+                    })
+                    .await?;
                 Ok(val)
             }
             async fn upsert_by_href(&self, conn: DbConn) -> anyhow::Result<VEvent> {
@@ -477,7 +588,7 @@ impl TryFrom<NewVEvent> for icalendar::Event {
         vevent.append_property(icalendar::Property::from(new_event.status));
         vevent.append_property(icalendar::Property::from(new_event.event_type));
         if let Some(tag) = new_event.tag {
-            vevent.append_property(icalendar::Property::new(ComponentProps::Tag, tag));
+            add_tag_properties(&mut vevent, &tag);
         }
 
         Ok(vevent)
@@ -486,15 +597,8 @@ impl TryFrom<NewVEvent> for icalendar::Event {
 
 fn parse_event_start_and_end(
     calendar: &icalendar::Calendar,
+    timezones: &CalendarTimezones,
 ) -> anyhow::Result<(chrono::DateTime<Utc>, chrono::DateTime<Utc>)> {
-    let timezone = calendar
-        .get_timezone()
-        .and_then(|tzid| {
-            let tz: Option<chrono_tz::Tz> = tzid.parse().ok();
-            tz
-        })
-        .unwrap_or(chrono_tz::UTC);
-
     let event = calendar
         .components
         .iter()
@@ -508,11 +612,11 @@ fn parse_event_start_and_end(
 
     let start = event
         .get_start()
-        .and_then(|s| date_from_calendar_to_utc(s, timezone))
+        .and_then(|s| date_from_calendar_to_utc(s, timezones))
         .ok_or(anyhow!("Missing start date {calendar:?}"))?;
 
     let end = if let Some(end) = event.get_end() {
-        date_from_calendar_to_utc(end, timezone)
+        date_from_calendar_to_utc(end, timezones)
     } else {
         event
             .property_value(ComponentProps::Duration.as_ref())
@@ -568,9 +672,13 @@ impl FromResource for NewVEvent {
             load,
             postponed,
             last_modified,
+            ..
         } = GeneralComponentProps::try_from(*first_event)?;
 
-        let (starts_at, ends_at) = parse_event_start_and_end(&calendar_item)?;
+        let (starts_at, ends_at) = parse_event_start_and_end(
+            &calendar_item,
+            &CalendarTimezones::from_ical_data(ical_data),
+        )?;
 
         let new_event = NewVEvent {
             calendar_id: cal_id,
@@ -609,9 +717,9 @@ impl FromResource for NewVEvent {
 mod tests {
 
     use super::*;
+    use crate::test_utils::load_file;
     use chrono::{NaiveDate, TimeZone};
     use rrule::Tz;
-    use std::{fs, path::PathBuf};
 
     macro_rules! assert_property {
         ($vevent:ident, $prop:expr, $expected:expr) => {
@@ -622,9 +730,7 @@ mod tests {
 
     #[test]
     fn gets_the_value() {
-        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        d.push("./fixtures/basic.ics");
-        let ics = fs::read_to_string(d).expect("To Load file");
+        let ics = load_file("./fixtures/basic.ics");
         let event = NewVEvent::from_ical_data(1, "/hello", ics.as_str(), "")
             .unwrap()
             .unwrap();
@@ -654,9 +760,7 @@ mod tests {
 
     #[test]
     fn test_uses_correct_timezone_dst() {
-        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        d.push("./fixtures/with_timezone.ics");
-        let ics = fs::read_to_string(d).expect("To Load file");
+        let ics = load_file("./fixtures/with_timezone.ics");
 
         let event = NewVEvent::from_ical_data(1, "/hello", ics.as_str(), "")
             .unwrap()
@@ -680,9 +784,7 @@ mod tests {
 
     #[test]
     fn test_correct_timezone() {
-        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        d.push("./fixtures/with_timezone.ics");
-        let ics = fs::read_to_string(d).expect("To Load file");
+        let ics = load_file("./fixtures/with_timezone.ics");
         let event = NewVEvent::from_ical_data(1, "/cal", ics.as_str(), "");
 
         let event = event.unwrap().unwrap();
@@ -699,9 +801,7 @@ mod tests {
 
     #[test]
     fn test_to_vevent() {
-        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        d.push("./fixtures/with_timezone.ics");
-        let ics = fs::read_to_string(d).expect("To Load file");
+        let ics = load_file("./fixtures/with_timezone.ics");
         let event = NewVEvent::from_ical_data(1, "/cal", ics.as_str(), "");
 
         let event = event.unwrap().unwrap();
@@ -729,4 +829,69 @@ mod tests {
             event.event_type.as_ref()
         );
     }
+
+    #[test]
+    fn test_windows_timezone() {
+        let ics = load_file("./fixtures/outlook_windows_timezone.ics");
+        let event = NewVEvent::from_ical_data(1, "/cal", ics.as_str(), "")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            event.starts_at,
+            chrono::Utc.with_ymd_and_hms(2025, 3, 10, 13, 0, 0).unwrap()
+        );
+        assert_eq!(
+            event.ends_at,
+            chrono::Utc.with_ymd_and_hms(2025, 3, 10, 14, 0, 0).unwrap()
+        );
+        assert!(event.has_rrule);
+        let recurrence = event
+            .get_next_recurrence_from_date(
+                &chrono::Utc.with_ymd_and_hms(2025, 3, 11, 0, 0, 0).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(
+            recurrence,
+            chrono::Utc.with_ymd_and_hms(2025, 3, 17, 13, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_custom_vtimezone() {
+        let ics = load_file("./fixtures/exchange_custom_timezone.ics");
+        let event = NewVEvent::from_ical_data(1, "/cal", ics.as_str(), "")
+            .unwrap()
+            .unwrap();
+
+        // Summer time of the custom definition is UTC+2
+        assert_eq!(
+            event.starts_at,
+            chrono::Utc.with_ymd_and_hms(2025, 7, 1, 7, 0, 0).unwrap()
+        );
+        assert_eq!(
+            event.ends_at,
+            chrono::Utc.with_ymd_and_hms(2025, 7, 1, 7, 15, 0).unwrap()
+        );
+        assert!(event.has_rrule);
+        let recurrence = event
+            .get_next_recurrence_from_date(
+                &chrono::Utc.with_ymd_and_hms(2025, 7, 2, 0, 0, 0).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(
+            recurrence,
+            chrono::Utc.with_ymd_and_hms(2025, 7, 8, 7, 0, 0).unwrap()
+        );
+        // Still at 09:00 local time after the end of the summer time
+        let recurrence = event
+            .get_next_recurrence_from_date(
+                &chrono::Utc.with_ymd_and_hms(2025, 11, 1, 0, 0, 0).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(
+            recurrence,
+            chrono::Utc.with_ymd_and_hms(2025, 11, 4, 8, 0, 0).unwrap()
+        );
+    }
 }
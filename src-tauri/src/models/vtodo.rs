@@ -1,6 +1,9 @@
 use crate::{
     calendar_items::{
-        component_props::{ComponentProps, GeneralComponentProps},
+        component_props::{
+            ComponentProps, GeneralComponentProps, add_parent_property, add_priority_property,
+            add_tag_properties,
+        },
         date_from_calendar_to_utc,
         event_date::EventDateInfo,
         event_status::EventStatus,
@@ -9,6 +12,7 @@ use crate::{
         event_upsert::EventUpsertInfo,
         input_traits::ToUserInput,
         parse_duration,
+        timezone::CalendarTimezones,
     },
     commands::extended_todo::UnscheduledTodo,
     db_conn::DbConn,
@@ -29,12 +33,20 @@ use diesel::{delete, insert_into, prelude::*, update};
 use icalendar::{CalendarComponent, Component, TodoStatus};
 use libdav::FetchedResource;
 use now::DateTimeNow;
-use tauri::async_runtime::spawn_blocking;
+use std::collections::HashMap;
 
 use super::IcalParseableTrait;
 
 #[derive(
-    Queryable, Selectable, Insertable, AsChangeset, Debug, serde::Serialize, specta::Type, Clone,
+    Queryable,
+    Selectable,
+    Insertable,
+    AsChangeset,
+    Debug,
+    serde::Serialize,
+    serde::Deserialize,
+    specta::Type,
+    Clone,
 )]
 #[diesel(table_name = vtodos)]
 #[diesel(treat_none_as_null = true)]
@@ -63,15 +75,22 @@ pub struct VTodo {
     pub synced_at: Option<chrono::DateTime<Utc>>,
     pub completed: Option<chrono::DateTime<Utc>>,
     pub out_of_sync: bool,
+    /// Set when deleted locally, the row is kept until the delete reaches the server
+    #[serde(default)]
+    pub deleted_at: Option<chrono::DateTime<Utc>>,
+    /// Uid of the todo this one is a subtask of, from `RELATED-TO`
+    #[serde(default)]
+    pub parent_uid: Option<String>,
+    /// When the todo was created, from `CREATED`
+    #[serde(default)]
+    pub created_at: Option<chrono::DateTime<Utc>>,
 }
 
 impl ById for VTodo {
     async fn by_id(conn: DbConn, id: i32) -> anyhow::Result<Option<Self>> {
         use crate::schema::vtodos::dsl as todo_dsl;
 
-        spawn_blocking(move || {
-            let conn = &mut *conn.0.lock().unwrap();
-
+        conn.run(move |conn| {
             todo_dsl::vtodos
                 .filter(todo_dsl::id.eq(id))
                 .select(Self::as_select())
@@ -79,7 +98,7 @@ impl ById for VTodo {
                 .optional()
                 .map_err(anyhow::Error::new)
         })
-        .await?
+        .await
     }
 }
 
@@ -88,9 +107,7 @@ impl ByHref for VTodo {
         use crate::schema::vtodos::dsl as todo_dsl;
         let href_str = href.to_string();
 
-        spawn_blocking(move || {
-            let conn = &mut *conn.0.lock().unwrap();
-
+        conn.run(move |conn| {
             todo_dsl::vtodos
                 .filter(todo_dsl::href.eq(href_str))
                 .select(Self::as_select())
@@ -98,21 +115,20 @@ impl ByHref for VTodo {
                 .optional()
                 .map_err(anyhow::Error::new)
         })
-        .await?
+        .await
     }
 }
 
 impl DeleteById for VTodo {
     async fn delete_by_id(conn: DbConn, vevent_id: i32) -> anyhow::Result<bool> {
         use crate::schema::vtodos::dsl as todo_dsl;
-        let res = spawn_blocking(move || {
-            let conn = &mut *conn.0.lock().unwrap();
-
-            delete(todo_dsl::vtodos)
-                .filter(todo_dsl::id.eq(vevent_id))
-                .execute(conn)
-        })
-        .await??;
+        let res = conn
+            .run(move |conn| {
+                delete(todo_dsl::vtodos)
+                    .filter(todo_dsl::id.eq(vevent_id))
+                    .execute(conn)
+            })
+            .await?;
         Ok(res > 0)
     }
 }
@@ -120,14 +136,13 @@ impl DeleteById for VTodo {
 impl DeleteAllByCalendar for VTodo {
     async fn delete_all_by_calendar(conn: DbConn, calendar_id: i32) -> anyhow::Result<()> {
         use crate::schema::vtodos::dsl as todo_dsl;
-        spawn_blocking(move || {
-            let conn = &mut *conn.0.lock().unwrap();
-
+        conn.run(move |conn| {
             delete(todo_dsl::vtodos)
                 .filter(todo_dsl::calendar_id.eq(calendar_id))
+                .filter(todo_dsl::deleted_at.is_null())
                 .execute(conn)
         })
-        .await??;
+        .await?;
         Ok(())
     }
 }
@@ -140,19 +155,19 @@ impl ListForDayOrRecurring for VTodo {
         use crate::schema::vtodos::dsl as todos_dsl;
         let start = date.beginning_of_day();
         let end = date.end_of_day();
-        let todos = spawn_blocking(move || {
-            let conn = &mut *conn.0.lock().unwrap();
-
-            todos_dsl::vtodos
-                .filter(
-                    todos_dsl::has_rrule.eq(true).or(todos_dsl::starts_at
-                        .ge(start)
-                        .and(todos_dsl::ends_at.le(end))),
-                )
-                .select(VTodo::as_select())
-                .load(conn)
-        })
-        .await??;
+        let todos = conn
+            .run(move |conn| {
+                todos_dsl::vtodos
+                    .filter(todos_dsl::deleted_at.is_null())
+                    .filter(
+                        todos_dsl::has_rrule.eq(true).or(todos_dsl::starts_at
+                            .ge(start)
+                            .and(todos_dsl::ends_at.le(end))),
+                    )
+                    .select(VTodo::as_select())
+                    .load(conn)
+            })
+            .await?;
         Ok(todos)
     }
 }
@@ -164,19 +179,19 @@ impl CalendarAndSyncStatus for VTodo {
     ) -> anyhow::Result<Vec<Self>> {
         use crate::schema::vtodos::dsl as todos_dsl;
 
-        let todos = spawn_blocking(move || {
-            let conn = &mut *conn.0.lock().unwrap();
-
-            todos_dsl::vtodos
-                .filter(
-                    todos_dsl::calendar_id
-                        .eq(calendar_id)
-                        .and(todos_dsl::synced_at.is_null()),
-                )
-                .select(VTodo::as_select())
-                .load(conn)
-        })
-        .await??;
+        let todos = conn
+            .run(move |conn| {
+                todos_dsl::vtodos
+                    .filter(todos_dsl::deleted_at.is_null())
+                    .filter(
+                        todos_dsl::calendar_id
+                            .eq(calendar_id)
+                            .and(todos_dsl::synced_at.is_null()),
+                    )
+                    .select(VTodo::as_select())
+                    .load(conn)
+            })
+            .await?;
         Ok(todos)
     }
 
@@ -186,19 +201,40 @@ impl CalendarAndSyncStatus for VTodo {
     ) -> anyhow::Result<Vec<Self>> {
         use crate::schema::vtodos::dsl as todos_dsl;
 
-        let todos = spawn_blocking(move || {
-            let conn = &mut *conn.0.lock().unwrap();
-
-            todos_dsl::vtodos
-                .filter(
-                    todos_dsl::calendar_id
-                        .eq(calendar_id)
-                        .and(todos_dsl::out_of_sync.eq(true)),
-                )
-                .select(VTodo::as_select())
-                .load(conn)
-        })
-        .await??;
+        let todos = conn
+            .run(move |conn| {
+                todos_dsl::vtodos
+                    .filter(todos_dsl::deleted_at.is_null())
+                    .filter(
+                        todos_dsl::calendar_id
+                            .eq(calendar_id)
+                            .and(todos_dsl::out_of_sync.eq(true)),
+                    )
+                    .select(VTodo::as_select())
+                    .load(conn)
+            })
+            .await?;
+        Ok(todos)
+    }
+
+    async fn by_calendar_id_and_deleted(
+        conn: DbConn,
+        calendar_id: i32,
+    ) -> anyhow::Result<Vec<Self>> {
+        use crate::schema::vtodos::dsl as todos_dsl;
+
+        let todos = conn
+            .run(move |conn| {
+                todos_dsl::vtodos
+                    .filter(
+                        todos_dsl::calendar_id
+                            .eq(calendar_id)
+                            .and(todos_dsl::deleted_at.is_not_null()),
+                    )
+                    .select(VTodo::as_select())
+                    .load(conn)
+            })
+            .await?;
         Ok(todos)
     }
 }
@@ -213,8 +249,7 @@ impl SetSyncedAt for VTodo {
         use crate::schema::vtodos::dsl as todos_dsl;
 
         let id = self.id;
-        spawn_blocking(move || {
-            let conn = &mut *conn.0.lock().unwrap();
+        conn.run(move |conn| {
             update(todos_dsl::vtodos)
                 .filter(todos_dsl::id.eq(id))
                 .set((
@@ -224,11 +259,20 @@ impl SetSyncedAt for VTodo {
                 ))
                 .execute(conn)
         })
-        .await??;
+        .await?;
         Ok(())
     }
 }
 
+/// Progress of the subtasks of a todo
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, specta::Type)]
+pub struct SubtaskProgress {
+    pub done: i32,
+    pub total: i32,
+    /// Sum of the `load` of the subtasks
+    pub load: i32,
+}
+
 impl VTodo {
     /// Will try to find it, if it doesn't find it it will do nothing
     /// will return true if the vevent has been found and deleted
@@ -245,36 +289,200 @@ impl VTodo {
     ) -> anyhow::Result<Vec<UnscheduledTodo>> {
         use crate::schema::vtodos::dsl as todo_dsl;
 
-        let todos = spawn_blocking(move || {
-            let conn = &mut *conn.0.lock().unwrap();
-            let now = Utc::now() - TimeDelta::minutes(30);
+        let todos = conn
+            .run(move |conn| {
+                let now = Utc::now() - TimeDelta::minutes(30);
+
+                if include_done {
+                    todo_dsl::vtodos
+                        .filter(todo_dsl::deleted_at.is_null())
+                        .filter(todo_dsl::starts_at.is_null())
+                        .select(VTodo::as_select())
+                        .load(conn)
+                } else {
+                    todo_dsl::vtodos
+                        .filter(todo_dsl::deleted_at.is_null())
+                        .filter(
+                            todo_dsl::starts_at.is_null().and(
+                                todo_dsl::status
+                                    .is_not(EventStatus::Done)
+                                    .or(todo_dsl::status
+                                        .is(EventStatus::Done)
+                                        .and(todo_dsl::completed.gt(now))),
+                            ),
+                        )
+                        .select(VTodo::as_select())
+                        .load(conn)
+                }
+            })
+            .await?;
 
-            if include_done {
+        Ok(todos
+            .iter()
+            .map(|t| UnscheduledTodo::on_day(t, &Utc::now()))
+            .collect::<Vec<UnscheduledTodo>>())
+    }
+
+    pub async fn list_subtasks(conn: DbConn, parent_uid: String) -> anyhow::Result<Vec<VTodo>> {
+        use crate::schema::vtodos::dsl as todo_dsl;
+
+        let todos = conn
+            .run(move |conn| {
+                todo_dsl::vtodos
+                    .filter(todo_dsl::deleted_at.is_null())
+                    .filter(todo_dsl::parent_uid.eq(parent_uid))
+                    .order(todo_dsl::id.asc())
+                    .select(VTodo::as_select())
+                    .load(conn)
+            })
+            .await?;
+        Ok(todos)
+    }
+
+    /// Uids of the parent of the todo, of the parent of that parent and so on up to a todo
+    /// that isn't a subtask
+    pub async fn ancestor_uids(conn: DbConn, uid: String) -> anyhow::Result<Vec<String>> {
+        use crate::schema::vtodos::dsl as todo_dsl;
+
+        let ancestors = conn
+            .run(move |conn| {
+                let mut ancestors = Vec::<String>::new();
+                let mut current = uid;
+                loop {
+                    let parent_uid = todo_dsl::vtodos
+                        .filter(todo_dsl::deleted_at.is_null())
+                        .filter(todo_dsl::uid.eq(&current))
+                        .select(todo_dsl::parent_uid)
+                        .first::<Option<String>>(conn)
+                        .optional()?
+                        .flatten();
+                    // A cycle synced from another client would never end
+                    match parent_uid {
+                        Some(parent_uid) if !ancestors.contains(&parent_uid) => {
+                            ancestors.push(parent_uid.clone());
+                            current = parent_uid;
+                        }
+                        _ => return QueryResult::Ok(ancestors),
+                    }
+                }
+            })
+            .await?;
+        Ok(ancestors)
+    }
+
+    /// Scheduled todos that started before `before` and are not done yet, recurring ones are left out
+    pub async fn list_overdue(conn: DbConn, before: DateTime<Utc>) -> anyhow::Result<Vec<VTodo>> {
+        use crate::schema::vtodos::dsl as todo_dsl;
+
+        let todos = conn
+            .run(move |conn| {
+                todo_dsl::vtodos
+                    .filter(todo_dsl::deleted_at.is_null())
+                    .filter(todo_dsl::has_rrule.eq(false))
+                    .filter(todo_dsl::status.is_not(EventStatus::Done))
+                    .filter(todo_dsl::starts_at.lt(before))
+                    .order(todo_dsl::starts_at.asc())
+                    .select(VTodo::as_select())
+                    .load(conn)
+            })
+            .await?;
+        Ok(todos)
+    }
+
+    /// Recurring todos of the given types, sorted by summary
+    pub async fn list_recurring_of_types(
+        conn: DbConn,
+        event_types: Vec<EventType>,
+    ) -> anyhow::Result<Vec<VTodo>> {
+        use crate::schema::vtodos::dsl as todo_dsl;
+
+        let todos = conn
+            .run(move |conn| {
                 todo_dsl::vtodos
-                    .filter(todo_dsl::starts_at.is_null())
+                    .filter(todo_dsl::deleted_at.is_null())
+                    .filter(todo_dsl::has_rrule.eq(true))
+                    .filter(todo_dsl::event_type.eq_any(event_types))
+                    .order(todo_dsl::summary.asc())
                     .select(VTodo::as_select())
                     .load(conn)
-            } else {
+            })
+            .await?;
+        Ok(todos)
+    }
+
+    /// Todos created or completed between `start` and `end`
+    pub async fn list_created_or_completed_between(
+        conn: DbConn,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<VTodo>> {
+        use crate::schema::vtodos::dsl as todo_dsl;
+
+        let todos = conn
+            .run(move |conn| {
                 todo_dsl::vtodos
+                    .filter(todo_dsl::deleted_at.is_null())
                     .filter(
-                        todo_dsl::starts_at.is_null().and(
-                            todo_dsl::status
-                                .is_not(EventStatus::Done)
-                                .or(todo_dsl::status
-                                    .is(EventStatus::Done)
-                                    .and(todo_dsl::completed.gt(now))),
-                        ),
+                        todo_dsl::created_at
+                            .between(start, end)
+                            .or(todo_dsl::completed.between(start, end)),
                     )
                     .select(VTodo::as_select())
                     .load(conn)
-            }
-        })
-        .await??;
+            })
+            .await?;
+        Ok(todos)
+    }
 
-        Ok(todos
-            .iter()
-            .map(|t| UnscheduledTodo::on_day(t, &Utc::now()))
-            .collect::<Vec<UnscheduledTodo>>())
+    /// Todos that are not done yet and were postponed at least once, the most postponed first
+    pub async fn list_most_postponed(conn: DbConn, limit: i64) -> anyhow::Result<Vec<VTodo>> {
+        use crate::schema::vtodos::dsl as todo_dsl;
+
+        let todos = conn
+            .run(move |conn| {
+                todo_dsl::vtodos
+                    .filter(todo_dsl::deleted_at.is_null())
+                    .filter(todo_dsl::status.is_not(EventStatus::Done))
+                    .filter(todo_dsl::postponed.gt(0))
+                    .order((todo_dsl::postponed.desc(), todo_dsl::id.asc()))
+                    .limit(limit)
+                    .select(VTodo::as_select())
+                    .load(conn)
+            })
+            .await?;
+        Ok(todos)
+    }
+
+    /// Progress of the subtasks of each parent, the parents without subtasks are left out
+    pub async fn subtask_progress(
+        conn: DbConn,
+        parent_uids: Vec<String>,
+    ) -> anyhow::Result<HashMap<String, SubtaskProgress>> {
+        use crate::schema::vtodos::dsl as todo_dsl;
+
+        let subtasks = conn
+            .run(move |conn| {
+                todo_dsl::vtodos
+                    .filter(todo_dsl::deleted_at.is_null())
+                    .filter(todo_dsl::parent_uid.eq_any(parent_uids))
+                    .select((todo_dsl::parent_uid, todo_dsl::status, todo_dsl::load))
+                    .load::<(Option<String>, EventStatus, i32)>(conn)
+            })
+            .await?;
+
+        let mut progress: HashMap<String, SubtaskProgress> = HashMap::new();
+        for (parent_uid, status, load) in subtasks {
+            let Some(parent_uid) = parent_uid else {
+                continue;
+            };
+            let entry = progress.entry(parent_uid).or_default();
+            entry.total += 1;
+            entry.load += load;
+            if matches!(status, EventStatus::Done) {
+                entry.done += 1;
+            }
+        }
+        Ok(progress)
     }
 
     pub async fn update_status_by_id(
@@ -283,28 +491,34 @@ impl VTodo {
         status: EventStatus,
         update_at: DateTime<FixedOffset>,
     ) -> anyhow::Result<Option<VTodo>> {
+        conn.run(move |conn| Self::update_status_by_id_in(conn, vtodo_id, status, update_at))
+            .await
+    }
+
+    /// Same as [`VTodo::update_status_by_id`] on a connection that may be in a transaction
+    pub fn update_status_by_id_in(
+        conn: &mut SqliteConnection,
+        vtodo_id: i32,
+        status: EventStatus,
+        update_at: DateTime<FixedOffset>,
+    ) -> QueryResult<Option<VTodo>> {
         use crate::schema::vtodos::dsl as vtodos_dsl;
-        let conn_2 = conn.clone();
-        spawn_blocking(move || {
-            let conn = &mut *conn_2.0.lock().unwrap();
-
-            let completed_update = if matches!(status, EventStatus::Done) {
-                vtodos_dsl::completed.eq(Some(update_at.to_utc()))
-            } else {
-                vtodos_dsl::completed.eq(None)
-            };
 
-            diesel::update(vtodos_dsl::vtodos)
-                .filter(vtodos_dsl::id.eq(vtodo_id))
-                .set((
-                    vtodos_dsl::status.eq(status),
-                    completed_update,
-                    vtodos_dsl::out_of_sync.eq(true),
-                ))
-                .execute(conn)
-        })
-        .await??;
-        Self::by_id(conn, vtodo_id).await
+        let completed_update = if matches!(status, EventStatus::Done) {
+            vtodos_dsl::completed.eq(Some(update_at.to_utc()))
+        } else {
+            vtodos_dsl::completed.eq(None)
+        };
+        diesel::update(vtodos_dsl::vtodos)
+            .filter(vtodos_dsl::id.eq(vtodo_id))
+            .set((
+                vtodos_dsl::status.eq(status),
+                completed_update,
+                vtodos_dsl::out_of_sync.eq(true),
+            ))
+            .returning(VTodo::as_returning())
+            .get_result(conn)
+            .optional()
     }
 
     pub fn apply_upsert<Tz: TimeZone>(
@@ -319,9 +533,14 @@ impl VTodo {
             todo.starts_at = Some(date_info.start.to_utc());
             todo.ends_at = Some(date_info.get_end_or_default(extracted.event_type).to_utc());
         }
+        // Moving it later counts as postponing it
+        if let (Some(before), Some(after)) = (self.starts_at, todo.starts_at)
+            && after > before
+        {
+            todo.postponed += 1;
+        }
         todo.event_type = extracted.event_type;
         todo.status = extracted.status;
-        todo.postponed = extracted.postponed;
         todo.urgency = extracted.urgency;
         todo.load = extracted.load;
         todo.importance = extracted.importance;
@@ -353,6 +572,7 @@ impl From<VTodo> for CalendarComponent {
             .add_property(ComponentProps::Load, value.load.to_string())
             .add_property(ComponentProps::Urgency, value.urgency.to_string())
             .add_property(ComponentProps::Importance, value.importance.to_string())
+            .add_property(ComponentProps::Postponed, value.postponed.to_string())
             .status(match value.status {
                 EventStatus::Done => TodoStatus::Completed,
                 EventStatus::InProgress => TodoStatus::InProcess,
@@ -366,9 +586,22 @@ impl From<VTodo> for CalendarComponent {
             })
             .done();
 
+        add_priority_property(&mut todo, value.urgency, value.importance);
+
+        if let Some(description) = &value.description {
+            todo.description(description);
+        }
+
         if let Some(tag) = value.tag {
-            todo.add_property(ComponentProps::Tag, tag.to_lowercase());
-            todo.add_property(ComponentProps::Categories, tag.to_uppercase());
+            add_tag_properties(&mut todo, &tag);
+        }
+
+        if let Some(parent_uid) = value.parent_uid {
+            add_parent_property(&mut todo, &parent_uid);
+        }
+
+        if let Some(created_at) = value.created_at {
+            todo.created(created_at);
         }
 
         if let Some(completed_date) = value.completed {
@@ -405,6 +638,8 @@ pub struct NewVTodo {
     pub synced_at: Option<chrono::DateTime<Utc>>,
     pub completed: Option<chrono::DateTime<Utc>>,
     pub out_of_sync: bool,
+    pub parent_uid: Option<String>,
+    pub created_at: Option<chrono::DateTime<Utc>>,
 }
 
 impl_ical_parseable!(VTodo, icalendar::Todo, |f| f.as_todo());
@@ -444,29 +679,28 @@ macro_rules! impl_todo_trait {
                 use crate::schema::vtodos::dsl as todo_dsl;
 
                 let todo = self.clone();
-                let val = spawn_blocking(move || {
-                    let conn = &mut *conn.0.lock().unwrap();
-
-                    insert_into(todo_dsl::vtodos)
-                        .values(todo)
-                        .returning(VTodo::as_returning())
-                        .get_result(conn)
-                })
-                .await??;
+                let val = conn
+                    .run(move |conn| {
+                        insert_into(todo_dsl::vtodos)
+                            .values(todo)
+                            .returning(VTodo::as_returning())
+                            .get_result(conn)
+                    })
+                    .await?;
                 Ok(val)
             }
             async fn update(&self, conn: DbConn, id: i32) -> anyhow::Result<VTodo> {
                 use crate::schema::vtodos::dsl as todo_dsl;
 
                 let todo = self.clone();
-                let val = spawn_blocking(move || {
-                    let conn = &mut *conn.0.lock().unwrap();
-                    update(todo_dsl::vtodos.filter(todo_dsl::id.eq(id)))
-                        .set(todo)
-                        .returning(VTodo::as_returning())
-                        .get_result(conn)
-                })
-                .await??;
+                let val = conn
+                    .run(move |conn| {
+                        update(todo_dsl::vtodos.filter(todo_dsl::id.eq(id)))
+                            .set(todo)
+                            .returning(VTodo::as_returning())
+                            .get_result(conn)
+                    })
+                    .await?;
                 Ok(val)
             }
 
@@ -571,9 +805,14 @@ impl FromResource for NewVTodo {
             load,
             postponed,
             last_modified,
+            parent_uid,
+            created,
         } = GeneralComponentProps::try_from(*first_todo)?;
 
-        let (starts_at, ends_at) = match parse_todo_start_and_end(&calendar_item)? {
+        let (starts_at, ends_at) = match parse_todo_start_and_end(
+            &calendar_item,
+            &CalendarTimezones::from_ical_data(ical_data),
+        )? {
             Some((start, end)) => (Some(start), Some(end)),
             None => (None, None),
         };
@@ -585,7 +824,7 @@ impl FromResource for NewVTodo {
             ical_data: Some(ical_data.to_string()),
             last_modified: Some(last_modified),
             summary: summary.to_string(),
-            completed: None,
+            completed: first_todo.get_completed(),
             description,
             status,
             original_text,
@@ -602,6 +841,8 @@ impl FromResource for NewVTodo {
             starts_at,
             ends_at,
             out_of_sync: false,
+            parent_uid,
+            created_at: created,
         };
 
         let rrule_str = new_todo.get_rrule_from_ical().map(|r| r.to_string());
@@ -615,15 +856,8 @@ impl FromResource for NewVTodo {
 
 fn parse_todo_start_and_end(
     calendar: &icalendar::Calendar,
+    timezones: &CalendarTimezones,
 ) -> anyhow::Result<Option<(chrono::DateTime<Utc>, chrono::DateTime<Utc>)>> {
-    let timezone = calendar
-        .get_timezone()
-        .and_then(|tzid| {
-            let tz: Option<chrono_tz::Tz> = tzid.parse().ok();
-            tz
-        })
-        .unwrap_or(chrono_tz::UTC);
-
     let todo = calendar
         .components
         .iter()
@@ -633,7 +867,7 @@ fn parse_todo_start_and_end(
 
     let start = todo
         .get_start()
-        .and_then(|s| date_from_calendar_to_utc(s, timezone));
+        .and_then(|s| date_from_calendar_to_utc(s, timezones));
 
     let Some(start) = start else {
         return Ok(None);
@@ -641,7 +875,7 @@ fn parse_todo_start_and_end(
 
     let end = todo.get_end().or(todo.get_due());
     let end = if let Some(end) = end {
-        date_from_calendar_to_utc(end, timezone)
+        date_from_calendar_to_utc(end, timezones)
     } else {
         todo.property_value(ComponentProps::Duration.as_ref())
             .and_then(parse_duration)
@@ -654,14 +888,12 @@ fn parse_todo_start_and_end(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        calendar_items::input_traits::{ExtractedInput, FromUserInput},
+        test_utils::load_file,
+    };
     use chrono::{TimeZone, Utc};
-    use std::{fs, path::PathBuf};
-
-    fn load_file(path: &str) -> String {
-        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        d.push(path);
-        fs::read_to_string(d).expect("To Load file")
-    }
+    use tauri::async_runtime::block_on;
 
     #[test]
     fn test_should_parsed_todo() {
@@ -716,4 +948,199 @@ mod tests {
             ".t %d Yerba at 20/05/24 13:00-16:00"
         );
     }
+
+    #[test]
+    fn test_should_parse_windows_timezone() {
+        let ics = load_file("./fixtures/todo_windows_timezone.ics");
+        let todo = NewVTodo::from_ical_data(1, "test", ics.as_str(), "")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(todo.summary, "Send expense report");
+        assert_eq!(
+            todo.starts_at,
+            Some(Utc.with_ymd_and_hms(2025, 4, 15, 16, 0, 0).unwrap())
+        );
+        assert_eq!(
+            todo.ends_at,
+            Some(Utc.with_ymd_and_hms(2025, 4, 15, 17, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_should_parse_subtask() {
+        let ics = load_file("./fixtures/todo_subtask.ics");
+        let todo = NewVTodo::from_ical_data(1, "test", ics.as_str(), "")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(todo.summary, "Sand the walls");
+        assert_eq!(
+            todo.parent_uid,
+            Some("92C23FC0-2435-4AC8-9D73-20BC55FB8776".to_string())
+        );
+    }
+
+    #[test]
+    fn test_subtask_progress() {
+        let conn = DbConn::in_memory().unwrap();
+        let parent = NewVTodo::from_ical_data(1, "/parent", &load_file("./fixtures/todo.ics"), "")
+            .unwrap()
+            .unwrap();
+        let subtask =
+            NewVTodo::from_ical_data(1, "/subtask", &load_file("./fixtures/todo_subtask.ics"), "")
+                .unwrap()
+                .unwrap();
+        let done_subtask = NewVTodo {
+            uid: "done-subtask".to_string(),
+            href: Some("/done-subtask".to_string()),
+            status: EventStatus::Done,
+            load: 3,
+            ..subtask.clone()
+        };
+        let subtask = NewVTodo { load: 2, ..subtask };
+        for todo in [&parent, &subtask, &done_subtask] {
+            block_on(todo.create(conn.clone())).unwrap();
+        }
+
+        let subtasks = block_on(VTodo::list_subtasks(conn.clone(), parent.uid.clone())).unwrap();
+        assert_eq!(subtasks.len(), 2);
+
+        let progress = block_on(VTodo::subtask_progress(
+            conn,
+            vec![parent.uid.clone(), subtask.uid.clone()],
+        ))
+        .unwrap();
+        assert_eq!(
+            progress.get(&parent.uid),
+            Some(&SubtaskProgress {
+                done: 1,
+                total: 2,
+                load: 5,
+            })
+        );
+        assert_eq!(progress.get(&subtask.uid), None);
+    }
+
+    #[test]
+    fn test_ancestor_uids() {
+        let conn = DbConn::in_memory().unwrap();
+        let parent = NewVTodo::from_ical_data(1, "/parent", &load_file("./fixtures/todo.ics"), "")
+            .unwrap()
+            .unwrap();
+        let subtask =
+            NewVTodo::from_ical_data(1, "/subtask", &load_file("./fixtures/todo_subtask.ics"), "")
+                .unwrap()
+                .unwrap();
+        let nested = NewVTodo {
+            uid: "nested-subtask".to_string(),
+            href: Some("/nested-subtask".to_string()),
+            parent_uid: Some(subtask.uid.clone()),
+            ..subtask.clone()
+        };
+        for todo in [&parent, &subtask, &nested] {
+            block_on(todo.create(conn.clone())).unwrap();
+        }
+
+        let ancestors = block_on(VTodo::ancestor_uids(conn.clone(), nested.uid.clone())).unwrap();
+        assert_eq!(ancestors, vec![subtask.uid.clone(), parent.uid.clone()]);
+        assert!(
+            block_on(VTodo::ancestor_uids(conn, parent.uid))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_subtask_writes_related_to() {
+        let conn = DbConn::in_memory().unwrap();
+        let ics = load_file("./fixtures/todo_subtask.ics");
+        let todo = NewVTodo::from_ical_data(1, "test", ics.as_str(), "")
+            .unwrap()
+            .unwrap();
+        let component: CalendarComponent = block_on(todo.create(conn)).unwrap().into();
+        let ical = icalendar::Calendar::new()
+            .push(component)
+            .done()
+            .to_string();
+        assert!(ical.contains("RELATED-TO;RELTYPE=PARENT:92C23FC0-2435-4AC8-9D73-20BC55FB8776"));
+    }
+
+    #[test]
+    fn test_reschedule_later_counts_as_postponed() {
+        let conn = DbConn::in_memory().unwrap();
+        let ics = load_file("./fixtures/todo_date.ics");
+        let todo = NewVTodo::from_ical_data(1, "test", ics.as_str(), "")
+            .unwrap()
+            .unwrap();
+        let todo = block_on(todo.create(conn)).unwrap();
+        let reference_date = chrono_tz::Tz::UTC
+            .with_ymd_and_hms(2024, 5, 20, 10, 0, 0)
+            .unwrap();
+
+        let input = ".t Yerba tomorrow at 13";
+        let ExtractedInput(data, _) = EventUpsertInfo::extract_from_input(reference_date, input)
+            .unwrap()
+            .into();
+        let later = todo
+            .apply_upsert(input, data, reference_date, Some(true))
+            .unwrap();
+        assert_eq!(later.postponed, todo.postponed + 1);
+
+        let input = ".t Yerba today at 9";
+        let ExtractedInput(data, _) = EventUpsertInfo::extract_from_input(reference_date, input)
+            .unwrap()
+            .into();
+        let earlier = later
+            .apply_upsert(input, data, reference_date, Some(true))
+            .unwrap();
+        assert_eq!(earlier.postponed, later.postponed);
+    }
+
+    #[test]
+    fn test_list_most_postponed() {
+        let conn = DbConn::in_memory().unwrap();
+        let ics = load_file("./fixtures/todo_date.ics");
+        let todo = NewVTodo::from_ical_data(1, "/todo", ics.as_str(), "")
+            .unwrap()
+            .unwrap();
+        let todos = [
+            NewVTodo {
+                status: EventStatus::Todo,
+                postponed: 1,
+                ..todo.clone()
+            },
+            NewVTodo {
+                uid: "slipping".to_string(),
+                href: Some("/slipping".to_string()),
+                status: EventStatus::Todo,
+                postponed: 4,
+                ..todo.clone()
+            },
+            NewVTodo {
+                uid: "done".to_string(),
+                href: Some("/done".to_string()),
+                status: EventStatus::Done,
+                postponed: 9,
+                ..todo.clone()
+            },
+            NewVTodo {
+                uid: "on-time".to_string(),
+                href: Some("/on-time".to_string()),
+                status: EventStatus::Todo,
+                postponed: 0,
+                ..todo
+            },
+        ];
+        for todo in &todos {
+            block_on(todo.create(conn.clone())).unwrap();
+        }
+
+        let most_postponed = block_on(VTodo::list_most_postponed(conn, 10)).unwrap();
+        let uids = most_postponed
+            .iter()
+            .map(|t| t.uid.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(uids, vec!["slipping", todos[0].uid.as_str()]);
+    }
 }
@@ -14,6 +14,27 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    journal (id) {
+        id -> Integer,
+        vcmp_kind -> Text,
+        vcmp_id -> Integer,
+        before -> Nullable<Text>,
+        after -> Nullable<Text>,
+        undone -> Bool,
+        created_at -> TimestamptzSqlite,
+        occurrence_start -> Nullable<TimestamptzSqlite>,
+    }
+}
+
+diesel::table! {
+    named_items (kind, name) {
+        kind -> Text,
+        name -> Text,
+        value -> Text,
+    }
+}
+
 diesel::table! {
     servers (id) {
         server_url -> Text,
@@ -24,6 +45,35 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    settings (key) {
+        key -> Text,
+        value -> Text,
+    }
+}
+
+diesel::table! {
+    todo_occurrences (vcmp_uid, occurrence_start) {
+        vcmp_uid -> Text,
+        occurrence_start -> TimestamptzSqlite,
+        status -> Text,
+        completed -> Nullable<TimestamptzSqlite>,
+    }
+}
+
+diesel::table! {
+    trash (id) {
+        id -> Integer,
+        vcmp_kind -> Text,
+        vcmp_id -> Integer,
+        calendar_id -> Integer,
+        summary -> Text,
+        snapshot -> Text,
+        ical_data -> Text,
+        deleted_at -> TimestamptzSqlite,
+    }
+}
+
 diesel::table! {
     vevents (id) {
         id -> Integer,
@@ -49,6 +99,7 @@ diesel::table! {
         synced_at -> Nullable<TimestamptzSqlite>,
         last_modified -> Nullable<TimestamptzSqlite>,
         out_of_sync -> Bool,
+        deleted_at -> Nullable<TimestamptzSqlite>,
     }
 }
 
@@ -78,6 +129,9 @@ diesel::table! {
         synced_at -> Nullable<TimestamptzSqlite>,
         last_modified -> Nullable<TimestamptzSqlite>,
         out_of_sync -> Bool,
+        deleted_at -> Nullable<TimestamptzSqlite>,
+        parent_uid -> Nullable<Text>,
+        created_at -> Nullable<TimestamptzSqlite>,
     }
 }
 
@@ -85,4 +139,14 @@ diesel::joinable!(calendars -> servers (server_id));
 diesel::joinable!(vevents -> calendars (calendar_id));
 diesel::joinable!(vtodos -> calendars (calendar_id));
 
-diesel::allow_tables_to_appear_in_same_query!(calendars, servers, vevents, vtodos,);
+diesel::allow_tables_to_appear_in_same_query!(
+    calendars,
+    journal,
+    named_items,
+    servers,
+    settings,
+    todo_occurrences,
+    trash,
+    vevents,
+    vtodos,
+);
@@ -0,0 +1,92 @@
+//! Fixtures and factories shared by the tests.
+
+use std::{fs, path::PathBuf};
+
+use chrono::{DateTime, Utc};
+use tauri::async_runtime::block_on;
+
+use crate::{
+    db_conn::DbConn,
+    models::{
+        FromResource, VCmp, VCmpId,
+        vevent::{NewVEvent, VEvent, VEventTrait},
+        vtodo::{NewVTodo, VTodo, VTodoTrait},
+    },
+};
+
+/// Reads a file relative to the crate root, like `./fixtures/basic.ics`
+pub fn load_file(path: &str) -> String {
+    let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    d.push(path);
+    fs::read_to_string(d).expect("To Load file")
+}
+
+/// Event of the calendar 1 parsed from a file of `fixtures`
+pub fn new_event(fixture: &str) -> NewVEvent {
+    let ical = load_file(&format!("./fixtures/{fixture}"));
+    NewVEvent::from_ical_data(1, "/event", &ical, "")
+        .unwrap()
+        .unwrap()
+}
+
+/// Recurring block of `basic.ics`, from 13:00 to 16:00 every weekday, taking time
+pub fn new_opaque_block() -> NewVEvent {
+    let ical = load_file("./fixtures/basic.ics").replace("TRANSP:TRANSPARENT", "TRANSP:OPAQUE");
+    NewVEvent::from_ical_data(1, "/event", &ical, "")
+        .unwrap()
+        .unwrap()
+}
+
+/// Event of `basic.ics` without recurrence, only from `starts_at` to `ends_at`
+pub fn new_single_event(uid: &str, starts_at: DateTime<Utc>, ends_at: DateTime<Utc>) -> NewVEvent {
+    NewVEvent {
+        uid: uid.to_string(),
+        href: Some(format!("/{uid}")),
+        starts_at,
+        ends_at,
+        has_rrule: false,
+        rrule_str: None,
+        ical_data: None,
+        ..new_event("basic.ics")
+    }
+}
+
+/// Todo of the calendar 1 parsed from a file of `fixtures`
+pub fn new_todo(fixture: &str) -> NewVTodo {
+    let ical = load_file(&format!("./fixtures/{fixture}"));
+    NewVTodo::from_ical_data(1, "/todo", &ical, "")
+        .unwrap()
+        .unwrap()
+}
+
+/// Todo of the calendar 1 parsed from a file of `fixtures`, as fetched from the server
+pub fn new_synced_todo(fixture: &str) -> NewVTodo {
+    let ical = load_file(&format!("./fixtures/{fixture}"));
+    NewVTodo::from_ical_data(1, "/todo", &ical, "etag")
+        .unwrap()
+        .unwrap()
+}
+
+/// Todo of the calendar 1 parsed from a file of `fixtures`, created locally and never synced
+pub fn new_local_todo(fixture: &str) -> NewVTodo {
+    NewVTodo {
+        etag: None,
+        synced_at: None,
+        ..new_synced_todo(fixture)
+    }
+}
+
+pub fn create_event(conn: DbConn, event: NewVEvent) -> VEvent {
+    block_on(event.create(conn)).unwrap()
+}
+
+pub fn create_todo(conn: DbConn, todo: NewVTodo) -> VTodo {
+    block_on(todo.create(conn)).unwrap()
+}
+
+pub fn get_todo(conn: DbConn, vcmp_id: VCmpId) -> Option<VTodo> {
+    match block_on(VCmp::by_id(conn, vcmp_id)).unwrap() {
+        Some(VCmp::Todo(vtodo)) => Some(vtodo),
+        _ => None,
+    }
+}
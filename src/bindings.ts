@@ -69,7 +69,7 @@ async superSyncCalendar(calendarId: number) : Promise<Result<null, string>> {
     else return { status: "error", error: e  as any };
 }
 },
-async listEventsForDay(datetime: string) : Promise<Result<ExtendedEvent[], string>> {
+async listEventsForDay(datetime: string) : Promise<Result<EventsForDay, string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("list_events_for_day", { datetime }) };
 } catch (e) {
@@ -77,7 +77,7 @@ async listEventsForDay(datetime: string) : Promise<Result<ExtendedEvent[], strin
     else return { status: "error", error: e  as any };
 }
 },
-async listTodosForDay(datetime: string) : Promise<Result<ExtendedTodo[], string>> {
+async listTodosForDay(datetime: string) : Promise<Result<TodosForDay, string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("list_todos_for_day", { datetime }) };
 } catch (e) {
@@ -85,15 +85,23 @@ async listTodosForDay(datetime: string) : Promise<Result<ExtendedTodo[], string>
     else return { status: "error", error: e  as any };
 }
 },
-async parseEvent(dateOfInputStr: string, componentInput: string) : Promise<Result<DisplayUpsertInfo, string>> {
+/**
+ * Parses the input to show what would be saved, `editing` is the component the input
+ * replaces when it's an edit
+ */
+async parseEvent(dateOfInputStr: string, componentInput: string, editing: VCmpId | null) : Promise<Result<DisplayUpsertInfo, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("parse_event", { dateOfInputStr, componentInput }) };
+    return { status: "ok", data: await TAURI_INVOKE("parse_event", { dateOfInputStr, componentInput, editing }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async createComponent(calendarId: number, dateOfInputStr: string, componentInput: string) : Promise<Result<null, string>> {
+/**
+ * Creates the component, returns the summary of its day when it puts that day over the
+ * load budget
+ */
+async createComponent(calendarId: number, dateOfInputStr: string, componentInput: string) : Promise<Result<DaySummary | null, string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("create_component", { calendarId, dateOfInputStr, componentInput }) };
 } catch (e) {
@@ -101,23 +109,35 @@ async createComponent(calendarId: number, dateOfInputStr: string, componentInput
     else return { status: "error", error: e  as any };
 }
 },
-async setVcmpStatus(vcmp: number, status: string, dateOfChange: string) : Promise<Result<null, string>> {
+async setVcmpStatus(vcmpId: VCmpId, status: string, dateOfChange: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("set_vcmp_status", { vcmp, status, dateOfChange }) };
+    return { status: "ok", data: await TAURI_INVOKE("set_vcmp_status", { vcmpId, status, dateOfChange }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async deleteVcmp(veventId: number) : Promise<Result<null, string>> {
+/**
+ * Sets the status of the occurrence of a recurring todo or reminder on the day of `day`,
+ * the series stays as is
+ */
+async setOccurrenceStatus(vcmpId: VCmpId, day: string, status: string, dateOfChange: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("delete_vcmp", { veventId }) };
+    return { status: "ok", data: await TAURI_INVOKE("set_occurrence_status", { vcmpId, day, status, dateOfChange }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async updateVcmp(vcmpId: number, dateOfInputStr: string, componentInput: string) : Promise<Result<null, string>> {
+async deleteVcmp(vcmpId: VCmpId) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_vcmp", { vcmpId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateVcmp(vcmpId: VCmpId, dateOfInputStr: string, componentInput: string) : Promise<Result<null, string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("update_vcmp", { vcmpId, dateOfInputStr, componentInput }) };
 } catch (e) {
@@ -125,6 +145,40 @@ async updateVcmp(vcmpId: number, dateOfInputStr: string, componentInput: string)
     else return { status: "error", error: e  as any };
 }
 },
+/**
+ * Changes only the fields set in the patch, the other ones keep their value
+ */
+async updateVcmpFields(vcmpId: VCmpId, dateOfChange: string, patch: VCmpPatch) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_vcmp_fields", { vcmpId, dateOfChange, patch }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Moves the component to the date in `to`, like "tomorrow" or "next week", counting it
+ * as postponed when it's later than before
+ */
+async postponeVcmp(vcmpId: VCmpId, dateOfInputStr: string, to: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("postpone_vcmp", { vcmpId, dateOfInputStr, to }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Lists the pending todos that were postponed the most
+ */
+async listMostPostponed(limit: number) : Promise<Result<UnscheduledTodo[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_most_postponed", { limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async listUnscheduledTodos(includeDone: boolean) : Promise<Result<UnscheduledTodo[], string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("list_unscheduled_todos", { includeDone }) };
@@ -132,6 +186,331 @@ async listUnscheduledTodos(includeDone: boolean) : Promise<Result<UnscheduledTod
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
+},
+/**
+ * Lists the scheduled todos whose day passed without being done
+ */
+async listOverdueTodos(dateOfInputStr: string) : Promise<Result<UnscheduledTodo[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_overdue_todos", { dateOfInputStr }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Lists the subtasks of a todo
+ */
+async listSubtasks(vcmpId: VCmpId) : Promise<Result<UnscheduledTodo[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_subtasks", { vcmpId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Undoes the last mutation, returns the id of the affected component
+ */
+async undo() : Promise<Result<VCmpId | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("undo") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Redoes the last undone mutation, returns the id of the affected component
+ */
+async redo() : Promise<Result<VCmpId | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("redo") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listTrash() : Promise<Result<TrashItem[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_trash") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Restores the component, returns the id it has now
+ */
+async restoreFromTrash(trashId: number) : Promise<Result<VCmpId, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("restore_from_trash", { trashId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async emptyTrash() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("empty_trash") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getSettings() : Promise<Result<Settings, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_settings") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateSettings(settings: Settings) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_settings", { settings }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Statistics from the day of `from` to the day of `to`, both included
+ */
+async getStatistics(from: string, to: string) : Promise<Result<Statistics, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_statistics", { from, to }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Proposes slots in the working hours of the next `days` for the selected todos,
+ * nothing is saved until [`apply_schedule`] is called with them
+ */
+async previewSchedule(vcmpIds: VCmpId[], dateOfInputStr: string, days: number) : Promise<Result<SchedulePreview, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("preview_schedule", { vcmpIds, dateOfInputStr, days }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Schedules the todos in the slots of a preview, nothing is scheduled when one of them
+ * isn't unscheduled anymore
+ */
+async applySchedule(slots: ProposedSlot[]) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("apply_schedule", { slots }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Writes the busy time of the calendars from `from` to `to` as a `VFREEBUSY` file at
+ * `path`, events of the `excluded_types` are left out
+ */
+async exportFreeBusy(calendarIds: number[], from: string, to: string, excludedTypes: EventType[], path: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_free_busy", { calendarIds, from, to, excludedTypes, path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Free slots of `duration_minutes` between `from` and `to` across all the calendars,
+ * best ones first
+ */
+async findSlots(durationMinutes: number, from: string, to: string, constraints: SlotConstraints, limit: number) : Promise<Result<FreeSlot[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("find_slots", { durationMinutes, from, to, constraints, limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Overlapping events between `from` and `to` across all the calendars, recurring
+ * events included
+ */
+async listConflicts(from: string, to: string) : Promise<Result<Conflict[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_conflicts", { from, to }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Flexible items of the day starting from `date_of_input` on, moved by `minutes`,
+ * nothing is saved until [`apply_shift_day`] is called with them
+ * 
+ * Flexible items are the `Block`, `Task` and `Reminder` ones that are not done and
+ * don't repeat, `Event`s stay where they are
+ */
+async previewShiftDay(dateOfInputStr: string, minutes: number) : Promise<Result<ShiftedItem[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("preview_shift_day", { dateOfInputStr, minutes }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Moves the items of a preview to their new times, they are synced as any other change.
+ * Nothing moves when one of them was moved since the preview
+ */
+async applyShiftDay(items: ShiftedItem[]) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("apply_shift_day", { items }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listRoutines() : Promise<Result<Routine[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_routines") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Creates the routine or replaces the one with the same name
+ */
+async saveRoutine(routine: Routine) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("save_routine", { routine }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteRoutine(name: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_routine", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Creates every item of the routine in the calendar, the routine starts at
+ * `date_of_input_str` and each item at its offset from it
+ */
+async instantiateRoutine(name: string, dateOfInputStr: string, calendarId: number) : Promise<Result<VCmpId[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("instantiate_routine", { name, dateOfInputStr, calendarId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listTemplates() : Promise<Result<Template[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_templates") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Creates the template or replaces the one with the same name
+ */
+async saveTemplate(template: Template) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("save_template", { template }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteTemplate(name: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_template", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Sets the status of the components, for the recurring ones only the one of their
+ * occurrence on the day of `day`
+ */
+async bulkSetStatus(vcmpIds: VCmpId[], status: string, day: string, dateOfChange: string) : Promise<Result<BulkItemResult[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("bulk_set_status", { vcmpIds, status, day, dateOfChange }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Moves the components to the calendar, they're pushed there and deleted from their old
+ * calendar on the next sync
+ */
+async bulkMoveToCalendar(vcmpIds: VCmpId[], calendarId: number) : Promise<Result<BulkItemResult[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("bulk_move_to_calendar", { vcmpIds, calendarId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async bulkAddTag(vcmpIds: VCmpId[], tag: string) : Promise<Result<BulkItemResult[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("bulk_add_tag", { vcmpIds, tag }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async bulkRemoveTag(vcmpIds: VCmpId[], tag: string) : Promise<Result<BulkItemResult[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("bulk_remove_tag", { vcmpIds, tag }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Moves the components by `minutes`, negative to move them earlier
+ */
+async bulkReschedule(vcmpIds: VCmpId[], minutes: number) : Promise<Result<BulkItemResult[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("bulk_reschedule", { vcmpIds, minutes }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Deletes the components like [`super::components::delete_vcmp`], they go to the trash
+ */
+async bulkDelete(vcmpIds: VCmpId[]) : Promise<Result<BulkItemResult[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("bulk_delete", { vcmpIds }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Streaks and history of the recurring reminders and tasks up to the day of `datetime`,
+ * the completion rate is taken over the `last_occurrences` of each one
+ */
+async listHabits(datetime: string, lastOccurrences: number) : Promise<Result<Habit[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_habits", { datetime, lastOccurrences }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
 }
 }
 
@@ -145,18 +524,88 @@ async listUnscheduledTodos(includeDone: boolean) : Promise<Result<UnscheduledTod
 
 /** user-defined types **/
 
+/**
+ * Outcome of a bulk action for one component, `error` is set when it was left unchanged
+ */
+export type BulkItemResult = { vcmp_id: VCmpId; error: string | null }
 export type Calendar = { id: number; name: string; url: string; etag: string | null; server_id: number; is_default: boolean; sync_token: string | null; synced_at: string | null }
+/**
+ * Two occurrences overlapping each other, `first` starts earlier
+ */
+export type Conflict = { first: ConflictingItem; second: ConflictingItem }
+/**
+ * Occurrence of an event that takes the same time as another item
+ */
+export type ConflictingItem = { vcmp_id: VCmpId; summary: string; starts_at: string; ends_at: string }
+/**
+ * Values of a single day of the range
+ */
+export type DayBucket = { day: string; 
+/**
+ * Todos created that day
+ */
+created: number; 
+/**
+ * Todos completed that day
+ */
+completed: number; 
+/**
+ * Sum of the load of the todos completed that day
+ */
+load_done: number }
+/**
+ * Load of a day compared with its budget
+ */
+export type DaySummary = { day: string; 
+/**
+ * Sum of the load of the events and todos of the day, done ones included
+ */
+scheduled_load: number; load_budget: number; 
+/**
+ * Minutes of the working day not taken by events
+ */
+free_minutes: number; over_budget: boolean }
 /**
  * Simplified version of a [`EventUpsertInfo`] for showing to the user while creating
  */
-export type DisplayUpsertInfo = { summary: string; starts_at: string | null; ends_at: string | null; recurrence: string | null; status: EventStatus; event_type: EventType; postponed: number; urgency: number; load: number; importance: number; tag: string | null }
+export type DisplayUpsertInfo = { summary: string; starts_at: string | null; ends_at: string | null; recurrence: string | null; status: EventStatus; event_type: EventType; postponed: number; urgency: number; load: number; importance: number; tag: string | null; 
+/**
+ * Id of the todo it will be a subtask of
+ */
+parent_id: number | null; 
+/**
+ * Set when adding it puts its day over the load budget
+ */
+budget_warning: DaySummary | null; 
+/**
+ * Existing items taking the same time
+ */
+conflicts: ConflictingItem[]; 
+/**
+ * Input after expanding the template it started with
+ */
+expanded_input: string | null; 
+/**
+ * Why the input couldn't be fully understood, like a slot query without a free slot
+ */
+warning: string | null }
 export type EventStatus = "Backlog" | "Todo" | "InProgress" | "Done"
 export type EventType = "Event" | "Block" | "Reminder" | "Task"
+export type EventsForDay = { events: ExtendedEvent[]; summary: DaySummary }
 export type ExtendedEvent = { 
+/**
+ * Id to reference the event in other commands
+ */
+vcmp_id: VCmpId; 
 /**
  * Date when the extended event was calculated
  */
-query_date: string; event: VEvent; 
+query_date: string; 
+/**
+ * For recurring events with a status on this occurrence, like the reminders followed
+ * as habits, the status is the one of this occurrence
+ */
+event: VEvent; 
 /**
  * The start date of the event, if recurrent the value for the current query
  */
@@ -164,12 +613,26 @@ starts_at: string;
 /**
  * The end date of the event, if recurrent the value for the current query
  */
-ends_at: string; natural_recurrence: string | null; natural_string: string }
+ends_at: string; natural_recurrence: string | null; natural_string: string; 
+/**
+ * Other events taking the same time, set by [`flag_conflicts`]
+ * 
+ * [`flag_conflicts`]: crate::commands::conflicts::flag_conflicts
+ */
+conflicts: VCmpId[] }
 export type ExtendedTodo = { 
+/**
+ * Id to reference the todo in other commands
+ */
+vcmp_id: VCmpId; 
 /**
  * Date when the extended event was calculated
  */
-query_date: string; todo: VTodo; 
+query_date: string; 
+/**
+ * For recurring todos the status and completion are the ones of this occurrence
+ */
+todo: VTodo; 
 /**
  * The start date of the event, if recurrent the value for the current query
  */
@@ -177,11 +640,210 @@ starts_at: string;
 /**
  * The end date of the event, if recurrent the value for the current query
  */
-ends_at: string; natural_recurrence: string | null; natural_string: string }
+ends_at: string; natural_recurrence: string | null; natural_string: string; 
+/**
+ * Progress of the subtasks, if it has any
+ */
+progress: SubtaskProgress | null }
+export type FreeSlot = { starts_at: string; ends_at: string; 
+/**
+ * Fully inside the preferred time of the day
+ */
+preferred: boolean }
+export type Habit = { vcmp_id: VCmpId; summary: string; event_type: EventType; 
+/**
+ * Occurrences done in a row up to the last one
+ */
+current_streak: number; longest_streak: number; 
+/**
+ * Share of the last occurrences that were done, from 0 to 1
+ */
+completion_rate: number; 
+/**
+ * Occurrences of the last year, the oldest first
+ */
+history: HabitDay[] }
+export type HabitDay = { occurrence_start: string; done: boolean }
+export type NamedTime = "Morning" | "Afternoon" | "Evening" | "Night" | "Noon" | "Midnight"
+/**
+ * Slot proposed for an unscheduled todo
+ */
+export type ProposedSlot = { vcmp_id: VCmpId; summary: string; starts_at: string; ends_at: string; 
+/**
+ * Placed inside a block with a matching tag
+ */
+in_block: boolean }
+/**
+ * Named sequence of items created together, stored only locally with its items as
+ * json
+ */
+export type Routine = { name: string; items: RoutineItem[] }
+/**
+ * Step of a routine
+ */
+export type RoutineItem = { 
+/**
+ * Quick-add input, like `.b Stretch #health`
+ */
+input: string; 
+/**
+ * Minutes from the start of the routine, used when the input has no date
+ */
+offset_minutes: number }
+export type SchedulePreview = { slots: ProposedSlot[]; 
+/**
+ * Todos that didn't fit in the free time
+ */
+not_scheduled: VCmpId[] }
 export type Server = { id: number; server_url: string; user: string; password: string; last_sync: string | null }
-export type UnscheduledTodo = { todo: VTodo; natural_string: string }
-export type VEvent = { id: number; calendar_id: number; uid: string; href: string | null; ical_data: string | null; summary: string; description: string | null; starts_at: string; ends_at: string; has_rrule: boolean; rrule_str: string | null; tag: string | null; status: EventStatus; event_type: EventType; original_text: string | null; load: number; urgency: number; importance: number; postponed: number; last_modified: string | null; etag: string | null; synced_at: string | null; out_of_sync: boolean }
-export type VTodo = { id: number; calendar_id: number; uid: string; href: string | null; ical_data: string | null; summary: string; description: string | null; starts_at: string | null; ends_at: string | null; has_rrule: boolean; rrule_str: string | null; tag: string | null; status: EventStatus; event_type: EventType; original_text: string | null; load: number; urgency: number; importance: number; postponed: number; last_modified: string | null; etag: string | null; synced_at: string | null; completed: string | null; out_of_sync: boolean }
+/**
+ * User settings, each field is stored as a row of the `settings` table with
+ * its value as json, the missing ones take the default value
+ */
+export type Settings = { 
+/**
+ * Days a deleted component stays in the trash before it's purged
+ */
+trash_retention_days: number; 
+/**
+ * Calendars whose overdue todos are moved to the current day
+ */
+rollover_calendar_ids: number[]; 
+/**
+ * Tags whose overdue todos are moved to the current day, without the `#`
+ */
+rollover_tags: string[]; 
+/**
+ * Load that fits in a day
+ */
+daily_load_budget: number; 
+/**
+ * Load that fits in each weekday starting on monday, a missing value uses `daily_load_budget`
+ */
+weekday_load_budgets: (number | null)[]; 
+/**
+ * Hour the working day starts, the free time of a day is counted from it
+ */
+day_start_hour: number; 
+/**
+ * Hour the working day ends
+ */
+day_end_hour: number }
+/**
+ * Item of the day with the times it would move to
+ */
+export type ShiftedItem = { vcmp_id: VCmpId; summary: string; starts_at: string; ends_at: string; new_starts_at: string; new_ends_at: string }
+/**
+ * Conditions the slots have to meet
+ */
+export type SlotConstraints = { 
+/**
+ * Start of the working hours, the one of the settings when missing
+ */
+day_start_hour: number | null; 
+/**
+ * End of the working hours, the one of the settings when missing
+ */
+day_end_hour: number | null; 
+/**
+ * Slots in this time of the day are ranked first
+ */
+time_of_day: NamedTime | null; 
+/**
+ * Minutes kept free before and after other items
+ */
+buffer_minutes: number }
+/**
+ * Metrics over the work of a range of days, shaped for charts
+ */
+export type Statistics = { 
+/**
+ * One bucket per day of the range, including the empty ones
+ */
+days: DayBucket[]; created: number; completed: number; 
+/**
+ * Average times the todos completed in the range were postponed
+ */
+average_postponed: number; 
+/**
+ * Sorted by tag, the untagged items first
+ */
+time_per_tag: TagSeries[] }
+/**
+ * Progress of the subtasks of a todo
+ */
+export type SubtaskProgress = { done: number; total: number; 
+/**
+ * Sum of the `load` of the subtasks
+ */
+load: number }
+/**
+ * Minutes spent in `Block` and `Event` items of a tag, one value per day of the range
+ */
+export type TagSeries = { 
+/**
+ * [`None`] for the items without tags
+ */
+tag: string | null; minutes: number[]; total_minutes: number }
+export type Template = { name: string; input: string }
+export type TodosForDay = { todos: ExtendedTodo[]; summary: DaySummary }
+export type TrashItem = { 
+/**
+ * Id to reference the entry in `restore_from_trash`
+ */
+id: number; 
+/**
+ * Id the component had before being deleted
+ */
+vcmp_id: VCmpId; calendar_id: number; summary: string; deleted_at: string }
+export type UnscheduledTodo = { 
+/**
+ * Id to reference the todo in other commands
+ */
+vcmp_id: VCmpId; todo: VTodo; natural_string: string; 
+/**
+ * Progress of the subtasks, if it has any
+ */
+progress: SubtaskProgress | null }
+/**
+ * Identifies a component across the `vevents` and `vtodos` tables,
+ * since both tables have their own id sequence
+ */
+export type VCmpId = { kind: "event"; id: number } | { kind: "todo"; id: number }
+/**
+ * Fields to change, the ones left as [`None`] keep their value
+ */
+export type VCmpPatch = { summary: string | null; 
+/**
+ * An empty description removes it
+ */
+description: string | null; starts_at: string | null; ends_at: string | null; 
+/**
+ * Value of an `RRULE`, like `FREQ=WEEKLY;BYDAY=MO`, empty to stop repeating
+ */
+rrule: string | null; status: EventStatus | null; event_type: EventType | null; 
+/**
+ * Replaces all the tags, an empty list removes them
+ */
+tags: string[] | null; urgency: number | null; importance: number | null; load: number | null; calendar_id: number | null }
+export type VEvent = { id: number; calendar_id: number; uid: string; href: string | null; ical_data: string | null; summary: string; description: string | null; starts_at: string; ends_at: string; has_rrule: boolean; rrule_str: string | null; tag: string | null; status: EventStatus; event_type: EventType; original_text: string | null; load: number; urgency: number; importance: number; postponed: number; last_modified: string | null; etag: string | null; synced_at: string | null; out_of_sync: boolean; 
+/**
+ * Set when deleted locally, the row is kept until the delete reaches the server
+ */
+deleted_at: string | null }
+export type VTodo = { id: number; calendar_id: number; uid: string; href: string | null; ical_data: string | null; summary: string; description: string | null; starts_at: string | null; ends_at: string | null; has_rrule: boolean; rrule_str: string | null; tag: string | null; status: EventStatus; event_type: EventType; original_text: string | null; load: number; urgency: number; importance: number; postponed: number; last_modified: string | null; etag: string | null; synced_at: string | null; completed: string | null; out_of_sync: boolean; 
+/**
+ * Set when deleted locally, the row is kept until the delete reaches the server
+ */
+deleted_at: string | null; 
+/**
+ * Uid of the todo this one is a subtask of, from `RELATED-TO`
+ */
+parent_uid: string | null; 
+/**
+ * When the todo was created, from `CREATED`
+ */
+created_at: string | null }
 
 /** tauri-specta globals **/
 
@@ -1,7 +1,8 @@
 import { addWeeks, constructNow, format, isSameWeek, isThisWeek, isToday, isTomorrow, isYesterday, startOfWeek, subWeeks, type Day } from 'date-fns';
-import { type VEvent, type VTodo } from '../bindings';
+import { type VCmpId, type VEvent, type VTodo } from '../bindings';
 
 export type ScheduledTask = Omit<VEvent | VTodo, "starts_at" | "ends_at"> & {
+  vcmp_id: VCmpId;
   starts_at: Date;
   ends_at: Date;
   natural_recurrence?: string;
@@ -9,6 +10,7 @@ export type ScheduledTask = Omit<VEvent | VTodo, "starts_at" | "ends_at"> & {
 };
 
 export type UnscheduledTask = VTodo & {
+  vcmp_id: VCmpId;
   natural_string: string;
 };
 
@@ -13,16 +13,19 @@ export const load: PageLoad = async ({ url }) => {
     commands.listTodosForDay(formatISO(date)),
     commands.listUnscheduledTodos(false),
   ]);
-  const events = unwrap(eventResult).map((e) => ({
+  const { events: dayEvents, summary } = unwrap(eventResult);
+  const events = dayEvents.map((e) => ({
     ...e.event,
+    vcmp_id: e.vcmp_id,
     starts_at: parseISO(e.starts_at),
     ends_at: parseISO(e.ends_at),
     natural_recurrence: e.natural_recurrence ?? undefined,
     natural_string: e.natural_string,
   }));
 
-  const todos = unwrap(todosResult).map((e) => ({
+  const todos = unwrap(todosResult).todos.map((e) => ({
     ...e.todo,
+    vcmp_id: e.vcmp_id,
     starts_at: parseISO(e.starts_at),
     ends_at: parseISO(e.ends_at),
     natural_recurrence: e.natural_recurrence ?? undefined,
@@ -31,8 +34,9 @@ export const load: PageLoad = async ({ url }) => {
 
   const unscheduledTodos = unwrap(unscheduledResult).map((t) => ({
     ...t.todo,
+    vcmp_id: t.vcmp_id,
     natural_string: t.natural_string,
   }));
 
-  return { events, todos, date, unscheduledTodos };
+  return { events, todos, date, unscheduledTodos, summary };
 };